@@ -0,0 +1,15 @@
+CREATE TABLE refresh_tokens (
+    id          VARCHAR(36)  PRIMARY KEY,
+    user_id     VARCHAR(36)  NOT NULL,
+    family_id   VARCHAR(36)  NOT NULL,
+    token_hash  VARCHAR(64)  NOT NULL UNIQUE,
+    expires_at  TIMESTAMPTZ  NOT NULL,
+    created_at  TIMESTAMPTZ  NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    used_at     TIMESTAMPTZ,
+    revoked_at  TIMESTAMPTZ,
+
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_refresh_tokens_family_id ON refresh_tokens(family_id);
+CREATE INDEX idx_refresh_tokens_user_id ON refresh_tokens(user_id);
@@ -0,0 +1,17 @@
+CREATE TABLE revoked_tokens (
+    jti         VARCHAR(36)  PRIMARY KEY,
+    user_id     VARCHAR(36)  NOT NULL,
+    expires_at  TIMESTAMPTZ  NOT NULL,
+    revoked_at  TIMESTAMPTZ  NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_revoked_tokens_expires_at ON revoked_tokens(expires_at);
+
+-- Tokens issued to a user at or before `revoked_before` are rejected.
+CREATE TABLE user_token_revocations (
+    user_id         VARCHAR(36)  PRIMARY KEY,
+    revoked_before  TIMESTAMPTZ  NOT NULL,
+    revoked_by      VARCHAR(36),
+
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
@@ -0,0 +1,53 @@
+CREATE TABLE roles (
+    name         VARCHAR(64)   PRIMARY KEY,
+    description  VARCHAR(255),
+    created_at   TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE permissions (
+    name         VARCHAR(64)   PRIMARY KEY,
+    description  VARCHAR(255),
+    created_at   TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE role_permissions (
+    role_name        VARCHAR(64)  NOT NULL,
+    permission_name  VARCHAR(64)  NOT NULL,
+
+    PRIMARY KEY (role_name, permission_name),
+    FOREIGN KEY (role_name) REFERENCES roles(name) ON DELETE CASCADE,
+    FOREIGN KEY (permission_name) REFERENCES permissions(name) ON DELETE CASCADE
+);
+
+CREATE TABLE user_roles (
+    user_id     VARCHAR(36)  NOT NULL,
+    role_name   VARCHAR(64)  NOT NULL,
+    created_at  TIMESTAMPTZ  NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (user_id, role_name),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (role_name) REFERENCES roles(name) ON DELETE CASCADE
+);
+
+INSERT INTO roles (name, description)
+VALUES ('admin', 'Full access to all resources'),
+       ('user', 'Regular user');
+
+INSERT INTO permissions (name, description)
+VALUES ('users:read', 'Read users'),
+       ('users:create', 'Create users'),
+       ('users:update', 'Update users'),
+       ('users:delete', 'Delete users'),
+       ('tokens:revoke', 'Revoke all tokens of a user');
+
+INSERT INTO role_permissions (role_name, permission_name)
+SELECT 'admin', name FROM permissions;
+
+INSERT INTO role_permissions (role_name, permission_name)
+VALUES ('user', 'users:read');
+
+INSERT INTO user_roles (user_id, role_name)
+SELECT id, 'admin' FROM users WHERE username = 'admin';
+
+INSERT INTO user_roles (user_id, role_name)
+SELECT id, 'user' FROM users WHERE username <> 'admin';
@@ -0,0 +1,4 @@
+-- Regular users may update their own record; the user policy restricts them to it.
+INSERT INTO role_permissions (role_name, permission_name)
+VALUES ('user', 'users:update')
+ON CONFLICT DO NOTHING;
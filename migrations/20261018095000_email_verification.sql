@@ -0,0 +1,21 @@
+ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMPTZ;
+
+-- The seeded administrator is considered verified so it can still log in
+-- when REQUIRE_EMAIL_VERIFICATION is enabled.
+UPDATE users
+   SET email_verified_at = CURRENT_TIMESTAMP
+ WHERE id = '00000000-0000-0000-0000-000000000001';
+
+CREATE TABLE email_verification_tokens (
+    id          VARCHAR(36)   PRIMARY KEY,
+    user_id     VARCHAR(36)   NOT NULL,
+    email       VARCHAR(128)  NOT NULL,
+    token_hash  VARCHAR(64)   NOT NULL UNIQUE,
+    expires_at  TIMESTAMPTZ   NOT NULL,
+    created_at  TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    used_at     TIMESTAMPTZ,
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_email_verification_tokens_user_id ON email_verification_tokens(user_id);
@@ -0,0 +1,38 @@
+-- TOTP two-factor authentication. A secret is pending until the user confirms it with a
+-- first code; `last_used_step` prevents a code from being used twice.
+CREATE TABLE user_totp (
+    user_id         VARCHAR(36)  PRIMARY KEY,
+    secret          VARCHAR(64)  NOT NULL,
+    enabled_at      TIMESTAMPTZ,
+    last_used_step  BIGINT,
+    created_at      TIMESTAMPTZ  NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE mfa_recovery_codes (
+    id          VARCHAR(36)  PRIMARY KEY,
+    user_id     VARCHAR(36)  NOT NULL,
+    code_hash   VARCHAR(64)  NOT NULL,
+    created_at  TIMESTAMPTZ  NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    used_at     TIMESTAMPTZ,
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_mfa_recovery_codes_user_id ON mfa_recovery_codes(user_id);
+
+-- Issued by a password login of a user with 2FA; exchanged for tokens with a second factor.
+CREATE TABLE mfa_challenges (
+    id               VARCHAR(36)  PRIMARY KEY,
+    user_id          VARCHAR(36)  NOT NULL,
+    token_hash       VARCHAR(64)  NOT NULL UNIQUE,
+    expires_at       TIMESTAMPTZ  NOT NULL,
+    failed_attempts  INTEGER      NOT NULL DEFAULT 0,
+    created_at       TIMESTAMPTZ  NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    used_at          TIMESTAMPTZ,
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_mfa_challenges_user_id ON mfa_challenges(user_id);
@@ -0,0 +1,18 @@
+-- Personal API keys for machine clients. Only the SHA-256 hash of a key is stored;
+-- `prefix` holds its first characters so users can tell their keys apart.
+CREATE TABLE api_keys (
+    id            VARCHAR(36)   PRIMARY KEY,
+    user_id       VARCHAR(36)   NOT NULL,
+    name          VARCHAR(100)  NOT NULL,
+    prefix        VARCHAR(16)   NOT NULL,
+    token_hash    VARCHAR(64)   NOT NULL UNIQUE,
+    scopes        TEXT[]        NOT NULL,
+    expires_at    TIMESTAMPTZ   NOT NULL,
+    created_at    TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at  TIMESTAMPTZ,
+    revoked_at    TIMESTAMPTZ,
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_api_keys_user_id ON api_keys(user_id);
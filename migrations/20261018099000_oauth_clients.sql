@@ -0,0 +1,17 @@
+-- OAuth clients that obtain access tokens with the `client_credentials` grant.
+-- Only the SHA-256 hash of a client secret is stored.
+CREATE TABLE oauth_clients (
+    id           VARCHAR(36)   PRIMARY KEY,
+    name         VARCHAR(100)  NOT NULL,
+    secret_hash  VARCHAR(64)   NOT NULL,
+    scopes       TEXT[]        NOT NULL,
+    created_by   VARCHAR(36),
+    created_at   TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    revoked_at   TIMESTAMPTZ
+);
+
+INSERT INTO permissions (name, description)
+VALUES ('clients:manage', 'Register and revoke OAuth clients');
+
+INSERT INTO role_permissions (role_name, permission_name)
+VALUES ('admin', 'clients:manage');
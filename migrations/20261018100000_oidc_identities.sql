@@ -0,0 +1,31 @@
+-- Identities at external OpenID Connect providers, linked to users.
+-- `subject` is the provider's `sub` claim, which is unique per provider.
+CREATE TABLE user_identities (
+    id             VARCHAR(36)   PRIMARY KEY,
+    user_id        VARCHAR(36)   NOT NULL,
+    provider       VARCHAR(64)   NOT NULL,
+    subject        VARCHAR(255)  NOT NULL,
+    email          VARCHAR(128),
+    created_at     TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_login_at  TIMESTAMPTZ,
+
+    UNIQUE (provider, subject),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_user_identities_user_id ON user_identities(user_id);
+
+-- Logins in progress at a provider, keyed by the hash of the `state` parameter.
+-- `link_user_id` is set when a signed-in user links a new identity instead of logging in.
+CREATE TABLE oidc_login_states (
+    id             VARCHAR(36)   PRIMARY KEY,
+    state_hash     VARCHAR(64)   NOT NULL UNIQUE,
+    provider       VARCHAR(64)   NOT NULL,
+    nonce          VARCHAR(64)   NOT NULL,
+    code_verifier  VARCHAR(128)  NOT NULL,
+    link_user_id   VARCHAR(36),
+    expires_at     TIMESTAMPTZ   NOT NULL,
+    created_at     TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (link_user_id) REFERENCES users(id) ON DELETE CASCADE
+);
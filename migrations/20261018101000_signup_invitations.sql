@@ -0,0 +1,15 @@
+-- Invitations for SIGNUP_MODE=invite-only. Only the SHA-256 hash of the token is stored.
+-- An invitation with an email address can only be used to sign up with that address.
+CREATE TABLE signup_invitations (
+    id          VARCHAR(36)   PRIMARY KEY,
+    token_hash  VARCHAR(64)   NOT NULL UNIQUE,
+    email       VARCHAR(128),
+    created_by  VARCHAR(36),
+    expires_at  TIMESTAMPTZ   NOT NULL,
+    created_at  TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    used_at     TIMESTAMPTZ,
+    used_by     VARCHAR(36),
+
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL,
+    FOREIGN KEY (used_by) REFERENCES users(id) ON DELETE SET NULL
+);
@@ -0,0 +1,16 @@
+-- One row per login, keyed by the refresh token family the login started.
+-- `access_jti` is the most recent access token issued for the session.
+CREATE TABLE sessions (
+    id            VARCHAR(36)   PRIMARY KEY,
+    user_id       VARCHAR(36)   NOT NULL,
+    access_jti    VARCHAR(36)   NOT NULL,
+    ip_address    VARCHAR(45),
+    user_agent    VARCHAR(512),
+    created_at    TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at  TIMESTAMPTZ   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    revoked_at    TIMESTAMPTZ,
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_sessions_user_id ON sessions(user_id);
@@ -0,0 +1,7 @@
+-- Imported password hashes come from other systems (bcrypt, PBKDF2-SHA256, Argon2)
+-- and are replaced by an Argon2id hash at the user's next login.
+INSERT INTO permissions (name, description)
+VALUES ('credentials:import', 'Import password hashes from other systems');
+
+INSERT INTO role_permissions (role_name, permission_name)
+VALUES ('admin', 'credentials:import');
@@ -0,0 +1,7 @@
+-- Impersonation tokens let support staff act as another user for a short time.
+-- They carry the real actor in an `act` claim (RFC 8693).
+INSERT INTO permissions (name, description)
+VALUES ('users:impersonate', 'Obtain short-lived tokens acting as another user');
+
+INSERT INTO role_permissions (role_name, permission_name)
+VALUES ('admin', 'users:impersonate');
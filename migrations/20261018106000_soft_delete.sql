@@ -0,0 +1,26 @@
+-- Deleting a user only deactivates it, keeping the record that `created_by` and
+-- `modified_by` columns refer to. Purging a deleted user removes it for good.
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN deleted_by VARCHAR(36);
+
+-- Purging removes the rows that belong to the user along with it.
+ALTER TABLE user_auth
+    DROP CONSTRAINT user_auth_user_id_fkey,
+    ADD CONSTRAINT user_auth_user_id_fkey
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+
+ALTER TABLE refresh_tokens
+    DROP CONSTRAINT refresh_tokens_user_id_fkey,
+    ADD CONSTRAINT refresh_tokens_user_id_fkey
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+
+ALTER TABLE user_token_revocations
+    DROP CONSTRAINT user_token_revocations_user_id_fkey,
+    ADD CONSTRAINT user_token_revocations_user_id_fkey
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+
+INSERT INTO permissions (name, description)
+VALUES ('users:purge', 'Permanently remove deleted users');
+
+INSERT INTO role_permissions (role_name, permission_name)
+VALUES ('admin', 'users:purge');
@@ -0,0 +1,2 @@
+-- Incremented on every change of a user; exposed as its ETag for optimistic concurrency control.
+ALTER TABLE users ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
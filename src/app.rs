@@ -3,7 +3,7 @@ use axum::{
     error_handling::HandleErrorLayer,
     extract::Request,
     http::{
-        header::{AUTHORIZATION, CONTENT_TYPE},
+        header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH},
         Method, StatusCode,
     },
     middleware::{self, Next},
@@ -28,8 +28,14 @@ use crate::{
         jwt,
     },
     domain::{
-        auth::{user_auth_routes, UserAuthApiDoc},
-        user::{user_routes, UserApiDoc},
+        api_key::{api_key_routes, ApiKeyApiDoc},
+        auth::{
+            user_auth_credential_routes, user_auth_protected_routes, user_auth_routes,
+            well_known_routes, UserAuthApiDoc,
+        },
+        oauth::{oauth_client_routes, oauth_routes, OAuthApiDoc},
+        oidc::{oidc_protected_routes, oidc_routes, OidcApiDoc},
+        user::{user_public_routes, user_routes, UserApiDoc},
     },
 };
 
@@ -52,6 +58,9 @@ fn create_swagger_ui() -> SwaggerUi {
             UserAuthApiDoc::openapi(),
         )
         .url("/api-docs/user/openapi.json", UserApiDoc::openapi())
+        .url("/api-docs/api_key/openapi.json", ApiKeyApiDoc::openapi())
+        .url("/api-docs/oauth/openapi.json", OAuthApiDoc::openapi())
+        .url("/api-docs/oidc/openapi.json", OidcApiDoc::openapi())
 
 }
 
@@ -67,9 +76,16 @@ fn build_cors_layer(allowed_origins: &str) -> CorsLayer {
     };
 
     CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
         .allow_origin(allow_origin)
-        .allow_headers([AUTHORIZATION, CONTENT_TYPE])
+        .allow_headers([AUTHORIZATION, CONTENT_TYPE, IF_MATCH])
+        .expose_headers([ETAG])
 }
 
 pub fn create_router(state: AppState) -> Router {
@@ -81,19 +97,38 @@ pub fn create_router(state: AppState) -> Router {
         .timeout(Duration::from_secs(state.config.request_timeout_secs))
         .layer(cors);
 
-    // /auth routes (login, register, refresh, etc.) — no logging here
+    // /auth routes (login, register, refresh, OIDC login, etc.), the OAuth token endpoint and
+    // public /users routes (email verification) — no logging here
     let auth_router = Router::new()
         .nest("/auth", user_auth_routes())
+        .nest("/auth/oidc", oidc_routes())
+        .nest("/oauth", oauth_routes())
+        .nest("/users", user_public_routes())
         .layer(middleware::from_fn(make_request_response_inspecter(false)));
 
+    // Public discovery routes (JWKS)
+    let well_known_router = Router::new().nest("/.well-known", well_known_routes());
+
     // Protected API routes
     let protected_routes = Router::new()
         .nest("/users", user_routes())
+        .nest("/auth", user_auth_protected_routes())
+        .nest("/auth/oidc", oidc_protected_routes())
         // enforce JWT authentication
-        .route_layer(middleware::from_fn(jwt::jwt_auth))
+        .route_layer(middleware::from_fn_with_state(state.clone(), jwt::jwt_auth))
         // attach inspecter
         .layer(middleware::from_fn(make_request_response_inspecter(true)));
 
+    // Protected routes whose requests or responses carry passwords, secrets or tokens — bodies
+    // are inspected but never logged
+    let credential_routes = Router::new()
+        .nest("/auth", user_auth_credential_routes())
+        .nest("/auth/api-keys", api_key_routes())
+        .nest("/oauth/clients", oauth_client_routes())
+        // enforce JWT authentication
+        .route_layer(middleware::from_fn_with_state(state.clone(), jwt::jwt_auth))
+        .layer(middleware::from_fn(make_request_response_inspecter(false)));
+
     // Create the main router
     // and merge all the routes
     // and add the middleware stack
@@ -101,7 +136,9 @@ pub fn create_router(state: AppState) -> Router {
     Router::new()
         .route("/health", axum::routing::get(health_check))
         .merge(auth_router)
+        .merge(well_known_router)
         .merge(protected_routes)
+        .merge(credential_routes)
         .merge(create_swagger_ui())
         .layer(
             TraceLayer::new_for_http()
@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
-use crate::domain::{auth::AuthService, user::UserServiceImpl};
+use crate::domain::{
+    api_key::ApiKeyService, auth::AuthService, oauth::OAuthService, oidc::OidcService,
+    user::UserServiceImpl,
+};
 
 use super::config::Config;
 
@@ -14,6 +17,12 @@ pub struct AppState {
     pub auth_service: Arc<AuthService>,
     /// Service handling user-related logic.
     pub user_service: Arc<UserServiceImpl>,
+    /// Service handling API keys.
+    pub api_key_service: Arc<ApiKeyService>,
+    /// Service handling OAuth clients and the token endpoint.
+    pub oauth_service: Arc<OAuthService>,
+    /// Service handling logins through external OpenID Connect providers.
+    pub oidc_service: Arc<OidcService>,
 }
 
 impl AppState {
@@ -22,11 +31,17 @@ impl AppState {
         config: Config,
         auth_service: Arc<AuthService>,
         user_service: Arc<UserServiceImpl>,
+        api_key_service: Arc<ApiKeyService>,
+        oauth_service: Arc<OAuthService>,
+        oidc_service: Arc<OidcService>,
     ) -> Self {
         Self {
             config,
             auth_service,
             user_service,
+            api_key_service,
+            oauth_service,
+            oidc_service,
         }
     }
 }
@@ -1,7 +1,13 @@
+use std::sync::Arc;
+
 use sqlx::PgPool;
 
 use crate::common::config::Config;
+use crate::common::mailer::AppMailer;
+use crate::domain::api_key::ApiKeyService;
 use crate::domain::auth::AuthService;
+use crate::domain::oauth::OAuthService;
+use crate::domain::oidc::OidcService;
 use crate::domain::user::UserServiceImpl;
 use crate::common::app_state::AppState;
 
@@ -9,13 +15,25 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 /// Constructs and wires all application services and returns a configured AppState.
 pub fn build_app_state(pool: PgPool, config: Config) -> AppState {
-    let auth_service = AuthService::new(pool.clone());
-    let user_service = UserServiceImpl::new(pool.clone());
+    let mailer = Arc::new(AppMailer::from_config(&config).expect("failed to configure mailer"));
+    let auth_service = AuthService::new(pool.clone(), config.clone(), mailer.clone());
+    let user_service = UserServiceImpl::new(
+        pool.clone(),
+        config.clone(),
+        mailer,
+        auth_service.revocation_cache(),
+    );
+    let api_key_service = ApiKeyService::new(pool.clone(), config.clone());
+    let oauth_service = OAuthService::new(pool.clone(), config.clone());
+    let oidc_service = OidcService::new(pool.clone(), config.clone());
 
     AppState::new(
         config,
         auth_service,
         user_service,
+        api_key_service,
+        oauth_service,
+        oidc_service,
     )
 }
 
@@ -0,0 +1,102 @@
+//! Extractors for the IP address and user agent of the client that sent a request.
+
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header::USER_AGENT, request::Parts, HeaderMap},
+};
+
+use super::app_state::AppState;
+
+/// The client's IP address, if known.
+///
+/// Taken from `X-Forwarded-For` when `TRUST_PROXY_HEADERS` is enabled, otherwise from the peer
+/// address of the connection. Proxies append to the header the client sent, so the entry
+/// `TRUSTED_PROXY_HOPS` places from the right is used: entries further left are client-written.
+/// Only trust proxy headers when the service is reachable exclusively through such proxies,
+/// or clients can spoof it.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if state.config.trust_proxy_headers {
+            let forwarded = forwarded_ip(&parts.headers, state.config.trusted_proxy_hops);
+            if forwarded.is_some() {
+                return Ok(Self(forwarded));
+            }
+        }
+
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        Ok(Self(peer))
+    }
+}
+
+/// Returns the `X-Forwarded-For` entry `hops` places from the right, across all occurrences
+/// of the header, or `None` if there are fewer entries or it is not an IP address.
+fn forwarded_ip(headers: &HeaderMap, hops: usize) -> Option<IpAddr> {
+    let entries: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+    let index = entries.len().checked_sub(hops)?;
+    entries[index].trim().parse().ok()
+}
+
+/// The client's IP address and `User-Agent` header, as recorded for a login session.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+impl FromRequestParts<AppState> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let ClientIp(ip) = ClientIp::from_request_parts(parts, state).await?;
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Ok(Self { ip, user_agent })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-for", value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_forwarded_ip_ignores_client_written_entries() {
+        let spoofed = headers(&["203.0.113.7, 198.51.100.9"]);
+        assert_eq!(forwarded_ip(&spoofed, 1), "198.51.100.9".parse().ok());
+        assert_eq!(forwarded_ip(&spoofed, 2), "203.0.113.7".parse().ok());
+        assert_eq!(forwarded_ip(&spoofed, 3), None);
+
+        let repeated = headers(&["203.0.113.7", "198.51.100.9"]);
+        assert_eq!(forwarded_ip(&repeated, 1), "198.51.100.9".parse().ok());
+        assert_eq!(forwarded_ip(&headers(&["not-an-ip"]), 1), None);
+        assert_eq!(forwarded_ip(&HeaderMap::new(), 1), None);
+    }
+}
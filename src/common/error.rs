@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, StatusCode},
     response::{IntoResponse, Response},
     BoxError,
 };
@@ -31,6 +31,17 @@ pub enum AppError {
     #[error("Forbidden Request")]
     Forbidden,
 
+    /// Used when a request clashes with existing data, e.g. a username that is already taken.
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// Used when the `If-Match` header does not match the current version of a resource.
+    #[error("Precondition failed")]
+    PreconditionFailed,
+    /// Used when `If-Match` is required but missing.
+    #[error("Precondition required")]
+    PreconditionRequired,
+
     /// Used for authentication-related errors
     #[error("Wrong credentials")]
     WrongCredentials,
@@ -38,14 +49,26 @@ pub enum AppError {
     MissingCredentials,
     #[error("Invalid token")]
     InvalidToken,
+    /// Used for access tokens that were valid but are past their expiry.
+    #[error("Token expired")]
+    TokenExpired,
     #[error("Token creation error")]
     TokenCreation,
+    #[error("Email address not verified")]
+    EmailNotVerified,
+    /// Used when a client exceeds a rate limit; carries the seconds until it may retry.
+    #[error("Too many requests")]
+    TooManyRequests(u64),
 }
 
 /// Converts the AppError enum into an HTTP response.
 /// It maps the error to an appropriate HTTP status code and constructs a JSON response body.
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let retry_after = match self {
+            AppError::TooManyRequests(secs) => Some(secs),
+            _ => None,
+        };
         let (status, message) = match self {
             AppError::ValidationError(msg) => (StatusCode::BAD_REQUEST, format!("Validation error: {msg}")),
             AppError::DatabaseError(ref db_err) => {
@@ -56,10 +79,16 @@ impl IntoResponse for AppError {
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, format!("Not found: {msg}")),
             AppError::InternalError => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string()),
             AppError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden request".to_string()),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, format!("Conflict: {msg}")),
+            AppError::PreconditionFailed => (StatusCode::PRECONDITION_FAILED, "Precondition failed".to_string()),
+            AppError::PreconditionRequired => (StatusCode::PRECONDITION_REQUIRED, "If-Match header required".to_string()),
             AppError::WrongCredentials => (StatusCode::UNAUTHORIZED, "Wrong credentials".to_string()),
             AppError::MissingCredentials => (StatusCode::BAD_REQUEST, "Missing credentials".to_string()),
             AppError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token".to_string()),
+            AppError::TokenExpired => (StatusCode::UNAUTHORIZED, "Token expired".to_string()),
             AppError::TokenCreation => (StatusCode::INTERNAL_SERVER_ERROR, "Token creation error".to_string()),
+            AppError::EmailNotVerified => (StatusCode::FORBIDDEN, "Email address not verified".to_string()),
+            AppError::TooManyRequests(_) => (StatusCode::TOO_MANY_REQUESTS, "Too many requests".to_string()),
         };
         let body = axum::Json(ApiResponse::<()> {
             status: status.as_u16(),
@@ -67,10 +96,19 @@ impl IntoResponse for AppError {
             data: None,
         });
 
-        (status, body).into_response()
+        match retry_after {
+            Some(secs) => (status, [(RETRY_AFTER, secs.to_string())], body).into_response(),
+            None => (status, body).into_response(),
+        }
     }
 }
 
+/// Returns whether a database error is a violation of a unique constraint.
+pub fn is_unique_violation(err: &SqlxError) -> bool {
+    err.as_database_error()
+        .is_some_and(|db_err| db_err.is_unique_violation())
+}
+
 /// handle_error is a function that middlewares the error handling in the application.
 /// It takes a BoxError as input and returns an HTTP response.
 /// It maps the error to an appropriate HTTP status code and constructs a JSON response body.
@@ -0,0 +1,113 @@
+//! Optimistic concurrency control with entity tags.
+//!
+//! Resources with a version expose it as a strong `ETag`. Clients send it back in `If-Match`
+//! when changing the resource, and the change is refused with `412 Precondition Failed` if
+//! someone else changed the resource in the meantime.
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header::IF_MATCH, request::Parts},
+};
+
+use super::{app_state::AppState, error::AppError};
+
+/// Returns the entity tag of the given version of a resource.
+pub fn etag(version: i64) -> String {
+    format!("\"{version}\"")
+}
+
+/// The `If-Match` precondition of a request.
+///
+/// Rejects requests without the header with `AppError::PreconditionRequired` when
+/// `REQUIRE_IF_MATCH` is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfMatch {
+    /// No `If-Match` header was sent; the change applies unconditionally.
+    Absent,
+    /// `If-Match: *`; the change applies if the resource exists.
+    Any,
+    /// The strong entity tags the client accepts. Weak tags never match.
+    Tags(Vec<String>),
+}
+
+impl IfMatch {
+    /// Parses the values of the `If-Match` headers of a request.
+    pub fn parse<'a>(values: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut values = values.into_iter().peekable();
+        if values.peek().is_none() {
+            return Self::Absent;
+        }
+
+        let mut tags = Vec::new();
+        for tag in values.flat_map(|value| value.split(',')).map(str::trim) {
+            if tag == "*" {
+                return Self::Any;
+            }
+            if tag.len() >= 2 && tag.starts_with('"') && tag.ends_with('"') {
+                tags.push(tag.to_string());
+            }
+        }
+        Self::Tags(tags)
+    }
+
+    /// Checks the precondition against the current version of a resource.
+    ///
+    /// Returns the version the change must still find when it is written, so a concurrent
+    /// change in between is detected as well, or `None` if the change is unconditional.
+    pub fn expected_version(&self, current: i64) -> Result<Option<i64>, AppError> {
+        match self {
+            Self::Absent | Self::Any => Ok(None),
+            Self::Tags(tags) if tags.contains(&etag(current)) => Ok(Some(current)),
+            Self::Tags(_) => Err(AppError::PreconditionFailed),
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for IfMatch {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let if_match = Self::parse(
+            parts
+                .headers
+                .get_all(IF_MATCH)
+                .iter()
+                .map(|value| value.to_str().unwrap_or_default()),
+        );
+        if if_match == Self::Absent && state.config.require_if_match {
+            return Err(AppError::PreconditionRequired);
+        }
+        Ok(if_match)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_if_match() {
+        assert_eq!(IfMatch::parse([]), IfMatch::Absent);
+        assert_eq!(IfMatch::parse(["*"]), IfMatch::Any);
+        assert_eq!(
+            IfMatch::parse(["\"1\", W/\"2\"", " \"3\" "]),
+            IfMatch::Tags(vec!["\"1\"".into(), "\"3\"".into()])
+        );
+        assert_eq!(IfMatch::parse(["garbage"]), IfMatch::Tags(vec![]));
+    }
+
+    #[test]
+    fn test_expected_version() {
+        assert_eq!(IfMatch::Absent.expected_version(4).unwrap(), None);
+        assert_eq!(IfMatch::Any.expected_version(4).unwrap(), None);
+        assert_eq!(IfMatch::parse(["\"3\", \"4\""]).expected_version(4).unwrap(), Some(4));
+        assert!(matches!(
+            IfMatch::parse(["\"3\""]).expected_version(4),
+            Err(AppError::PreconditionFailed)
+        ));
+        assert!(matches!(
+            IfMatch::parse(["W/\"4\""]).expected_version(4),
+            Err(AppError::PreconditionFailed)
+        ));
+    }
+}
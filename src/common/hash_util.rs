@@ -1,17 +1,96 @@
+//! Password hashing with Argon2id.
+//!
+//! Hashes imported from other systems are verified too: bcrypt (`$2a$`, `$2b$`, `$2x$`, `$2y$`),
+//! PBKDF2-SHA256 as a PHC string (`$pbkdf2-sha256$i=...`) or in passlib's modular crypt format
+//! (`$pbkdf2-sha256$<rounds>$...`), and any Argon2 variant. `check_password` reports them as
+//! outdated, so they are replaced by an Argon2id hash when their user logs in.
+
+use std::{
+    borrow::Cow,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
+use pbkdf2::Pbkdf2;
 
-/// Hash the provided password using Argon2.
-pub fn hash_password(password: &str) -> Result<String, argon2::Error> {
-    let salt = SaltString::generate(&mut OsRng);
+/// Hash of a random password, verified against when the user does not exist so that
+/// the response time does not reveal whether an account exists. It is created with the
+/// parameters of the first call, which are the configured ones.
+static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+/// Largest memory cost `calibrate` tries, in KiB (1 GiB).
+const CALIBRATION_MAX_MEMORY_KIB: u32 = 1024 * 1024;
+
+/// Argon2id cost parameters of new password hashes.
+/// The defaults are those of the `argon2` crate: 19 MiB of memory, 2 iterations, 1 lane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HashParams {
+    /// Memory cost in KiB.
+    pub memory_kib: u32,
+    /// Number of passes over the memory.
+    pub iterations: u32,
+    /// Degree of parallelism (lanes).
+    pub parallelism: u32,
+}
+
+impl Default for HashParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl HashParams {
+    /// Builds the hasher, failing if Argon2 rejects the parameters.
+    fn argon2(&self) -> Result<Argon2<'static>, argon2::Error> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+
+    /// Returns whether Argon2 accepts the parameters,
+    /// e.g. memory must be at least 8 KiB per lane.
+    pub fn is_valid(&self) -> bool {
+        self.argon2().is_ok()
+    }
 
-    // Argon2 with default params (Argon2id v19)
-    let argon2 = Argon2::default();
+    /// Returns whether a stored hash was made with Argon2id and these parameters.
+    fn produced(&self, hash: &PasswordHash) -> bool {
+        hash.algorithm == Algorithm::Argon2id.ident()
+            && hash.version == Some(Version::V0x13.into())
+            && Params::try_from(hash).is_ok_and(|params| {
+                params.m_cost() == self.memory_kib
+                    && params.t_cost() == self.iterations
+                    && params.p_cost() == self.parallelism
+            })
+    }
+}
+
+/// Outcome of checking a password with `check_password`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PasswordCheck {
+    /// The password is wrong, or the stored hash is unreadable.
+    Mismatch,
+    /// The password is right and the hash uses the current parameters.
+    Match,
+    /// The password is right, but the hash uses another algorithm or other parameters
+    /// and should be replaced by a new hash of the password.
+    Outdated,
+}
+
+/// Hash the provided password using Argon2id with the given parameters.
+pub fn hash_password(password: &str, params: &HashParams) -> Result<String, argon2::Error> {
+    let salt = SaltString::generate(&mut OsRng);
 
     // Hash password to PHC string ($argon2id$v=19$...)
-    let hash_password = argon2
+    let hash_password = params
+        .argon2()?
         .hash_password(password.as_bytes(), &salt)
         .map_err(|e| {
             tracing::error!("Error hashing password: {}", e);
@@ -21,28 +100,166 @@ pub fn hash_password(password: &str) -> Result<String, argon2::Error> {
     Ok(hash_password.to_string())
 }
 
-/// Verify that a password matches the provided çå.
+/// Verify that a password matches the provided hash.
+/// The hash is verified with the algorithm and parameters recorded in it.
 pub fn verify_password(password_hash: &str, password: &str) -> bool {
-    let parsed_hash = match PasswordHash::new(password_hash) {
+    if is_bcrypt(password_hash) {
+        return bcrypt::verify(password, password_hash).unwrap_or_else(|e| {
+            tracing::error!("Error verifying bcrypt hash: {}", e);
+            false
+        });
+    }
+
+    let password_hash = passlib_to_phc(password_hash);
+    let parsed_hash = match PasswordHash::new(&password_hash) {
         Ok(hash) => hash,
         Err(e) => {
             tracing::error!("Error hashing password: {}", e);
             return false;
         }
     };
-    Argon2::default()
-        .verify_password(password.as_bytes(), &parsed_hash)
+    let verifiers: [&dyn PasswordVerifier; 2] = [&Argon2::default(), &Pbkdf2];
+    parsed_hash
+        .verify_password(&verifiers, password.as_bytes())
         .is_ok()
 }
 
+/// Returns whether `verify_password` understands a hash, i.e. whether it can be imported.
+pub fn is_supported_hash(password_hash: &str) -> bool {
+    if is_bcrypt(password_hash) {
+        return password_hash.parse::<bcrypt::HashParts>().is_ok();
+    }
+
+    let password_hash = passlib_to_phc(password_hash);
+    PasswordHash::new(&password_hash).is_ok_and(|hash| {
+        [
+            Algorithm::Argon2id.ident(),
+            Algorithm::Argon2i.ident(),
+            Algorithm::Argon2d.ident(),
+            pbkdf2::Algorithm::Pbkdf2Sha256.ident(),
+        ]
+        .contains(&hash.algorithm)
+            && hash.salt.is_some()
+            && hash.hash.is_some()
+    })
+}
+
+/// Returns whether a hash is in bcrypt's modular crypt format.
+fn is_bcrypt(password_hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| password_hash.starts_with(prefix))
+}
+
+/// Rewrites a PBKDF2-SHA256 hash in passlib's format, `$pbkdf2-sha256$<rounds>$<salt>$<hash>`
+/// with `.` in place of `+` in the Base64 parts, as a PHC string. Other hashes are returned as is.
+fn passlib_to_phc(password_hash: &str) -> Cow<'_, str> {
+    let parts: Vec<&str> = password_hash.split('$').collect();
+    match parts.as_slice() {
+        ["", "pbkdf2-sha256", rounds, salt, hash] if rounds.parse::<u32>().is_ok() => {
+            Cow::Owned(format!(
+                "$pbkdf2-sha256$i={rounds}${}${}",
+                salt.replace('.', "+"),
+                hash.replace('.', "+")
+            ))
+        }
+        _ => Cow::Borrowed(password_hash),
+    }
+}
+
+/// Like `verify_password`, but also reports whether a matching hash is outdated,
+/// i.e. was not made with Argon2id and `params`.
+pub fn check_password(password_hash: &str, password: &str, params: &HashParams) -> PasswordCheck {
+    if !verify_password(password_hash, password) {
+        return PasswordCheck::Mismatch;
+    }
+    match PasswordHash::new(password_hash) {
+        Ok(hash) if params.produced(&hash) => PasswordCheck::Match,
+        _ => PasswordCheck::Outdated,
+    }
+}
+
+/// Spends the same effort as `verify_password` without a stored hash. Always returns `false`.
+pub fn verify_dummy_password(password: &str, params: &HashParams) -> bool {
+    let dummy_hash = DUMMY_HASH.get_or_init(|| {
+        hash_password(&uuid::Uuid::new_v4().to_string(), params)
+            .expect("failed to hash dummy password")
+    });
+    verify_password(dummy_hash, password);
+    false
+}
+
+/// Picks the most expensive parameters whose hashing takes at most `target` on this machine.
+///
+/// Starting from the defaults with `parallelism` lanes, the memory cost is doubled while the
+/// target allows (up to 1 GiB), then iterations are added. If even the starting parameters
+/// exceed the target, they are returned anyway. Returns the parameters and the time one hash
+/// took with them.
+pub fn calibrate(
+    target: Duration,
+    parallelism: u32,
+) -> Result<(HashParams, Duration), argon2::Error> {
+    let mut params = HashParams {
+        parallelism,
+        ..HashParams::default()
+    };
+    params.memory_kib = params.memory_kib.max(8 * parallelism);
+    let mut elapsed = time_hash(&params)?;
+
+    while elapsed < target && params.memory_kib <= CALIBRATION_MAX_MEMORY_KIB / 2 {
+        let next = HashParams {
+            memory_kib: params.memory_kib * 2,
+            ..params
+        };
+        let next_elapsed = time_hash(&next)?;
+        if next_elapsed > target {
+            return Ok((params, elapsed));
+        }
+        (params, elapsed) = (next, next_elapsed);
+    }
+
+    while elapsed < target {
+        let next = HashParams {
+            iterations: params.iterations + 1,
+            ..params
+        };
+        let next_elapsed = time_hash(&next)?;
+        if next_elapsed > target {
+            break;
+        }
+        (params, elapsed) = (next, next_elapsed);
+    }
+
+    Ok((params, elapsed))
+}
+
+/// Measures how long hashing a password takes, taking the faster of two runs.
+fn time_hash(params: &HashParams) -> Result<Duration, argon2::Error> {
+    let mut fastest = Duration::MAX;
+    for _ in 0..2 {
+        let start = Instant::now();
+        hash_password("calibration", params)?;
+        fastest = fastest.min(start.elapsed());
+    }
+    Ok(fastest)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Cheap parameters, so tests do not spend time hashing.
+    const FAST: HashParams = HashParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
     #[test]
     fn test_password_hash_and_verify() {
         let password = "super_secret_password";
-        let hash = hash_password(password).expect("Failed to hash password");
+        let hash =
+            hash_password(password, &HashParams::default()).expect("Failed to hash password");
 
         // Verifying that the hashed password matches the original one
         assert!(verify_password(&hash, password));
@@ -50,10 +267,92 @@ mod tests {
         assert!(!verify_password(&hash, "wrong_password"));
     }
 
+    #[test]
+    fn test_dummy_password_never_matches() {
+        assert!(!verify_dummy_password("", &FAST));
+        assert!(!verify_dummy_password("test", &FAST));
+    }
+
     #[test]
     fn test_argon2_jvm_verify() {
         let password = "mySecretPassword";
         let hash = "$argon2i$v=19$m=65536,t=2,p=1$vNVL5PZ1hRwgLUlGmCQVTA$fg1d0/f8pdtMnzQTeh2YE6R0E8vfqMOQOs5k6Y22Qi0";
         assert!(verify_password(hash, password));
+        // argon2i is not the algorithm new hashes are made with.
+        assert_eq!(
+            check_password(hash, password, &HashParams::default()),
+            PasswordCheck::Outdated
+        );
+    }
+
+    #[test]
+    fn test_bcrypt_verify() {
+        // PHP's password_hash() and Spring's BCryptPasswordEncoder
+        let php = "$2y$10$.vGA1O9wmRjrwAVXD98HNOgsNpDczlqm3Jq7KnEd1rVAGv3Fykk1a";
+        let spring = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+
+        assert!(verify_password(php, "rasmuslerdorf"));
+        assert!(!verify_password(php, "wrong_password"));
+        assert!(verify_password(spring, "U*U"));
+        assert_eq!(
+            check_password(php, "rasmuslerdorf", &HashParams::default()),
+            PasswordCheck::Outdated
+        );
+    }
+
+    #[test]
+    fn test_pbkdf2_sha256_verify() {
+        let phc = "$pbkdf2-sha256$i=1000,l=32$mhNzYWx0eXNhbHT+AQID$UpBWdFZtTEzL3T++PuuNa99Kfjxkm9C8MhMOzQdeYnA";
+        let passlib = "$pbkdf2-sha256$1000$mhNzYWx0eXNhbHT.AQID$UpBWdFZtTEzL3T..PuuNa99Kfjxkm9C8MhMOzQdeYnA";
+
+        for hash in [phc, passlib] {
+            assert!(verify_password(hash, "mySecretPassword"));
+            assert!(!verify_password(hash, "wrong_password"));
+            assert_eq!(
+                check_password(hash, "mySecretPassword", &HashParams::default()),
+                PasswordCheck::Outdated
+            );
+        }
+    }
+
+    #[test]
+    fn test_supported_hashes() {
+        let argon2 = hash_password("password", &FAST).expect("Failed to hash password");
+
+        assert!(is_supported_hash(&argon2));
+        assert!(is_supported_hash("$2y$10$.vGA1O9wmRjrwAVXD98HNOgsNpDczlqm3Jq7KnEd1rVAGv3Fykk1a"));
+        assert!(is_supported_hash(
+            "$pbkdf2-sha256$1000$mhNzYWx0eXNhbHT.AQID$UpBWdFZtTEzL3T..PuuNa99Kfjxkm9C8MhMOzQdeYnA"
+        ));
+        assert!(!is_supported_hash("$2y$10$tooshort"));
+        assert!(!is_supported_hash("$pbkdf2-sha512$i=1000$c2FsdA$aGFzaA"));
+        assert!(!is_supported_hash("5f4dcc3b5aa765d61d8327deb882cf99"));
+    }
+
+    #[test]
+    fn test_check_password_reports_outdated_params() {
+        let hash = hash_password("password", &FAST).expect("Failed to hash password");
+        let stronger = HashParams {
+            iterations: 2,
+            ..FAST
+        };
+
+        assert_eq!(check_password(&hash, "password", &FAST), PasswordCheck::Match);
+        assert_eq!(check_password(&hash, "password", &stronger), PasswordCheck::Outdated);
+        assert_eq!(check_password(&hash, "wrong", &FAST), PasswordCheck::Mismatch);
+        assert_eq!(check_password("not a hash", "password", &FAST), PasswordCheck::Mismatch);
+    }
+
+    #[test]
+    fn test_invalid_params_are_rejected() {
+        assert!(HashParams::default().is_valid());
+        // Argon2 needs at least 8 KiB of memory per lane.
+        let invalid = HashParams {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 2,
+        };
+        assert!(!invalid.is_valid());
+        assert!(hash_password("password", &invalid).is_err());
     }
 }
@@ -1,53 +1,76 @@
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{
+    errors::{Error as JwtError, ErrorKind},
+    Validation,
+};
 use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 use std::sync::LazyLock;
-use std::{env, fmt::Display};
 use utoipa::ToSchema;
+use uuid::Uuid;
 
-use super::error::AppError;
+use super::{app_state::AppState, error::AppError, jwt_keys::KeySet};
+use crate::domain::{api_key::ApiKeyServiceTrait, auth::AuthServiceTrait};
 
-/// JWT_SECRET_KEY is the environment variable that holds the secret key for JWT encoding and decoding.
-/// It is loaded from the environment variables using the dotenv crate.
-/// The secret key is used to sign the JWT tokens and should be kept secret.
-pub static KEYS: LazyLock<Keys> = LazyLock::new(|| {
+/// KEYS holds the keys used to sign and verify JWT tokens.
+/// It is loaded from the environment variables using the dotenv crate; see
+/// `KeySet::from_env` for the supported variables. By default tokens are signed with HS256
+/// using `JWT_SECRET_KEY`, which should be kept secret.
+pub static KEYS: LazyLock<KeySet> = LazyLock::new(|| {
     dotenvy::dotenv().ok();
 
-    let secret = env::var("JWT_SECRET_KEY").expect("JWT_SECRET_KEY must be set");
-    Keys::new(secret.as_bytes())
+    KeySet::from_env().expect("failed to load JWT keys")
 });
 
-/// Keys is a struct that holds the encoding and decoding keys for JWT.
-pub struct Keys {
-    pub encoding: EncodingKey,
-    pub decoding: DecodingKey,
-}
-
-/// The Keys struct is used to create the encoding and decoding keys for JWT.
-impl Keys {
-    fn new(secret: &[u8]) -> Self {
-        Self {
-            encoding: EncodingKey::from_secret(secret),
-            decoding: DecodingKey::from_secret(secret),
-        }
-    }
-}
-
 /// Claims is a struct that represents the claims in the JWT token.
-/// It contains the subject (user ID), expiration time, and issued at time.
-/// The `sub` field is the user ID, `exp` is the expiration time, and `iat` is the issued at time.
+/// It contains the subject (user ID), expiration time, issued at time, token ID and authorities.
+/// The `sub` field is the user ID, `exp` is the expiration time, `iat` is the issued at time
+/// and `jti` uniquely identifies the token so it can be revoked before it expires.
+/// `iss`, `aud` and `nbf` are set from the `JwtSettings` when the token is signed.
+/// `roles` and `permissions` are resolved from the database when the token is issued.
+/// Tokens issued to an OAuth client carry its `client_id`, which is also their `sub`.
+/// Access tokens issued at login carry the `sid` of their session, so revoking the session
+/// revokes every access token issued for it.
+/// Impersonation tokens carry the user acting as the subject in `act` (RFC 8693).
+/// `iat_ms` repeats the issue time in milliseconds, so a token issued right after a
+/// revocation cut-off is told apart from one issued before it within the same second.
 /// The `Claims` struct is used to encode and decode the JWT tokens.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
     pub iat: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat_ms: Option<i64>,
+    pub jti: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<usize>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<ActClaim>,
+}
+
+/// The `act` (actor) claim of RFC 8693: the party acting on behalf of the subject.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActClaim {
+    pub sub: String,
 }
 
 /// The Claims struct implements the `Display` trait for easy printing.
@@ -58,20 +81,76 @@ impl Display for Claims {
     }
 }
 
-/// The Default trait is implemented for the Claims struct.
-/// It sets the default values for the claims.
-impl Default for Claims {
-    fn default() -> Self {
+impl Claims {
+    /// Creates claims for the given subject that expire `ttl` from now.
+    pub fn new(sub: impl Into<String>, ttl: Duration) -> Self {
         let now = Utc::now();
-        let expire: Duration = Duration::hours(24);
-        let exp: usize = (now + expire).timestamp() as usize;
-        let iat: usize = now.timestamp() as usize;
         Claims {
-            sub: String::new(),
-            exp,
-            iat,
+            sub: sub.into(),
+            exp: (now + ttl).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            iat_ms: Some(now.timestamp_millis()),
+            jti: Uuid::new_v4().to_string(),
+            iss: None,
+            aud: None,
+            nbf: None,
+            roles: Vec::new(),
+            permissions: Vec::new(),
+            client_id: None,
+            sid: None,
+            act: None,
         }
     }
+
+    /// Sets the roles and permissions granted by the token.
+    pub fn with_authorities(mut self, roles: Vec<String>, permissions: Vec<String>) -> Self {
+        self.roles = roles;
+        self.permissions = permissions;
+        self
+    }
+
+    /// Marks the token as issued to the given OAuth client.
+    pub fn with_client_id(mut self, client_id: String) -> Self {
+        self.client_id = Some(client_id);
+        self
+    }
+
+    /// Ties the token to the given login session.
+    pub fn with_session_id(mut self, sid: String) -> Self {
+        self.sid = Some(sid);
+        self
+    }
+
+    /// Marks the token as issued to the given user acting as the subject.
+    pub fn with_actor(mut self, actor_id: String) -> Self {
+        self.act = Some(ActClaim { sub: actor_id });
+        self
+    }
+
+    /// Returns the user accountable for requests made with the token: the actor of an
+    /// impersonation token, or the subject otherwise.
+    pub fn actor_id(&self) -> &str {
+        self.act.as_ref().map_or(&self.sub, |act| &act.sub)
+    }
+
+    /// Returns whether the token was issued before a revocation cut-off (Unix milliseconds).
+    ///
+    /// Tokens without `iat_ms` are taken to be issued at the start of their `iat` second, so
+    /// one from the same second as the cut-off counts as revoked.
+    pub fn issued_before(&self, cutoff_ms: i64) -> bool {
+        let issued_at_ms = self.iat_ms.unwrap_or(self.iat as i64 * 1000);
+        issued_at_ms < cutoff_ms
+    }
+
+    /// Returns whether the token grants the given role.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// Returns whether the token grants the given permission.
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
 }
 
 /// AuthBody is a struct that represents the authentication body.
@@ -79,17 +158,30 @@ impl Default for Claims {
 pub struct AuthBody {
     pub access_token: String,
     pub token_type: String,
+    /// Lifetime of the access token in seconds.
+    pub expires_in: i64,
+    /// Opaque token that can be exchanged for a new access token at `/auth/refresh`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
 }
 
 /// The AuthBody struct is used to create a new instance of the authentication body.
-/// It takes an access token as a parameter and sets the token type to "Bearer".
+/// It takes an access token and its lifetime as parameters and sets the token type to "Bearer".
 impl AuthBody {
-    pub fn new(access_token: String) -> Self {
+    pub fn new(access_token: String, expires_in: i64) -> Self {
         Self {
             access_token,
             token_type: "Bearer".to_string(),
+            expires_in,
+            refresh_token: None,
         }
     }
+
+    /// Attaches a refresh token to the authentication body.
+    pub fn with_refresh_token(mut self, refresh_token: String) -> Self {
+        self.refresh_token = Some(refresh_token);
+        self
+    }
 }
 
 /// AuthPayload is a struct that represents the authentication payload.
@@ -100,40 +192,230 @@ pub struct AuthPayload {
     pub client_secret: String,
 }
 
+/// Issuer and audience of the access tokens this service signs, and the clock skew tolerated
+/// when verifying them. Tokens from another issuer or for another audience are rejected, even
+/// if they are signed with a key we accept.
+#[derive(Clone, Debug)]
+pub struct JwtSettings {
+    pub issuer: String,
+    pub audience: String,
+    /// Seconds a token is still accepted after `exp`, or before `nbf`.
+    pub leeway_secs: u64,
+}
+
+impl JwtSettings {
+    /// Returns the claims with the configured issuer and audience, valid from their issue time.
+    fn stamp(&self, claims: &Claims) -> Claims {
+        Claims {
+            iss: Some(self.issuer.clone()),
+            aud: Some(self.audience.clone()),
+            nbf: Some(claims.iat),
+            ..claims.clone()
+        }
+    }
+
+    /// Returns the rules tokens signed by `keys` are verified against: the configured issuer
+    /// and audience, and unexpired `exp` and reached `nbf` claims, with the configured leeway.
+    pub fn validation(&self, keys: &KeySet) -> Validation {
+        let mut validation = keys.validation();
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud", "sub"]);
+        validation.validate_nbf = true;
+        validation.leeway = self.leeway_secs;
+        validation
+    }
+}
+
 /// make_jwt_token is a function that creates a JWT token.
-/// It takes a user ID as a parameter and returns a Result with the JWT token or an error.
-pub fn make_jwt_token(user_id: &str) -> Result<String, AppError> {
-    let claims = Claims {
-        sub: user_id.to_string(),
-        ..Default::default()
-    };
-    encode(&Header::default(), &claims, &KEYS.encoding).map_err(|_| AppError::TokenCreation)
+/// It takes the claims to sign and the settings providing `iss` and `aud` as parameters
+/// and returns a Result with the JWT token or an error.
+pub fn make_jwt_token(claims: &Claims, settings: &JwtSettings) -> Result<String, AppError> {
+    KEYS.encode(&settings.stamp(claims)).map_err(|_| AppError::TokenCreation)
 }
 
-/// Middleware to validate JWT tokens.
-/// If the token is valid, the request proceeds; otherwise, a 401 Unauthorized is returned.
-pub async fn jwt_auth<B>(mut req: Request<B>, next: Next) -> Result<Response, Response>
-where
-    B: Send + Into<axum::body::Body>,
-{
-    // Try to extract and trim the token in one go.
-    let token = req
+/// How the caller of a protected route authenticated.
+/// `jwt_auth` inserts it into the request extensions next to the `Claims`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    /// `Authorization: Bearer <access token>`
+    Bearer,
+    /// `Authorization: ApiKey <key>`
+    ApiKey,
+    /// `Authorization: Bearer <access token>` issued to an OAuth client by `/oauth/token`
+    Client,
+    /// `Authorization: Bearer <access token>` issued to a user acting as another user
+    Impersonation,
+}
+
+/// decode_jwt_token verifies a JWT token against the configured keys and settings
+/// and returns its claims.
+pub fn decode_jwt_token(token: &str, settings: &JwtSettings) -> Result<Claims, AppError> {
+    KEYS.decode::<Claims>(token, &settings.validation(&KEYS))
+        .map(|token_data| token_data.claims)
+        .map_err(decode_error)
+}
+
+/// Maps a failed verification to `TokenExpired` for tokens that are merely past their expiry,
+/// and to `InvalidToken` for everything else.
+fn decode_error(err: JwtError) -> AppError {
+    match err.kind() {
+        ErrorKind::ExpiredSignature => {
+            tracing::debug!("Expired token presented");
+            AppError::TokenExpired
+        }
+        _ => {
+            tracing::error!("Error decoding token: {:?}", err);
+            AppError::InvalidToken
+        }
+    }
+}
+
+/// Middleware to validate JWT tokens and API keys.
+/// Accepts `Authorization: Bearer <access token>` or `Authorization: ApiKey <key>`; an API key
+/// is resolved to claims equivalent to an access token of its user, limited to its scopes.
+/// If the credential is valid and has not been revoked, the request proceeds;
+/// otherwise, a 401 Unauthorized is returned.
+pub async fn jwt_auth(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    // Try to extract the scheme and the trimmed credential in one go.
+    let (auth_method, credential) = req
         .headers()
         .get("Authorization")
         .and_then(|v| v.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "))
-        .map(|t| t.trim())
-        .filter(|t| !t.is_empty())
+        .and_then(|header| {
+            header
+                .strip_prefix("Bearer ")
+                .map(|t| (AuthMethod::Bearer, t))
+                .or_else(|| header.strip_prefix("ApiKey ").map(|t| (AuthMethod::ApiKey, t)))
+        })
+        .map(|(auth_method, t)| (auth_method, t.trim()))
+        .filter(|(_, t)| !t.is_empty())
         .ok_or_else(|| AppError::InvalidToken.into_response())?;
 
-    // Validate and decode the token.
-    let token_data =
-        decode::<Claims>(token, &KEYS.decoding, &Validation::default()).map_err(|err| {
-            tracing::error!("Error decoding token: {:?}", err);
-            AppError::InvalidToken.into_response()
-        })?;
+    // Validate and decode the token, or look up the API key.
+    let claims = match auth_method {
+        AuthMethod::Bearer | AuthMethod::Client | AuthMethod::Impersonation => {
+            decode_jwt_token(credential, &state.config.jwt_settings)
+        }
+        AuthMethod::ApiKey => state.api_key_service.authenticate(credential).await,
+    }
+    .map_err(IntoResponse::into_response)?;
+
+    // Reject tokens that were revoked before their expiry.
+    let revoked = state
+        .auth_service
+        .is_token_revoked(&claims)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    if revoked {
+        return Err(AppError::InvalidToken.into_response());
+    }
+
+    // Access tokens of OAuth clients have no user behind them, and impersonation tokens
+    // have a different one.
+    let auth_method = match (&claims.client_id, &claims.act) {
+        (Some(_), _) => AuthMethod::Client,
+        (None, Some(_)) => AuthMethod::Impersonation,
+        (None, None) => auth_method,
+    };
+
+    if let Some(act) = &claims.act {
+        tracing::info!(
+            actor = %act.sub,
+            subject = %claims.sub,
+            method = %req.method(),
+            path = %req.uri().path(),
+            "Request made while impersonating"
+        );
+    }
 
     // Insert the decoded claims into the request extensions.
-    req.extensions_mut().insert(token_data.claims);
-    Ok(next.run(req.map(Into::into)).await)
+    req.extensions_mut().insert(claims);
+    req.extensions_mut().insert(auth_method);
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> JwtSettings {
+        JwtSettings {
+            issuer: "issuer".into(),
+            audience: "audience".into(),
+            leeway_secs: 60,
+        }
+    }
+
+    fn verify(keys: &KeySet, token: &str, settings: &JwtSettings) -> Result<Claims, AppError> {
+        keys.decode::<Claims>(token, &settings.validation(keys))
+            .map(|token_data| token_data.claims)
+            .map_err(decode_error)
+    }
+
+    #[test]
+    fn test_stamped_claims_verify() {
+        let keys = KeySet::hmac(b"secret", None);
+        let claims = Claims::new("user", Duration::minutes(5));
+        let token = keys.encode(&settings().stamp(&claims)).unwrap();
+
+        let decoded = verify(&keys, &token, &settings()).unwrap();
+        assert_eq!(decoded.sub, "user");
+        assert_eq!(decoded.iss.as_deref(), Some("issuer"));
+        assert_eq!(decoded.aud.as_deref(), Some("audience"));
+        assert_eq!(decoded.nbf, Some(claims.iat));
+    }
+
+    #[test]
+    fn test_rejects_other_issuer_audience_and_unstamped_tokens() {
+        let keys = KeySet::hmac(b"secret", None);
+        let claims = Claims::new("user", Duration::minutes(5));
+        let token = keys.encode(&settings().stamp(&claims)).unwrap();
+
+        let other_issuer = JwtSettings {
+            issuer: "other".into(),
+            ..settings()
+        };
+        let other_audience = JwtSettings {
+            audience: "other".into(),
+            ..settings()
+        };
+        assert!(matches!(verify(&keys, &token, &other_issuer), Err(AppError::InvalidToken)));
+        assert!(matches!(verify(&keys, &token, &other_audience), Err(AppError::InvalidToken)));
+
+        let unstamped = keys.encode(&claims).unwrap();
+        assert!(matches!(verify(&keys, &unstamped, &settings()), Err(AppError::InvalidToken)));
+        assert!(matches!(verify(&keys, "not.a.token", &settings()), Err(AppError::InvalidToken)));
+    }
+
+    #[test]
+    fn test_expiry_with_leeway() {
+        let keys = KeySet::hmac(b"secret", None);
+        let recently_expired = Claims::new("user", Duration::seconds(-30));
+        let token = keys.encode(&settings().stamp(&recently_expired)).unwrap();
+        assert!(verify(&keys, &token, &settings()).is_ok());
+
+        let expired = Claims::new("user", Duration::seconds(-120));
+        let token = keys.encode(&settings().stamp(&expired)).unwrap();
+        assert!(matches!(verify(&keys, &token, &settings()), Err(AppError::TokenExpired)));
+    }
+
+    #[test]
+    fn test_issued_before_compares_milliseconds() {
+        let mut claims = Claims::new("user", Duration::minutes(5));
+        let iat_ms = claims.iat_ms.unwrap();
+        assert!(claims.issued_before(iat_ms + 1));
+        assert!(!claims.issued_before(iat_ms));
+        assert!(!claims.issued_before(iat_ms - 1));
+
+        // Without `iat_ms`, anything in the `iat` second after its start counts as later.
+        claims.iat_ms = None;
+        let second_ms = claims.iat as i64 * 1000;
+        assert!(claims.issued_before(second_ms + 1));
+        assert!(!claims.issued_before(second_ms));
+    }
 }
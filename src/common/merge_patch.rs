@@ -0,0 +1,153 @@
+//! JSON Merge Patch (RFC 7396) support.
+//!
+//! This module provides `MergePatch`, an extractor for `application/merge-patch+json` request
+//! bodies, and `merge`, which applies such a patch to a JSON document. Handlers apply the patch
+//! to the current representation of a resource and validate the merged result, so clients only
+//! need to send the fields they want to change.
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use super::dto::ApiResponse;
+
+/// The media type of a JSON Merge Patch document.
+pub const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+
+/// A JSON Merge Patch request body.
+///
+/// The request must be sent with the `application/merge-patch+json` content type and the body
+/// must be a JSON object; a patch that replaces the whole resource is not supported.
+#[derive(Debug, Clone)]
+pub struct MergePatch(pub Map<String, Value>);
+
+/// Error type for MergePatch extraction failures.
+#[derive(Debug, Error)]
+pub enum MergePatchRejection {
+    #[error("Expected request with `Content-Type: application/merge-patch+json`")]
+    UnsupportedMediaType,
+
+    #[error("Failed to read request body")]
+    Body,
+
+    #[error("Invalid merge patch: {0}")]
+    InvalidPatch(String),
+}
+
+impl IntoResponse for MergePatchRejection {
+    fn into_response(self) -> Response {
+        let status = match self {
+            MergePatchRejection::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            MergePatchRejection::Body | MergePatchRejection::InvalidPatch(_) => {
+                StatusCode::BAD_REQUEST
+            }
+        };
+
+        let body = axum::Json(ApiResponse::<()> {
+            status: status.as_u16(),
+            message: self.to_string(),
+            data: None,
+        });
+
+        (status, body).into_response()
+    }
+}
+
+impl<S> FromRequest<S> for MergePatch
+where
+    S: Send + Sync,
+{
+    type Rejection = MergePatchRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_merge_patch = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|mime| mime.trim().eq_ignore_ascii_case(MERGE_PATCH_CONTENT_TYPE));
+        if !is_merge_patch {
+            return Err(MergePatchRejection::UnsupportedMediaType);
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|_| MergePatchRejection::Body)?;
+
+        match serde_json::from_slice(&bytes) {
+            Ok(Value::Object(patch)) => Ok(MergePatch(patch)),
+            Ok(_) => Err(MergePatchRejection::InvalidPatch(
+                "the patch must be a JSON object".into(),
+            )),
+            Err(e) => Err(MergePatchRejection::InvalidPatch(e.to_string())),
+        }
+    }
+}
+
+/// Applies a merge patch to `target` as described in RFC 7396.
+///
+/// Members set to `null` in the patch are removed from the target, objects are merged
+/// recursively and any other value replaces the target's member.
+pub fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!("target was just made an object");
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge(target.entry(key.as_str()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn merged(target: Value, patch: Value) -> Value {
+        let mut target = target;
+        merge(&mut target, &patch);
+        target
+    }
+
+    #[test]
+    fn test_merge_replaces_adds_and_removes_members() {
+        assert_eq!(merged(json!({"a": "b"}), json!({"a": "c"})), json!({"a": "c"}));
+        assert_eq!(merged(json!({"a": "b"}), json!({"b": "c"})), json!({"a": "b", "b": "c"}));
+        assert_eq!(merged(json!({"a": "b"}), json!({"a": null})), json!({}));
+        assert_eq!(merged(json!({"a": "b", "b": "c"}), json!({"a": null})), json!({"b": "c"}));
+        assert_eq!(merged(json!({"a": "b"}), json!({})), json!({"a": "b"}));
+    }
+
+    #[test]
+    fn test_merge_recurses_into_objects_but_replaces_arrays() {
+        assert_eq!(
+            merged(json!({"a": {"b": "c", "d": "e"}}), json!({"a": {"b": null, "f": "g"}})),
+            json!({"a": {"d": "e", "f": "g"}})
+        );
+        assert_eq!(merged(json!({"a": ["b"]}), json!({"a": ["c"]})), json!({"a": ["c"]}));
+        assert_eq!(merged(json!({"a": "c"}), json!({"a": {"b": "c"}})), json!({"a": {"b": "c"}}));
+        assert_eq!(merged(json!({}), json!({"a": {"bb": {"ccc": null}}})), json!({"a": {"bb": {}}}));
+    }
+
+    #[test]
+    fn test_non_object_patch_replaces_target() {
+        assert_eq!(merged(json!({"a": "b"}), json!(["c"])), json!(["c"]));
+        assert_eq!(merged(json!({"a": "foo"}), json!(null)), json!(null));
+        assert_eq!(merged(json!(["a", "b"]), json!({"a": "b"})), json!({"a": "b"}));
+    }
+}
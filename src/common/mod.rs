@@ -1,12 +1,24 @@
 pub mod app_state;
 pub mod bootstrap;
+pub mod client_ip;
 pub mod config;
 pub mod dto;
 pub mod error;
+pub mod etag;
 pub mod hash_util;
 pub mod jwt;
+pub mod jwt_keys;
+pub mod mailer;
+pub mod merge_patch;
+pub mod oidc;
 #[cfg(feature = "opentelemetry")]
 pub mod opentelemetry;
 pub mod pagination;
+pub mod password_policy;
+pub mod query_spec;
+pub mod rate_limit;
+pub mod rbac;
+pub mod token_util;
+pub mod totp;
 pub mod ts_format;
 pub mod validated_json;
@@ -0,0 +1,400 @@
+//! OpenID Connect relying party: provider discovery, the authorization code flow with PKCE,
+//! and validation of ID tokens against the keys a provider publishes.
+//!
+//! This module only speaks the protocol. Linking provider identities to users and issuing
+//! our own tokens is left to the `oidc` domain.
+
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+/// How long discovery documents and key sets are cached.
+const CACHE_TTL: Duration = Duration::from_secs(3600);
+/// Minimum time between key set downloads triggered by an unknown `kid`, so that tokens with
+/// made-up key IDs cannot make us hammer the provider.
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+/// Timeout of each request to a provider.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Signature algorithms accepted for ID tokens. HMAC algorithms are left out on purpose:
+/// they would turn the client secret into a signing key.
+const ALLOWED_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// Errors of the OpenID Connect protocol flow.
+#[derive(Debug, Error)]
+pub enum OidcError {
+    #[error("request to identity provider failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("invalid provider metadata: {0}")]
+    Discovery(String),
+    #[error("token request rejected: {0}")]
+    TokenExchange(String),
+    #[error("invalid ID token: {0}")]
+    InvalidIdToken(String),
+}
+
+/// Settings of one identity provider.
+#[derive(Debug, Clone)]
+pub struct OidcProviderConfig {
+    /// Name used in our URLs, e.g. `/auth/oidc/{name}/authorize`.
+    pub name: String,
+    /// Issuer identifier; the discovery document is fetched from
+    /// `{issuer}/.well-known/openid-configuration`.
+    pub issuer: String,
+    pub client_id: String,
+    /// `None` for public clients, which rely on PKCE alone.
+    pub client_secret: Option<String>,
+    /// Space-delimited scopes to request; must include `openid`.
+    pub scopes: String,
+    /// Callback URL registered with the provider.
+    pub redirect_url: String,
+}
+
+impl OidcProviderConfig {
+    /// Reads the settings of provider `name` from the `OIDC_<NAME>_*` environment variables,
+    /// where `<NAME>` is the upper-cased name with dashes replaced by underscores.
+    /// `ISSUER` and `CLIENT_ID` are required; `CLIENT_SECRET` and `SCOPES` are optional.
+    pub fn from_env(name: &str, redirect_base_url: &str) -> Result<Self, env::VarError> {
+        let prefix = format!("OIDC_{}_", name.to_uppercase().replace('-', "_"));
+        let var = |key: &str| env::var(format!("{prefix}{key}"));
+
+        Ok(Self {
+            name: name.to_string(),
+            issuer: var("ISSUER")?,
+            client_id: var("CLIENT_ID")?,
+            client_secret: var("CLIENT_SECRET").ok().filter(|s| !s.is_empty()),
+            scopes: var("SCOPES").unwrap_or_else(|_| "openid email profile".to_string()),
+            redirect_url: format!(
+                "{}/auth/oidc/{name}/callback",
+                redirect_base_url.trim_end_matches('/')
+            ),
+        })
+    }
+}
+
+/// The parts of a provider's discovery document we use.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+    #[serde(default)]
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+}
+
+/// Audience of an ID token: a single client ID or a list of them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Claims of a validated ID token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    /// Identifier of the user at the provider; stable and unique per issuer.
+    pub sub: String,
+    pub aud: Audience,
+    pub azp: Option<String>,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    #[serde(default, deserialize_with = "bool_or_string")]
+    pub email_verified: bool,
+    pub preferred_username: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Accepts `true` as well as `"true"`, which some providers send for boolean claims.
+fn bool_or_string<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrString {
+        Bool(bool),
+        String(String),
+    }
+
+    Ok(match BoolOrString::deserialize(deserializer)? {
+        BoolOrString::Bool(value) => value,
+        BoolOrString::String(value) => value.eq_ignore_ascii_case("true"),
+    })
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+struct Cached<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+/// Returns the PKCE `S256` code challenge for a code verifier (RFC 7636, section 4.2).
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// Relying party for one identity provider.
+///
+/// The discovery document and the provider's key set are fetched on first use and cached;
+/// the key set is fetched again early when a token names a key it does not contain, which is
+/// how providers roll their signing keys.
+pub struct OidcClient {
+    config: OidcProviderConfig,
+    http: reqwest::Client,
+    metadata: RwLock<Option<Cached<ProviderMetadata>>>,
+    jwks: RwLock<Option<Cached<JwkSet>>>,
+}
+
+impl OidcClient {
+    /// Creates a client for the given provider.
+    pub fn new(config: OidcProviderConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("failed to build HTTP client");
+
+        Self {
+            config,
+            http,
+            metadata: RwLock::new(None),
+            jwks: RwLock::new(None),
+        }
+    }
+
+    /// Returns the settings of the provider.
+    pub fn config(&self) -> &OidcProviderConfig {
+        &self.config
+    }
+
+    /// Returns the provider's discovery document, fetching it if needed.
+    /// The document must name the configured issuer (OpenID Connect Discovery, section 4.3).
+    pub async fn metadata(&self) -> Result<ProviderMetadata, OidcError> {
+        if let Some(cached) = self.metadata.read().await.as_ref()
+            && cached.fetched_at.elapsed() < CACHE_TTL
+        {
+            return Ok(cached.value.clone());
+        }
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata =
+            self.http.get(url).send().await?.error_for_status()?.json().await?;
+        if metadata.issuer != self.config.issuer {
+            return Err(OidcError::Discovery(format!(
+                "issuer `{}` does not match `{}`",
+                metadata.issuer, self.config.issuer
+            )));
+        }
+
+        *self.metadata.write().await = Some(Cached {
+            value: metadata.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(metadata)
+    }
+
+    /// Builds the URL that sends the user to the provider to log in. `state` and `nonce` are
+    /// echoed back in the callback and the ID token; the PKCE challenge is derived from
+    /// `code_verifier`.
+    pub async fn authorization_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+    ) -> Result<String, OidcError> {
+        let metadata = self.metadata().await?;
+        let challenge = pkce_challenge(code_verifier);
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            [
+                ("response_type", "code"),
+                ("client_id", self.config.client_id.as_str()),
+                ("redirect_uri", self.config.redirect_url.as_str()),
+                ("scope", self.config.scopes.as_str()),
+                ("state", state),
+                ("nonce", nonce),
+                ("code_challenge", challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| OidcError::Discovery(format!("invalid authorization endpoint: {e}")))?;
+
+        Ok(url.into())
+    }
+
+    /// Exchanges an authorization code for the provider's tokens and returns the ID token.
+    ///
+    /// Confidential clients authenticate with `client_secret_post` if the provider lists it,
+    /// and with `client_secret_basic`, the default of the specification, otherwise.
+    pub async fn exchange_code(&self, code: &str, code_verifier: &str) -> Result<String, OidcError> {
+        let metadata = self.metadata().await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("code_verifier", code_verifier),
+            ("client_id", self.config.client_id.as_str()),
+        ];
+
+        let mut request = self.http.post(&metadata.token_endpoint);
+        if let Some(secret) = &self.config.client_secret {
+            let post = metadata
+                .token_endpoint_auth_methods_supported
+                .iter()
+                .any(|method| method == "client_secret_post");
+            if post {
+                form.push(("client_secret", secret));
+            } else {
+                request = request.basic_auth(&self.config.client_id, Some(secret));
+            }
+        }
+
+        let response = request.form(&form).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let message = match response.json::<TokenErrorResponse>().await {
+                Ok(err) => match err.error_description {
+                    Some(description) => format!("{}: {description}", err.error),
+                    None => err.error,
+                },
+                Err(_) => status.to_string(),
+            };
+            return Err(OidcError::TokenExchange(message));
+        }
+
+        response
+            .json::<TokenResponse>()
+            .await?
+            .id_token
+            .ok_or_else(|| OidcError::TokenExchange("response has no ID token".to_string()))
+    }
+
+    /// Validates an ID token (OpenID Connect Core, section 3.1.3.7): the signature against
+    /// the provider's keys, issuer, audience, authorized party, expiry, and the `nonce` sent
+    /// with the authorization request.
+    pub async fn validate_id_token(
+        &self,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let header =
+            decode_header(id_token).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(OidcError::InvalidIdToken(format!(
+                "algorithm {:?} is not allowed",
+                header.alg
+            )));
+        }
+
+        let jwk = self.find_key(header.kid.as_deref()).await?;
+        let key = DecodingKey::from_jwk(&jwk).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| OidcError::InvalidIdToken(e.to_string()))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(OidcError::InvalidIdToken("nonce does not match".to_string()));
+        }
+        let multiple_audiences = matches!(&claims.aud, Audience::Many(aud) if aud.len() > 1);
+        let azp_matches = claims.azp.as_deref() == Some(self.config.client_id.as_str());
+        if (multiple_audiences || claims.azp.is_some()) && !azp_matches {
+            return Err(OidcError::InvalidIdToken(
+                "authorized party does not match".to_string(),
+            ));
+        }
+
+        Ok(claims)
+    }
+
+    /// Finds the key with the given ID, or the only key if the token names none.
+    async fn find_key(&self, kid: Option<&str>) -> Result<Jwk, OidcError> {
+        let select = |jwks: &JwkSet| match kid {
+            Some(kid) => jwks.find(kid).cloned(),
+            None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+            None => None,
+        };
+
+        let refresh_after = {
+            let cached = self.jwks.read().await;
+            match cached.as_ref() {
+                Some(cached) if cached.fetched_at.elapsed() < CACHE_TTL => {
+                    if let Some(jwk) = select(&cached.value) {
+                        return Ok(jwk);
+                    }
+                    Some(cached.fetched_at)
+                }
+                _ => None,
+            }
+        };
+        if refresh_after.is_some_and(|fetched_at| fetched_at.elapsed() < JWKS_MIN_REFRESH) {
+            return Err(OidcError::InvalidIdToken("unknown signing key".to_string()));
+        }
+
+        let metadata = self.metadata().await?;
+        let jwks: JwkSet =
+            self.http.get(&metadata.jwks_uri).send().await?.error_for_status()?.json().await?;
+        let jwk = select(&jwks);
+        *self.jwks.write().await = Some(Cached {
+            value: jwks,
+            fetched_at: Instant::now(),
+        });
+
+        jwk.ok_or_else(|| OidcError::InvalidIdToken("unknown signing key".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_challenge() {
+        // RFC 7636, appendix B.
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+}
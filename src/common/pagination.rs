@@ -1,6 +1,26 @@
+//! Pagination for list endpoints.
+//!
+//! Lists are paged by offset by default: `page` and `page_size` select a page, and every
+//! response counts all matching items. Lists whose repositories support it can also be paged
+//! with keyset cursors, which stay fast and stable on large tables: sending a `cursor`
+//! parameter, empty for the first page, switches to cursor mode, and each response carries
+//! signed `next_cursor` and `prev_cursor` values that continue from its last or first item.
+
+use std::fmt;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
 use utoipa::IntoParams;
 
+use super::{
+    error::AppError,
+    query_spec::{QuerySpec, SortKey},
+};
+
 /// Default page size when not specified
 pub const DEFAULT_PAGE_SIZE: u32 = 20;
 
@@ -19,6 +39,16 @@ pub struct PageRequest {
     #[param(minimum = 1, maximum = 100, default = 20)]
     #[serde(default = "default_page_size")]
     pub page_size: u32,
+
+    /// Switches to cursor pagination: a `next_cursor` or `prev_cursor` from a previous page,
+    /// or empty for the first page. `page` is ignored.
+    pub cursor: Option<String>,
+
+    /// With cursor pagination, also return the total number of items. Offset pagination
+    /// always does.
+    #[param(default = false)]
+    #[serde(default)]
+    pub include_total: bool,
 }
 
 fn default_page() -> u32 {
@@ -34,6 +64,8 @@ impl Default for PageRequest {
         Self {
             page: 1,
             page_size: DEFAULT_PAGE_SIZE,
+            cursor: None,
+            include_total: false,
         }
     }
 }
@@ -41,7 +73,7 @@ impl Default for PageRequest {
 impl PageRequest {
     /// Returns the page size, clamped to MAX_PAGE_SIZE.
     pub fn page_size(&self) -> u32 {
-        self.page_size.min(MAX_PAGE_SIZE).max(1)
+        self.page_size.clamp(1, MAX_PAGE_SIZE)
     }
 
     /// Returns the page number, ensuring it's at least 1.
@@ -58,6 +90,11 @@ impl PageRequest {
     pub fn limit(&self) -> i64 {
         self.page_size() as i64
     }
+
+    /// Returns whether cursor pagination was requested.
+    pub fn is_cursor(&self) -> bool {
+        self.cursor.is_some()
+    }
 }
 
 /// Paginated response wrapper containing items and pagination metadata.
@@ -90,3 +127,281 @@ impl<T> PageResponse<T> {
         }
     }
 }
+
+/// A position in a list, handed to clients as an opaque signed string.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// The sort order the cursor was issued for; see `QuerySpec::order_key`.
+    #[serde(rename = "o")]
+    pub order: String,
+    /// Sort key values of the item the page starts after, or ends before if `backward`.
+    #[serde(rename = "k")]
+    pub keys: Vec<Value>,
+    #[serde(rename = "b", default)]
+    pub backward: bool,
+}
+
+/// The key cursors are signed with, using HMAC-SHA256, so clients cannot forge positions.
+#[derive(Clone)]
+pub struct CursorKey(Vec<u8>);
+
+impl fmt::Debug for CursorKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CursorKey(..)")
+    }
+}
+
+impl CursorKey {
+    /// Uses `secret`, or a random key if there is none. Cursors signed with a random key stop
+    /// working when the process restarts and are rejected by other instances.
+    pub fn new(secret: Option<&str>) -> Self {
+        match secret.filter(|secret| !secret.is_empty()) {
+            Some(secret) => Self(secret.as_bytes().to_vec()),
+            None => {
+                let mut key = vec![0u8; 32];
+                rand::rng().fill_bytes(&mut key);
+                Self(key)
+            }
+        }
+    }
+
+    /// Encodes and signs a cursor.
+    pub fn encode(&self, cursor: &Cursor) -> String {
+        let payload =
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).expect("cursors serialize to JSON"));
+        let signature = self.mac().chain_update(&payload).finalize().into_bytes();
+        format!("{payload}.{}", URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// Decodes a cursor, rejecting it if its signature does not match or it was issued for
+    /// another sort order than `order`.
+    pub fn decode(&self, raw: &str, order: &str) -> Result<Cursor, AppError> {
+        let invalid = || AppError::ValidationError("Invalid cursor".into());
+        let (payload, signature) = raw.split_once('.').ok_or_else(invalid)?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+        self.mac()
+            .chain_update(payload)
+            .verify_slice(&signature)
+            .map_err(|_| invalid())?;
+
+        let cursor: Cursor = URL_SAFE_NO_PAD
+            .decode(payload)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(invalid)?;
+        if cursor.order != order {
+            return Err(AppError::ValidationError(
+                "Cursor was issued for another sort order".into(),
+            ));
+        }
+        Ok(cursor)
+    }
+
+    /// Positions `spec` at the cursor of `page_request`, if there is one, and returns the
+    /// cursor along with it.
+    pub fn seek(
+        &self,
+        spec: QuerySpec,
+        page_request: &PageRequest,
+    ) -> Result<(QuerySpec, Option<Cursor>), AppError> {
+        match page_request.cursor.as_deref().filter(|raw| !raw.is_empty()) {
+            Some(raw) => {
+                let cursor = self.decode(raw, &spec.order_key())?;
+                let spec = spec.seek(&cursor.keys, cursor.backward)?;
+                Ok((spec, Some(cursor)))
+            }
+            None => Ok((spec, None)),
+        }
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::new_from_slice(&self.0).expect("HMAC takes keys of any length")
+    }
+}
+
+/// A page of a list paginated with cursors.
+#[derive(Debug, Clone, Serialize)]
+pub struct CursorPage<T> {
+    /// The items on the current page
+    pub items: Vec<T>,
+    /// Maximum number of items per page
+    pub page_size: u32,
+    /// Cursor of the page after this one, if there are more items
+    pub next_cursor: Option<String>,
+    /// Cursor of the page before this one, if there are earlier items
+    pub prev_cursor: Option<String>,
+    /// Total number of items, if requested with `include_total`
+    pub total: Option<u64>,
+}
+
+impl<T: SortKey> CursorPage<T> {
+    /// Creates a page from the rows a query for `spec`, positioned at `cursor` by
+    /// `CursorKey::seek`, returned with a limit of one more than the page size. The extra row
+    /// only shows that the list goes on.
+    pub fn new(
+        mut rows: Vec<T>,
+        total: Option<u64>,
+        page_request: &PageRequest,
+        spec: &QuerySpec,
+        cursor: Option<&Cursor>,
+        key: &CursorKey,
+    ) -> Self {
+        let page_size = page_request.page_size();
+        let has_more = rows.len() > page_size as usize;
+        rows.truncate(page_size as usize);
+
+        let backward = cursor.is_some_and(|cursor| cursor.backward);
+        if backward {
+            rows.reverse();
+        }
+        // A backward page ends just before the cursor, and a forward page after a cursor
+        // starts just after it; an empty page continues from the cursor itself.
+        let (more_before, more_after) = if backward {
+            (has_more, true)
+        } else {
+            (cursor.is_some(), has_more)
+        };
+        let position = |row: Option<&T>| {
+            row.map(|row| spec.sort_keys(row))
+                .or_else(|| cursor.map(|cursor| cursor.keys.clone()))
+        };
+        let encode = |keys: Vec<Value>, backward: bool| {
+            key.encode(&Cursor {
+                order: spec.order_key(),
+                keys,
+                backward,
+            })
+        };
+
+        Self {
+            prev_cursor: position(rows.first())
+                .filter(|_| more_before)
+                .map(|keys| encode(keys, true)),
+            next_cursor: position(rows.last())
+                .filter(|_| more_after)
+                .map(|keys| encode(keys, false)),
+            items: rows,
+            page_size,
+            total,
+        }
+    }
+}
+
+impl<T> CursorPage<T> {
+    /// Converts the items, e.g. from domain objects to DTOs.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> CursorPage<U> {
+        CursorPage {
+            items: self.items.into_iter().map(f).collect(),
+            page_size: self.page_size,
+            next_cursor: self.next_cursor,
+            prev_cursor: self.prev_cursor,
+            total: self.total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct Row(&'static str);
+
+    impl SortKey for Row {
+        fn sort_key(&self, _column: &str) -> Value {
+            json!(self.0)
+        }
+    }
+
+    fn cursor_request(cursor: Option<&str>) -> PageRequest {
+        PageRequest {
+            page_size: 2,
+            cursor: Some(cursor.unwrap_or_default().to_string()),
+            ..PageRequest::default()
+        }
+    }
+
+    fn keys(raw: Option<&String>, key: &CursorKey) -> Option<(Vec<Value>, bool)> {
+        raw.map(|raw| {
+            let cursor = key.decode(raw, "id").unwrap();
+            (cursor.keys, cursor.backward)
+        })
+    }
+
+    #[test]
+    fn test_cursor_signature_and_order_are_checked() {
+        let key = CursorKey::new(Some("secret"));
+        let cursor = Cursor {
+            order: "-created_at,id".into(),
+            keys: vec![json!(null), json!("7")],
+            backward: true,
+        };
+        let encoded = key.encode(&cursor);
+
+        assert_eq!(key.decode(&encoded, "-created_at,id").unwrap(), cursor);
+        assert!(key.decode(&encoded, "id").is_err());
+        assert!(CursorKey::new(Some("other")).decode(&encoded, "-created_at,id").is_err());
+        assert!(CursorKey::new(None).decode(&encoded, "-created_at,id").is_err());
+
+        let (payload, signature) = encoded.split_once('.').unwrap();
+        let forged = URL_SAFE_NO_PAD.encode(br#"{"o":"-created_at,id","k":[null,"1"],"b":true}"#);
+        assert!(key.decode(&format!("{forged}.{signature}"), "-created_at,id").is_err());
+        assert!(key.decode(payload, "-created_at,id").is_err());
+        assert!(key.decode("", "-created_at,id").is_err());
+    }
+
+    #[test]
+    fn test_cursor_page_links_neighbouring_pages() {
+        let key = CursorKey::new(Some("secret"));
+        let spec = QuerySpec::new("id");
+
+        // First page, with a row to spare
+        let page = CursorPage::new(
+            vec![Row("1"), Row("2"), Row("3")],
+            None,
+            &cursor_request(None),
+            &spec,
+            None,
+            &key,
+        );
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(keys(page.prev_cursor.as_ref(), &key), None);
+        assert_eq!(keys(page.next_cursor.as_ref(), &key), Some((vec![json!("2")], false)));
+
+        // Last page after a cursor
+        let after = Cursor {
+            order: "id".into(),
+            keys: vec![json!("2")],
+            backward: false,
+        };
+        let page = CursorPage::new(
+            vec![Row("3")],
+            Some(3),
+            &cursor_request(Some(&key.encode(&after))),
+            &spec,
+            Some(&after),
+            &key,
+        );
+        assert_eq!(page.total, Some(3));
+        assert_eq!(keys(page.prev_cursor.as_ref(), &key), Some((vec![json!("3")], true)));
+        assert_eq!(keys(page.next_cursor.as_ref(), &key), None);
+
+        // Backward from there: rows arrive in reverse order
+        let before = Cursor {
+            order: "id".into(),
+            keys: vec![json!("3")],
+            backward: true,
+        };
+        let page = CursorPage::new(
+            vec![Row("2"), Row("1")],
+            None,
+            &cursor_request(Some(&key.encode(&before))),
+            &spec,
+            Some(&before),
+            &key,
+        );
+        assert_eq!(page.items.iter().map(|row| row.0).collect::<Vec<_>>(), ["1", "2"]);
+        assert_eq!(keys(page.prev_cursor.as_ref(), &key), None);
+        assert_eq!(keys(page.next_cursor.as_ref(), &key), Some((vec![json!("2")], false)));
+    }
+}
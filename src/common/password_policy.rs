@@ -0,0 +1,192 @@
+//! Configurable password policy.
+//!
+//! The policy is loaded once from the environment and enforced on every password a user
+//! chooses, either through `ValidatedJson` (via `validate_password`) or, for rules that need
+//! to know who the user is, through `PasswordPolicy::check_identifiers` in the auth service.
+
+use std::{borrow::Cow, env, sync::LazyLock};
+
+use validator::ValidationError;
+
+/// The password policy configured via `PASSWORD_*` environment variables.
+pub static PASSWORD_POLICY: LazyLock<PasswordPolicy> = LazyLock::new(PasswordPolicy::from_env);
+
+/// Identifiers shorter than this are not checked for, as they would reject too many passwords.
+const MIN_IDENTIFIER_LENGTH: usize = 3;
+
+/// Rules a password must satisfy.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Reject passwords containing the user's username or email address.
+    pub reject_identifiers: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 12,
+            max_length: 128,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: false,
+            reject_identifiers: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Reads the policy from the environment, falling back to the defaults.
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+
+        let defaults = Self::default();
+        let flag = |name: &str, default: bool| {
+            env::var(name)
+                .map(|s| s.parse::<bool>().unwrap_or(default))
+                .unwrap_or(default)
+        };
+
+        Self {
+            min_length: env::var("PASSWORD_MIN_LENGTH")
+                .map(|s| s.parse::<usize>().unwrap_or(defaults.min_length))
+                .unwrap_or(defaults.min_length),
+            max_length: env::var("PASSWORD_MAX_LENGTH")
+                .map(|s| s.parse::<usize>().unwrap_or(defaults.max_length))
+                .unwrap_or(defaults.max_length),
+            require_uppercase: flag("PASSWORD_REQUIRE_UPPERCASE", defaults.require_uppercase),
+            require_lowercase: flag("PASSWORD_REQUIRE_LOWERCASE", defaults.require_lowercase),
+            require_digit: flag("PASSWORD_REQUIRE_DIGIT", defaults.require_digit),
+            require_symbol: flag("PASSWORD_REQUIRE_SYMBOL", defaults.require_symbol),
+            reject_identifiers: flag("PASSWORD_REJECT_IDENTIFIERS", defaults.reject_identifiers),
+        }
+    }
+
+    /// Checks the length and character class rules.
+    pub fn check(&self, password: &str) -> Result<(), ValidationError> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Err(violation(
+                "password_too_short",
+                format!("Password must be at least {} characters long", self.min_length),
+            ));
+        }
+        if length > self.max_length {
+            return Err(violation(
+                "password_too_long",
+                format!("Password cannot exceed {} characters", self.max_length),
+            ));
+        }
+
+        let classes = [
+            (self.require_uppercase, "an uppercase letter", char::is_uppercase as fn(char) -> bool),
+            (self.require_lowercase, "a lowercase letter", char::is_lowercase),
+            (self.require_digit, "a digit", |c: char| c.is_ascii_digit()),
+            (self.require_symbol, "a symbol", |c: char| !c.is_alphanumeric() && !c.is_whitespace()),
+        ];
+        for (required, description, matches) in classes {
+            if required && !password.chars().any(matches) {
+                return Err(violation(
+                    "password_missing_character_class",
+                    format!("Password must contain {description}"),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the password does not contain any of the user's identifiers
+    /// (username, email address or the local part of it), ignoring case.
+    pub fn check_identifiers(
+        &self,
+        password: &str,
+        identifiers: &[&str],
+    ) -> Result<(), ValidationError> {
+        if !self.reject_identifiers {
+            return Ok(());
+        }
+
+        let password = password.to_lowercase();
+        let contains_identifier = identifiers
+            .iter()
+            .flat_map(|identifier| {
+                let local_part = identifier.split_once('@').map(|(local, _)| local);
+                std::iter::once(*identifier).chain(local_part)
+            })
+            .map(str::to_lowercase)
+            .filter(|identifier| identifier.chars().count() >= MIN_IDENTIFIER_LENGTH)
+            .any(|identifier| password.contains(&identifier));
+
+        if contains_identifier {
+            return Err(violation(
+                "password_contains_identifier",
+                "Password must not contain your username or email address".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Validates a password against the configured policy.
+/// Intended for `#[validate(custom(function = validate_password))]` on request DTOs.
+pub fn validate_password(password: &str) -> Result<(), ValidationError> {
+    PASSWORD_POLICY.check(password)
+}
+
+fn violation(code: &'static str, message: String) -> ValidationError {
+    ValidationError::new(code).with_message(Cow::Owned(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy() {
+        let policy = PasswordPolicy::default();
+
+        assert!(policy.check("Correct-Horse-42").is_ok());
+        assert!(policy.check("Short1A").is_err());
+        assert!(policy.check("alllowercase42").is_err());
+        assert!(policy.check("ALLUPPERCASE42").is_err());
+        assert!(policy.check("NoDigitsAtAllHere").is_err());
+        assert!(policy.check(&"Aa1".repeat(50)).is_err());
+    }
+
+    #[test]
+    fn test_symbol_requirement() {
+        let policy = PasswordPolicy {
+            require_symbol: true,
+            ..PasswordPolicy::default()
+        };
+
+        assert!(policy.check("CorrectHorse42").is_err());
+        assert!(policy.check("Correct-Horse42").is_ok());
+    }
+
+    #[test]
+    fn test_identifiers_are_rejected() {
+        let policy = PasswordPolicy::default();
+        let identifiers = ["alice", "alice.smith@example.com"];
+
+        assert!(policy.check_identifiers("Secret-ALICE-42", &identifiers).is_err());
+        assert!(policy.check_identifiers("x-Alice.Smith-42", &identifiers).is_err());
+        assert!(policy.check_identifiers("Correct-Horse-42", &identifiers).is_ok());
+        // Very short identifiers are ignored.
+        assert!(policy.check_identifiers("Correct-Horse-42", &["or"]).is_ok());
+
+        let lenient = PasswordPolicy {
+            reject_identifiers: false,
+            ..policy
+        };
+        assert!(lenient.check_identifiers("Secret-ALICE-42", &identifiers).is_ok());
+    }
+}
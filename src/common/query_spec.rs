@@ -0,0 +1,621 @@
+//! Filtering and sorting for list endpoints.
+//!
+//! A list endpoint declares the fields clients may filter and sort by. Query parameters are
+//! parsed against that allowlist into a `QuerySpec`, which appends the matching `WHERE` and
+//! `ORDER BY` clauses to a `QueryBuilder`. Column names only ever come from the allowlist;
+//! values are always bound.
+//!
+//! A filter value may start with an operator, e.g. `username=in:alice,bob`:
+//!
+//! | Operator | Fields | Matches |
+//! |----------|--------|---------|
+//! | `eq:` | text | The exact value |
+//! | `ilike:` | text | Values containing it, ignoring case |
+//! | `in:` | text | Any of the comma-separated values |
+//! | `range:` | timestamps | `from..to`, RFC 3339, from inclusive, to exclusive; either may be left out |
+//!
+//! Without an operator, the field's default operator applies.
+//! Sorting takes comma-separated field names, descending with a leading `-`,
+//! e.g. `sort=-created_at,username`.
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{Postgres, QueryBuilder};
+
+use super::error::AppError;
+
+/// Most values an `in:` filter may list.
+pub const MAX_IN_VALUES: usize = 100;
+
+/// Most fields a list may be sorted by.
+pub const MAX_SORT_FIELDS: usize = 4;
+
+/// A filter operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    ILike,
+    In,
+    Range,
+}
+
+impl FilterOp {
+    const ALL: [FilterOp; 4] = [FilterOp::Eq, FilterOp::ILike, FilterOp::In, FilterOp::Range];
+
+    fn name(self) -> &'static str {
+        match self {
+            FilterOp::Eq => "eq",
+            FilterOp::ILike => "ilike",
+            FilterOp::In => "in",
+            FilterOp::Range => "range",
+        }
+    }
+}
+
+/// The type of a field, which decides the operators it supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Timestamp,
+}
+
+impl FieldKind {
+    fn supports(self, op: FilterOp) -> bool {
+        match self {
+            FieldKind::Text => matches!(op, FilterOp::Eq | FilterOp::ILike | FilterOp::In),
+            FieldKind::Timestamp => op == FilterOp::Range,
+        }
+    }
+}
+
+/// A field a list endpoint exposes for filtering or sorting.
+#[derive(Debug, Clone, Copy)]
+pub struct Field {
+    /// Name of the field in query parameters.
+    pub name: &'static str,
+    /// Column the field is stored in.
+    pub column: &'static str,
+    pub kind: FieldKind,
+    /// Operator applied to values without one.
+    pub default_op: FilterOp,
+}
+
+impl Field {
+    /// Declares a text field.
+    pub const fn text(name: &'static str, column: &'static str, default_op: FilterOp) -> Self {
+        Self {
+            name,
+            column,
+            kind: FieldKind::Text,
+            default_op,
+        }
+    }
+
+    /// Declares a timestamp field, filtered with ranges.
+    pub const fn timestamp(name: &'static str, column: &'static str) -> Self {
+        Self {
+            name,
+            column,
+            kind: FieldKind::Timestamp,
+            default_op: FilterOp::Range,
+        }
+    }
+}
+
+/// A parsed filter on one column.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Eq(String),
+    ILike(String),
+    In(Vec<String>),
+    Range {
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    },
+    /// Matches rows where the column is null. Not available to clients.
+    IsNull,
+}
+
+impl Filter {
+    /// Parses a filter value for `field`, e.g. `in:alice,bob` or `2026-01-01T00:00:00Z..`.
+    pub fn parse(field: &Field, raw: &str) -> Result<Self, AppError> {
+        let (op, value) = raw
+            .split_once(':')
+            .and_then(|(prefix, value)| {
+                FilterOp::ALL
+                    .into_iter()
+                    .find(|op| op.name() == prefix)
+                    .map(|op| (op, value))
+            })
+            .unwrap_or((field.default_op, raw));
+
+        if !field.kind.supports(op) {
+            return Err(invalid(format!(
+                "Operator `{}` is not supported for `{}`",
+                op.name(),
+                field.name
+            )));
+        }
+
+        match op {
+            FilterOp::Eq => Ok(Filter::Eq(value.to_string())),
+            FilterOp::ILike => Ok(Filter::ILike(value.to_string())),
+            FilterOp::In => {
+                let values: Vec<String> = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                if values.is_empty() || values.len() > MAX_IN_VALUES {
+                    return Err(invalid(format!(
+                        "`{}` must list 1 to {MAX_IN_VALUES} values",
+                        field.name
+                    )));
+                }
+                Ok(Filter::In(values))
+            }
+            FilterOp::Range => {
+                let (from, to) = value.split_once("..").ok_or_else(|| {
+                    invalid(format!("`{}` must be a range like `from..to`", field.name))
+                })?;
+                let from = parse_timestamp(field, from)?;
+                let to = parse_timestamp(field, to)?;
+                if from.is_none() && to.is_none() {
+                    return Err(invalid(format!("`{}` needs at least one bound", field.name)));
+                }
+                Ok(Filter::Range { from, to })
+            }
+        }
+    }
+
+    fn push(&self, builder: &mut QueryBuilder<'_, Postgres>, column: &str) {
+        match self {
+            Filter::Eq(value) => {
+                builder.push(format!(" AND {column} = ")).push_bind(value.clone());
+            }
+            Filter::ILike(value) => {
+                builder
+                    .push(format!(" AND {column} ILIKE "))
+                    .push_bind(format!("%{}%", escape_like(value)));
+            }
+            Filter::In(values) => {
+                builder
+                    .push(format!(" AND {column} = ANY("))
+                    .push_bind(values.clone())
+                    .push(")");
+            }
+            Filter::Range { from, to } => {
+                if let Some(from) = from {
+                    builder.push(format!(" AND {column} >= ")).push_bind(*from);
+                }
+                if let Some(to) = to {
+                    builder.push(format!(" AND {column} < ")).push_bind(*to);
+                }
+            }
+            Filter::IsNull => {
+                builder.push(format!(" AND {column} IS NULL"));
+            }
+        }
+    }
+}
+
+/// Sort direction of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    fn reversed(self) -> Self {
+        match self {
+            SortDirection::Asc => SortDirection::Desc,
+            SortDirection::Desc => SortDirection::Asc,
+        }
+    }
+}
+
+/// Items that can be paged through with keyset cursors.
+pub trait SortKey {
+    /// Returns the value of `column` for the item, as JSON. Timestamps are RFC 3339 strings.
+    fn sort_key(&self, column: &str) -> Value;
+}
+
+/// Filters and sort order for a list query.
+#[derive(Debug, Clone)]
+pub struct QuerySpec {
+    filters: Vec<(&'static str, Filter)>,
+    sort: Vec<(Field, SortDirection)>,
+    tiebreaker: &'static str,
+    seek: Option<Seek>,
+}
+
+/// A keyset position: rows after it, or before it if `backward`.
+#[derive(Debug, Clone)]
+struct Seek {
+    keys: Vec<KeyValue>,
+    backward: bool,
+}
+
+impl QuerySpec {
+    /// Creates a spec without filters or sort order.
+    ///
+    /// `tiebreaker` ends every sort order, ascending, so that the order is total and pages do
+    /// not overlap. It must be a unique, non-null text column.
+    pub fn new(tiebreaker: &'static str) -> Self {
+        Self {
+            filters: Vec::new(),
+            sort: Vec::new(),
+            tiebreaker,
+            seek: None,
+        }
+    }
+
+    /// Adds a filter parsed from a query parameter. Missing or blank values add nothing.
+    pub fn filter(mut self, field: &Field, raw: Option<&str>) -> Result<Self, AppError> {
+        if let Some(raw) = raw.map(str::trim).filter(|raw| !raw.is_empty()) {
+            self.filters.push((field.column, Filter::parse(field, raw)?));
+        }
+        Ok(self)
+    }
+
+    /// Adds a filter that only matches rows where `column` is null.
+    pub fn is_null(mut self, column: &'static str) -> Self {
+        self.filters.push((column, Filter::IsNull));
+        self
+    }
+
+    /// Sets the sort order from a `sort` parameter such as `-created_at,username`, allowing
+    /// only `fields`. Without a parameter, the `default` sort order applies.
+    pub fn sort(
+        mut self,
+        fields: &[Field],
+        raw: Option<&str>,
+        default: &str,
+    ) -> Result<Self, AppError> {
+        let raw = raw.map(str::trim).filter(|raw| !raw.is_empty()).unwrap_or(default);
+        let mut sort: Vec<(Field, SortDirection)> = Vec::new();
+        if raw.is_empty() {
+            self.sort = sort;
+            return Ok(self);
+        }
+
+        for key in raw.split(',').map(str::trim) {
+            let (name, direction) = match key.strip_prefix('-') {
+                Some(name) => (name, SortDirection::Desc),
+                None => (key, SortDirection::Asc),
+            };
+            let field = fields
+                .iter()
+                .find(|field| field.name == name)
+                .ok_or_else(|| invalid(format!("Cannot sort by `{name}`")))?;
+            if sort.iter().any(|(sorted, _)| sorted.column == field.column) {
+                return Err(invalid(format!("`{name}` is sorted by more than once")));
+            }
+            sort.push((*field, direction));
+        }
+        if sort.len() > MAX_SORT_FIELDS {
+            return Err(invalid(format!("Sort by at most {MAX_SORT_FIELDS} fields")));
+        }
+        self.sort = sort;
+        Ok(self)
+    }
+
+    /// Limits the query to the rows after the row with the sort key values `keys`, as returned
+    /// by `sort_keys`, or before it if `backward`. Backward queries return rows in reverse order.
+    pub fn seek(mut self, keys: &[Value], backward: bool) -> Result<Self, AppError> {
+        let kinds = self
+            .sort
+            .iter()
+            .map(|(field, _)| field.kind)
+            .chain([FieldKind::Text]);
+        if keys.len() != self.sort.len() + 1 {
+            return Err(invalid_cursor());
+        }
+        let keys = keys
+            .iter()
+            .zip(kinds)
+            .map(|(key, kind)| KeyValue::parse(key, kind))
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.last().is_none_or(|key| matches!(key, KeyValue::Null)) {
+            return Err(invalid_cursor());
+        }
+        self.seek = Some(Seek { keys, backward });
+        Ok(self)
+    }
+
+    /// Returns whether the query walks the sort order backwards; see `seek`.
+    pub fn is_backward(&self) -> bool {
+        self.seek.as_ref().is_some_and(|seek| seek.backward)
+    }
+
+    /// Appends ` AND ...` conditions for the filters, to follow a `WHERE` clause.
+    pub fn push_where(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        for (column, filter) in &self.filters {
+            filter.push(builder, column);
+        }
+    }
+
+    /// Appends an ` AND (...)` condition for the `seek` position, if any, to follow a `WHERE`
+    /// clause.
+    ///
+    /// Nulls sort as Postgres sorts them by default: after all values ascending, and before
+    /// them descending.
+    pub fn push_seek(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        let Some(seek) = &self.seek else {
+            return;
+        };
+        let columns: Vec<_> = self.keyset().collect();
+
+        // (a after x) OR (a = x AND b after y) OR ... with the tiebreaker last.
+        builder.push(" AND (");
+        let mut first_term = true;
+        for (i, ((column, direction), key)) in columns.iter().zip(&seek.keys).enumerate() {
+            if *direction == SortDirection::Asc && matches!(key, KeyValue::Null) {
+                continue;
+            }
+            if !first_term {
+                builder.push(" OR ");
+            }
+            first_term = false;
+
+            builder.push("(");
+            for ((column, _), key) in columns[..i].iter().zip(&seek.keys) {
+                if let KeyValue::Null = key {
+                    builder.push(format!("{column} IS NULL AND "));
+                } else {
+                    builder.push(format!("{column} = "));
+                    key.push_bind(builder);
+                    builder.push(" AND ");
+                }
+            }
+            match (direction, key) {
+                (SortDirection::Asc, key) => {
+                    builder.push(format!("({column} > "));
+                    key.push_bind(builder);
+                    builder.push(format!(" OR {column} IS NULL)"));
+                }
+                (SortDirection::Desc, KeyValue::Null) => {
+                    builder.push(format!("{column} IS NOT NULL"));
+                }
+                (SortDirection::Desc, key) => {
+                    builder.push(format!("{column} < "));
+                    key.push_bind(builder);
+                }
+            }
+            builder.push(")");
+        }
+        builder.push(")");
+    }
+
+    /// Appends an `ORDER BY` clause for the sort order, reversed for backward queries.
+    pub fn push_order_by(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        let order_by = self
+            .keyset()
+            .map(|(column, direction)| match direction {
+                SortDirection::Asc => format!("{column} ASC"),
+                SortDirection::Desc => format!("{column} DESC"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        builder.push(format!(" ORDER BY {order_by}"));
+    }
+
+    /// Describes the sort order, e.g. `-created_at,id`, so that cursors can be tied to it.
+    pub fn order_key(&self) -> String {
+        self.sort
+            .iter()
+            .map(|(field, direction)| (field.column, *direction))
+            .chain([(self.tiebreaker, SortDirection::Asc)])
+            .map(|(column, direction)| match direction {
+                SortDirection::Asc => column.to_string(),
+                SortDirection::Desc => format!("-{column}"),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Returns the sort key values of `item`, ending with the tiebreaker, for `seek`.
+    pub fn sort_keys<T: SortKey>(&self, item: &T) -> Vec<Value> {
+        self.sort
+            .iter()
+            .map(|(field, _)| field.column)
+            .chain([self.tiebreaker])
+            .map(|column| item.sort_key(column))
+            .collect()
+    }
+
+    /// The sort columns followed by the tiebreaker, in the direction the query walks them.
+    fn keyset(&self) -> impl Iterator<Item = (&'static str, SortDirection)> + '_ {
+        let backward = self.is_backward();
+        self.sort
+            .iter()
+            .map(|(field, direction)| (field.column, *direction))
+            .chain([(self.tiebreaker, SortDirection::Asc)])
+            .map(move |(column, direction)| {
+                (column, if backward { direction.reversed() } else { direction })
+            })
+    }
+}
+
+/// A sort key value read back from a cursor.
+#[derive(Debug, Clone)]
+enum KeyValue {
+    Null,
+    Text(String),
+    Timestamp(DateTime<Utc>),
+}
+
+impl KeyValue {
+    fn parse(value: &Value, kind: FieldKind) -> Result<Self, AppError> {
+        match (value, kind) {
+            (Value::Null, _) => Ok(KeyValue::Null),
+            (Value::String(s), FieldKind::Text) => Ok(KeyValue::Text(s.clone())),
+            (Value::String(s), FieldKind::Timestamp) => DateTime::parse_from_rfc3339(s)
+                .map(|t| KeyValue::Timestamp(t.with_timezone(&Utc)))
+                .map_err(|_| invalid_cursor()),
+            _ => Err(invalid_cursor()),
+        }
+    }
+
+    fn push_bind(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        match self {
+            KeyValue::Null => builder.push("NULL"),
+            KeyValue::Text(value) => builder.push_bind(value.clone()),
+            KeyValue::Timestamp(value) => builder.push_bind(*value),
+        };
+    }
+}
+
+fn invalid_cursor() -> AppError {
+    AppError::ValidationError("Invalid cursor".into())
+}
+
+fn parse_timestamp(field: &Field, raw: &str) -> Result<Option<DateTime<Utc>>, AppError> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    DateTime::parse_from_rfc3339(raw)
+        .map(|t| Some(t.with_timezone(&Utc)))
+        .map_err(|_| invalid(format!("`{}` bounds must be RFC 3339 timestamps", field.name)))
+}
+
+/// Escapes the wildcards of a `LIKE` pattern.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn invalid(message: String) -> AppError {
+    AppError::ValidationError(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NAME: Field = Field::text("name", "name", FilterOp::ILike);
+    const CREATED_AT: Field = Field::timestamp("created_at", "created_at");
+    const FIELDS: [Field; 2] = [NAME, CREATED_AT];
+
+    fn sql(spec: &QuerySpec) -> String {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT * FROM t WHERE 1=1");
+        spec.push_where(&mut builder);
+        spec.push_order_by(&mut builder);
+        builder.sql().to_string()
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        assert_eq!(Filter::parse(&NAME, "Bo").unwrap(), Filter::ILike("Bo".into()));
+        assert_eq!(Filter::parse(&NAME, "eq:bob").unwrap(), Filter::Eq("bob".into()));
+        assert_eq!(Filter::parse(&NAME, "eq:a:b").unwrap(), Filter::Eq("a:b".into()));
+        assert_eq!(
+            Filter::parse(&NAME, "in:a, b,").unwrap(),
+            Filter::In(vec!["a".into(), "b".into()])
+        );
+        assert!(Filter::parse(&NAME, "in:,").is_err());
+        assert!(Filter::parse(&NAME, "range:a..b").is_err());
+    }
+
+    #[test]
+    fn test_parse_timestamp_ranges() {
+        let from = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap();
+        assert_eq!(
+            Filter::parse(&CREATED_AT, "2026-01-01T00:00:00Z..").unwrap(),
+            Filter::Range {
+                from: Some(from.with_timezone(&Utc)),
+                to: None
+            }
+        );
+        assert!(Filter::parse(&CREATED_AT, "range:..2026-01-01T00:00:00Z").is_ok());
+        assert!(Filter::parse(&CREATED_AT, "..").is_err());
+        assert!(Filter::parse(&CREATED_AT, "2026-01-01").is_err());
+        assert!(Filter::parse(&CREATED_AT, "yesterday..").is_err());
+        assert!(Filter::parse(&CREATED_AT, "eq:2026-01-01T00:00:00Z").is_err());
+    }
+
+    #[test]
+    fn test_sort_allowlist() {
+        let spec = QuerySpec::new("id").sort(&FIELDS, Some("-created_at,name"), "").unwrap();
+        assert_eq!(spec.order_key(), "-created_at,name,id");
+
+        assert!(QuerySpec::new("id").sort(&FIELDS, Some("password"), "").is_err());
+        assert!(QuerySpec::new("id").sort(&FIELDS, Some("name,-name"), "").is_err());
+        assert!(QuerySpec::new("id").sort(&FIELDS, Some("name;drop"), "").is_err());
+        assert!(QuerySpec::new("id").sort(&FIELDS, Some("name,"), "").is_err());
+
+        let spec = QuerySpec::new("id").sort(&FIELDS, None, "-created_at").unwrap();
+        assert_eq!(spec.order_key(), "-created_at,id");
+        let spec = QuerySpec::new("id").sort(&FIELDS, None, "").unwrap();
+        assert_eq!(spec.order_key(), "id");
+    }
+
+    #[test]
+    fn test_builds_sql_with_bound_values() {
+        let spec = QuerySpec::new("id")
+            .filter(&NAME, Some("in:a,b"))
+            .unwrap()
+            .filter(&CREATED_AT, Some("2026-01-01T00:00:00Z..2026-02-01T00:00:00Z"))
+            .unwrap()
+            .filter(&NAME, Some("  "))
+            .unwrap()
+            .is_null("deleted_at")
+            .sort(&FIELDS, Some("-name"), "")
+            .unwrap();
+
+        assert_eq!(
+            sql(&spec),
+            "SELECT * FROM t WHERE 1=1 AND name = ANY($1) AND created_at >= $2 \
+             AND created_at < $3 AND deleted_at IS NULL ORDER BY name DESC, id ASC"
+        );
+    }
+
+    #[test]
+    fn test_builds_keyset_conditions() {
+        let spec = QuerySpec::new("id").sort(&FIELDS, Some("-created_at,name"), "").unwrap();
+        let seek = |keys: Value, backward: bool| {
+            spec.clone().seek(keys.as_array().unwrap(), backward).map(|spec| {
+                let mut builder = QueryBuilder::<Postgres>::new("WHERE 1=1");
+                spec.push_seek(&mut builder);
+                spec.push_order_by(&mut builder);
+                builder.sql().to_string()
+            })
+        };
+
+        assert_eq!(
+            seek(json!(["2026-01-01T00:00:00Z", "bob", "7"]), false).unwrap(),
+            "WHERE 1=1 AND ((created_at < $1) \
+             OR (created_at = $2 AND (name > $3 OR name IS NULL)) \
+             OR (created_at = $4 AND name = $5 AND (id > $6 OR id IS NULL))) \
+             ORDER BY created_at DESC, name ASC, id ASC"
+        );
+        assert_eq!(
+            seek(json!([null, null, "7"]), false).unwrap(),
+            "WHERE 1=1 AND ((created_at IS NOT NULL) \
+             OR (created_at IS NULL AND name IS NULL AND (id > $1 OR id IS NULL))) \
+             ORDER BY created_at DESC, name ASC, id ASC"
+        );
+        assert_eq!(
+            seek(json!([null, "bob", "7"]), true).unwrap(),
+            "WHERE 1=1 AND ((created_at IS NULL AND name < $1) \
+             OR (created_at IS NULL AND name = $2 AND id < $3)) \
+             ORDER BY created_at ASC, name DESC, id DESC"
+        );
+
+        assert!(seek(json!(["bob", "7"]), false).is_err());
+        assert!(seek(json!(["yesterday", "bob", "7"]), false).is_err());
+        assert!(seek(json!([null, "bob", null]), false).is_err());
+        assert!(seek(json!([null, 1, "7"]), false).is_err());
+    }
+
+    #[test]
+    fn test_escapes_like_wildcards() {
+        assert_eq!(escape_like(r"50%_a\b"), r"50\%\_a\\b");
+    }
+}
@@ -0,0 +1,92 @@
+//! In-process fixed-window rate limiting.
+//!
+//! Counters live in memory, so every instance enforces its limits independently.
+//! That is good enough to slow down guessing attacks from a single source; limits that
+//! must hold across instances belong in a shared store.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Windows are pruned once the limiter tracks more than this many keys.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+struct Window {
+    started_at: Instant,
+    hits: u32,
+}
+
+/// Allows at most `max_hits` hits per key within a fixed window.
+pub struct RateLimiter {
+    max_hits: u32,
+    window: Duration,
+    windows: Mutex<HashMap<String, Window>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter allowing `max_hits` hits per key every `window`.
+    pub fn new(max_hits: u32, window: Duration) -> Self {
+        Self {
+            max_hits,
+            window,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns how long `key` has to wait before its next hit is allowed,
+    /// or `None` if it is not limited.
+    pub fn retry_after(&self, key: &str) -> Option<Duration> {
+        let windows = self.windows.lock().ok()?;
+        let window = windows.get(key)?;
+        let elapsed = window.started_at.elapsed();
+        (elapsed < self.window && window.hits >= self.max_hits).then(|| self.window - elapsed)
+    }
+
+    /// Records a hit for `key`.
+    pub fn hit(&self, key: &str) {
+        let Ok(mut windows) = self.windows.lock() else {
+            return;
+        };
+
+        if windows.len() >= PRUNE_THRESHOLD {
+            let period = self.window;
+            windows.retain(|_, window| window.started_at.elapsed() < period);
+        }
+
+        let window = windows.entry(key.to_string()).or_insert(Window {
+            started_at: Instant::now(),
+            hits: 0,
+        });
+        if window.started_at.elapsed() >= self.window {
+            window.started_at = Instant::now();
+            window.hits = 0;
+        }
+        window.hits = window.hits.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_after_max_hits() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+
+        limiter.hit("a");
+        assert!(limiter.retry_after("a").is_none());
+        limiter.hit("a");
+        assert!(limiter.retry_after("a").is_some());
+        assert!(limiter.retry_after("b").is_none());
+    }
+
+    #[test]
+    fn test_window_expires() {
+        let limiter = RateLimiter::new(1, Duration::ZERO);
+
+        limiter.hit("a");
+        assert!(limiter.retry_after("a").is_none());
+    }
+}
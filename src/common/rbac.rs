@@ -0,0 +1,96 @@
+//! Role-based access control.
+//!
+//! Roles and their permissions are stored in the database and embedded in the JWT claims at
+//! login. Routes declare the permission they require with the `RequirePermission` layer, which
+//! must run after `jwt_auth` has inserted the `Claims` into the request extensions.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use tower::{Layer, Service};
+
+use super::{error::AppError, jwt::Claims};
+
+/// Role assigned to every newly created user.
+pub const DEFAULT_ROLE: &str = "user";
+
+/// Role with every permission.
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Permission names known to the application.
+pub mod permissions {
+    pub const USERS_READ: &str = "users:read";
+    pub const USERS_CREATE: &str = "users:create";
+    pub const USERS_UPDATE: &str = "users:update";
+    pub const USERS_DELETE: &str = "users:delete";
+    pub const TOKENS_REVOKE: &str = "tokens:revoke";
+    pub const CLIENTS_MANAGE: &str = "clients:manage";
+    pub const CREDENTIALS_IMPORT: &str = "credentials:import";
+    pub const USERS_IMPERSONATE: &str = "users:impersonate";
+    pub const USERS_PURGE: &str = "users:purge";
+}
+
+/// Route layer that rejects requests whose claims lack the given permission
+/// with `AppError::Forbidden`.
+///
+/// ```ignore
+/// Router::new().route(
+///     "/{id}",
+///     delete(delete_user).route_layer(RequirePermission(permissions::USERS_DELETE)),
+/// )
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RequirePermission(pub &'static str);
+
+impl<S> Layer<S> for RequirePermission {
+    type Service = RequirePermissionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequirePermissionService {
+            inner,
+            permission: self.0,
+        }
+    }
+}
+
+/// Service produced by the `RequirePermission` layer.
+#[derive(Debug, Clone)]
+pub struct RequirePermissionService<S> {
+    inner: S,
+    permission: &'static str,
+}
+
+impl<S> Service<Request> for RequirePermissionService<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let allowed = req
+            .extensions()
+            .get::<Claims>()
+            .is_some_and(|claims| claims.has_permission(self.permission));
+
+        if !allowed {
+            tracing::warn!(permission = self.permission, "Permission denied");
+            return Box::pin(async { Ok(AppError::Forbidden.into_response()) });
+        }
+
+        Box::pin(self.inner.call(req))
+    }
+}
@@ -0,0 +1,171 @@
+//! Time-based one-time passwords (RFC 6238) and recovery codes for two-factor authentication.
+//!
+//! Codes have 6 digits and change every 30 seconds, using HMAC-SHA1 as every common
+//! authenticator app expects. Recovery codes are single-use fallbacks for a lost device.
+
+use rand::Rng;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// Number of digits of a code.
+const DIGITS: usize = 6;
+/// Length of a time step in seconds.
+const STEP_SECS: u64 = 30;
+
+/// Characters of recovery codes: upper-case letters and digits without look-alikes (0/O, 1/I).
+const RECOVERY_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+/// Characters per recovery code; 16 characters from a 32-character alphabet give 80 bits.
+const RECOVERY_CODE_LENGTH: usize = 16;
+
+/// Generates a new random 160-bit shared secret, base32-encoded.
+pub fn generate_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+/// A user's TOTP generator.
+pub struct Totp(TOTP);
+
+impl Totp {
+    /// Creates a generator for a base32-encoded secret. `issuer` and `account_name` label
+    /// the entry in authenticator apps. Returns `None` if the secret is not valid base32.
+    pub fn new(secret: &str, issuer: &str, account_name: &str) -> Option<Self> {
+        let secret = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+        // A colon separates issuer and account name in the otpauth label.
+        Some(Self(TOTP::new_unchecked(
+            Algorithm::SHA1,
+            DIGITS,
+            0,
+            STEP_SECS,
+            secret,
+            Some(issuer.replace(':', "")),
+            account_name.replace(':', ""),
+        )))
+    }
+
+    /// Returns the `otpauth://` URI that authenticator apps import, usually as a QR code.
+    pub fn otpauth_uri(&self) -> String {
+        self.0.get_url()
+    }
+
+    /// Returns the time step `time` (Unix seconds) falls into.
+    pub fn step(time: u64) -> i64 {
+        (time / STEP_SECS) as i64
+    }
+
+    /// Checks `code` against the steps within `skew` steps of `time` to tolerate clock drift.
+    /// Steps at or before `last_used_step` are not accepted, so a code cannot be replayed.
+    /// Returns the matching step, which the caller records as the new `last_used_step`.
+    pub fn verify(
+        &self,
+        code: &str,
+        time: u64,
+        skew: u64,
+        last_used_step: Option<i64>,
+    ) -> Option<i64> {
+        let code = code.trim();
+        if code.len() != DIGITS || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let current = Self::step(time);
+        let skew = skew as i64;
+        (current - skew..=current + skew)
+            .filter(|step| *step >= 0 && last_used_step.is_none_or(|used| *step > used))
+            .find(|step| {
+                let expected = self.0.generate(*step as u64 * STEP_SECS);
+                constant_time_eq(expected.as_bytes(), code.as_bytes())
+            })
+    }
+}
+
+/// Generates `count` random recovery codes, formatted as `XXXX-XXXX-XXXX-XXXX`.
+pub fn generate_recovery_codes(count: usize) -> Vec<String> {
+    let mut rng = rand::rng();
+    (0..count)
+        .map(|_| {
+            let chars: Vec<char> = (0..RECOVERY_CODE_LENGTH)
+                .map(|_| {
+                    let index = rng.random_range(0..RECOVERY_CODE_ALPHABET.len());
+                    RECOVERY_CODE_ALPHABET[index] as char
+                })
+                .collect();
+            chars
+                .chunks(4)
+                .map(|chunk| chunk.iter().collect::<String>())
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect()
+}
+
+/// Normalizes a recovery code as typed by a user for hashing and lookup:
+/// separators and whitespace are dropped and letters are upper-cased.
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B test secret ("12345678901234567890"), base32-encoded.
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    fn totp() -> Totp {
+        Totp::new(RFC_SECRET, "Example", "alice@example.com").unwrap()
+    }
+
+    #[test]
+    fn test_rfc_6238_vectors() {
+        let totp = totp();
+
+        assert_eq!(totp.verify("287082", 59, 0, None), Some(1));
+        assert_eq!(totp.verify("081804", 1_111_111_109, 0, None), Some(37_037_036));
+        assert_eq!(totp.verify("050471", 1_111_111_111, 0, None), Some(37_037_037));
+        assert_eq!(totp.verify("000000", 59, 0, None), None);
+        assert_eq!(totp.verify("28708", 59, 0, None), None);
+    }
+
+    #[test]
+    fn test_skew_and_replay() {
+        let totp = totp();
+        let code = totp.0.generate(59);
+
+        // The code of step 1 is accepted one step later only with a skew of one step.
+        assert_eq!(totp.verify(&code, 89, 0, None), None);
+        assert_eq!(totp.verify(&code, 89, 1, None), Some(1));
+        // Once step 1 was used, neither it nor earlier steps are accepted again.
+        assert_eq!(totp.verify(&code, 59, 1, Some(1)), None);
+        assert_eq!(totp.verify(&totp.0.generate(89), 59, 1, Some(1)), Some(2));
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let uri = totp().otpauth_uri();
+
+        assert!(uri.starts_with("otpauth://totp/Example:alice%40example.com?"));
+        assert!(uri.contains(&format!("secret={RFC_SECRET}")));
+        assert!(uri.contains("issuer=Example"));
+        assert!(Totp::new(&generate_secret(), "Example", "alice").is_some());
+        assert!(Totp::new("not base32!", "Example", "alice").is_none());
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let codes = generate_recovery_codes(10);
+
+        assert_eq!(codes.len(), 10);
+        assert!(codes.iter().all(|code| code.len() == 19));
+        assert_ne!(codes[0], codes[1]);
+        assert_eq!(
+            normalize_recovery_code(&codes[0].to_lowercase().replace('-', " ")),
+            codes[0].replace('-', "")
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    common::{
+        app_state::AppState,
+        dto::RestApiResponse,
+        error::AppError,
+        jwt::{AuthMethod, Claims},
+        validated_json::ValidatedJson,
+    },
+    domain::api_key::{ApiKeyDto, ApiKeyServiceTrait, CreateApiKeyDto, CreatedApiKeyDto},
+};
+
+use axum::{
+    extract::{Path, State},
+    http::{header::CACHE_CONTROL, StatusCode},
+    response::IntoResponse,
+    Extension,
+};
+
+/// this function creates a router for creating an API key
+/// it returns the key once; only its hash is stored
+#[utoipa::path(
+    post,
+    path = "/auth/api-keys",
+    request_body = CreateApiKeyDto,
+    description = "Must be called with a user's access token; \
+        API keys and OAuth clients cannot create API keys.",
+    responses(
+        (status = 201, description = "API key created", body = CreatedApiKeyDto),
+        (status = 400, description = "Invalid name, lifetime, or a scope the caller does not hold"),
+        (status = 403, description = "Called with an API key or a client token")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "ApiKeys"
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+    ValidatedJson(payload): ValidatedJson<CreateApiKeyDto>,
+) -> Result<impl IntoResponse, AppError> {
+    // Otherwise a leaked key could be used to mint longer-lived ones,
+    // and a client has no user to own the key.
+    if auth_method != AuthMethod::Bearer {
+        return Err(AppError::Forbidden);
+    }
+
+    let api_key = state.api_key_service.create_api_key(&claims, payload).await?;
+    Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::created(api_key)))
+}
+
+/// this function creates a router for listing the API keys of the current user
+#[utoipa::path(
+    get,
+    path = "/auth/api-keys",
+    responses(
+        (status = 200, description = "API keys of the current user", body = [ApiKeyDto])
+    ),
+    security(("bearer_auth" = [])),
+    tag = "ApiKeys"
+)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let api_keys = state.api_key_service.list_api_keys(&claims).await?;
+    let api_keys: Vec<ApiKeyDto> = api_keys.into_iter().map(ApiKeyDto::from).collect();
+    Ok(RestApiResponse::success(api_keys))
+}
+
+/// this function creates a router for revoking an API key of the current user
+#[utoipa::path(
+    delete,
+    path = "/auth/api-keys/{id}",
+    params(("id" = String, Path, description = "ID of the API key")),
+    responses(
+        (status = 204, description = "API key revoked"),
+        (status = 404, description = "No active API key with this ID belongs to the caller")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "ApiKeys"
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    state.api_key_service.revoke_api_key(&claims, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,57 @@
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+use crate::common::app_state::AppState;
+
+use super::handlers;
+
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    OpenApi,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        super::handlers::create_api_key,
+        super::handlers::list_api_keys,
+        super::handlers::revoke_api_key,
+    ),
+    components(schemas(
+        crate::domain::api_key::CreateApiKeyDto,
+        crate::domain::api_key::ApiKeyDto,
+        crate::domain::api_key::CreatedApiKeyDto,
+    )),
+    tags(
+        (name = "ApiKeys", description = "API keys for machine clients")
+    ),
+    modifiers(&ApiKeyApiDoc)
+)]
+/// This struct is used to generate OpenAPI documentation for the API key routes.
+pub struct ApiKeyApiDoc;
+
+impl utoipa::Modify for ApiKeyApiDoc {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.as_mut().unwrap();
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("Input your `<your‑jwt>`"))
+                    .build(),
+            ),
+        )
+    }
+}
+
+/// This function creates a router for managing the API keys of the current user.
+/// It is nested under `/auth/api-keys` behind the JWT middleware.
+pub fn api_key_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_api_keys).post(handlers::create_api_key))
+        .route("/{id}", delete(handlers::revoke_api_key))
+}
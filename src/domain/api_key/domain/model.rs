@@ -0,0 +1,30 @@
+//! This module defines the `ApiKey` model used by machine clients to authenticate.
+
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+
+/// Represents a persisted API key.
+///
+/// Only the SHA-256 hash of the key is stored. A key acts on behalf of its user, limited to
+/// its scopes: the permissions it grants are the scopes the user still holds.
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiKey {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    /// The first characters of the key, to tell keys apart.
+    pub prefix: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Returns whether the key may be used to authenticate.
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at > Utc::now()
+    }
+}
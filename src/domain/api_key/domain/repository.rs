@@ -0,0 +1,47 @@
+//! This module defines the `ApiKeyRepository` trait, which abstracts
+//! the persistence of API keys.
+
+use std::future::Future;
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::model::ApiKey;
+
+/// Trait representing the repository contract for API keys.
+pub trait ApiKeyRepository: Send + Sync {
+    /// Inserts a new API key record within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        api_key: &ApiKey,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Finds an API key by the hash of the key, unless its user is deleted.
+    fn find_by_hash(
+        &self,
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> impl Future<Output = Result<Option<ApiKey>, sqlx::Error>> + Send;
+
+    /// Lists the API keys of a user, newest first.
+    fn find_by_user(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Vec<ApiKey>, sqlx::Error>> + Send;
+
+    /// Revokes an API key of a user. Returns `false` if the user has no such active key.
+    fn revoke(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+        user_id: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Records that a key was used. Writes at most once a minute per key.
+    fn touch(
+        &self,
+        pool: &PgPool,
+        id: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+}
@@ -0,0 +1,36 @@
+//! This module defines the API key service trait: managing a user's keys and
+//! authenticating requests that present one.
+
+use std::future::Future;
+
+use crate::{
+    common::{error::AppError, jwt::Claims},
+    domain::api_key::{ApiKey, CreateApiKeyDto, CreatedApiKeyDto},
+};
+
+/// Trait defining the contract for API key operations.
+pub trait ApiKeyServiceTrait: Send + Sync {
+    /// Creates an API key for the user described by `claims`.
+    /// The key itself is only returned here; afterwards only its prefix is known.
+    fn create_api_key(
+        &self,
+        claims: &Claims,
+        payload: CreateApiKeyDto,
+    ) -> impl Future<Output = Result<CreatedApiKeyDto, AppError>> + Send;
+
+    /// Lists the API keys of the user described by `claims`.
+    fn list_api_keys(
+        &self,
+        claims: &Claims,
+    ) -> impl Future<Output = Result<Vec<ApiKey>, AppError>> + Send;
+
+    /// Revokes an API key of the user described by `claims`.
+    fn revoke_api_key(
+        &self,
+        claims: &Claims,
+        id: &str,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Resolves a presented API key to the claims it grants.
+    fn authenticate(&self, key: &str) -> impl Future<Output = Result<Claims, AppError>> + Send;
+}
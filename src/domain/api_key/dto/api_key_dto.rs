@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::domain::api_key::ApiKey;
+
+/// Request body for creating an API key.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateApiKeyDto {
+    /// Describes what the key is used for, e.g. "nightly export".
+    #[validate(length(min = 1, max = 100, message = "Name must be 1 to 100 characters"))]
+    pub name: String,
+    /// Permissions the key grants; each must be held by the user.
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<String>,
+    /// Lifetime of the key in days. Defaults to `API_KEY_DEFAULT_TTL_DAYS`.
+    #[validate(range(min = 1, message = "Lifetime must be at least one day"))]
+    pub expires_in_days: Option<i64>,
+}
+
+/// An API key as listed to its owner. The key itself is never shown again after creation.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiKeyDto {
+    pub id: String,
+    pub name: String,
+    /// The first characters of the key.
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    #[serde(with = "crate::common::ts_format")]
+    pub expires_at: DateTime<Utc>,
+    #[serde(with = "crate::common::ts_format")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::common::ts_format::option")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::common::ts_format::option")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeyDto {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.id,
+            name: api_key.name,
+            prefix: api_key.prefix,
+            scopes: api_key.scopes,
+            expires_at: api_key.expires_at,
+            created_at: api_key.created_at,
+            last_used_at: api_key.last_used_at,
+            revoked_at: api_key.revoked_at,
+        }
+    }
+}
+
+/// A newly created API key, including the key itself.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiKeyDto {
+    /// Send as `Authorization: ApiKey <key>`. It is only shown once.
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyDto,
+}
@@ -0,0 +1,113 @@
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::domain::api_key::{ApiKey, ApiKeyRepository};
+
+#[derive(Clone)]
+pub struct ApiKeyRepo;
+
+impl ApiKeyRepository for ApiKeyRepo {
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        api_key: &ApiKey,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO api_keys
+            (id, user_id, name, prefix, token_hash, scopes, expires_at, created_at)
+            VALUES
+            ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(&api_key.id)
+        .bind(&api_key.user_id)
+        .bind(&api_key.name)
+        .bind(&api_key.prefix)
+        .bind(&api_key.token_hash)
+        .bind(&api_key.scopes)
+        .bind(api_key.expires_at)
+        .bind(api_key.created_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_hash(
+        &self,
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<Option<ApiKey>, sqlx::Error> {
+        sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT k.id, k.user_id, k.name, k.prefix, k.token_hash, k.scopes,
+                   k.expires_at, k.created_at, k.last_used_at, k.revoked_at
+              FROM api_keys k
+              JOIN users u ON u.id = k.user_id
+             WHERE k.token_hash = $1
+               AND u.deleted_at IS NULL
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(pool)
+        .await
+    }
+
+    async fn find_by_user(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> Result<Vec<ApiKey>, sqlx::Error> {
+        sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, user_id, name, prefix, token_hash, scopes,
+                   expires_at, created_at, last_used_at, revoked_at
+              FROM api_keys
+             WHERE user_id = $1
+             ORDER BY created_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    async fn revoke(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+        user_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE api_keys
+               SET revoked_at = NOW()
+             WHERE id = $1
+               AND user_id = $2
+               AND revoked_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn touch(&self, pool: &PgPool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE api_keys
+               SET last_used_at = NOW()
+             WHERE id = $1
+               AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+            "#,
+        )
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
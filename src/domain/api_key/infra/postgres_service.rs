@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    common::{config::Config, error::AppError, jwt::Claims, token_util},
+    domain::{
+        api_key::{
+            domain::{model::ApiKey, repository::ApiKeyRepository, service::ApiKeyServiceTrait},
+            dto::api_key_dto::{CreateApiKeyDto, CreatedApiKeyDto},
+            infra::postgres_repository::ApiKeyRepo,
+        },
+        auth::{UserAuthRepo, UserAuthRepository},
+    },
+};
+
+/// Every API key starts with this marker, which makes leaked keys easy to spot.
+const API_KEY_PREFIX: &str = "ak_";
+/// Characters of a key stored in the clear to tell keys apart: the marker plus 8 characters.
+const DISPLAY_PREFIX_LENGTH: usize = API_KEY_PREFIX.len() + 8;
+
+/// Service for managing API keys and authenticating requests made with them.
+#[derive(Clone)]
+pub struct PostgresApiKeyService {
+    pool: PgPool,
+    config: Config,
+    repo: ApiKeyRepo,
+    user_auth_repo: UserAuthRepo,
+}
+
+impl PostgresApiKeyService {
+    /// constructor for the service.
+    pub fn new(pool: PgPool, config: Config) -> Arc<Self> {
+        Arc::new(Self {
+            pool,
+            config,
+            repo: ApiKeyRepo,
+            user_auth_repo: UserAuthRepo,
+        })
+    }
+}
+
+impl ApiKeyServiceTrait for PostgresApiKeyService {
+    /// Creates a key limited to the requested scopes, each of which must be a permission of
+    /// the caller, and to a lifetime of at most `api_key_max_ttl_days`.
+    async fn create_api_key(
+        &self,
+        claims: &Claims,
+        payload: CreateApiKeyDto,
+    ) -> Result<CreatedApiKeyDto, AppError> {
+        let ttl_days = payload
+            .expires_in_days
+            .unwrap_or(self.config.api_key_default_ttl_days);
+        if ttl_days > self.config.api_key_max_ttl_days {
+            return Err(AppError::ValidationError(format!(
+                "API keys cannot be valid for more than {} days",
+                self.config.api_key_max_ttl_days
+            )));
+        }
+
+        let mut scopes = payload.scopes;
+        scopes.sort();
+        scopes.dedup();
+        if let Some(scope) = scopes.iter().find(|scope| !claims.has_permission(scope)) {
+            return Err(AppError::ValidationError(format!(
+                "Scope `{scope}` is not one of your permissions"
+            )));
+        }
+
+        let key = format!("{API_KEY_PREFIX}{}", token_util::generate_token());
+        let now = Utc::now();
+        let api_key = ApiKey {
+            id: Uuid::new_v4().to_string(),
+            user_id: claims.sub.clone(),
+            name: payload.name.trim().to_string(),
+            prefix: key[..DISPLAY_PREFIX_LENGTH].to_string(),
+            token_hash: token_util::hash_token(&key),
+            scopes,
+            expires_at: now + Duration::days(ttl_days),
+            created_at: now,
+            last_used_at: None,
+            revoked_at: None,
+        };
+
+        let mut tx = self.pool.begin().await?;
+        self.repo
+            .create(&mut tx, &api_key)
+            .await
+            .inspect_err(|e| tracing::error!("Error creating API key: {e}"))?;
+        tx.commit().await?;
+
+        tracing::info!(user_id = %claims.sub, api_key_id = %api_key.id, "API key created");
+        Ok(CreatedApiKeyDto {
+            key,
+            api_key: api_key.into(),
+        })
+    }
+
+    async fn list_api_keys(&self, claims: &Claims) -> Result<Vec<ApiKey>, AppError> {
+        let api_keys = self
+            .repo
+            .find_by_user(&self.pool, &claims.sub)
+            .await
+            .inspect_err(|e| tracing::error!("Error listing API keys: {e}"))?;
+        Ok(api_keys)
+    }
+
+    async fn revoke_api_key(&self, claims: &Claims, id: &str) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        let revoked = self.repo.revoke(&mut tx, id, &claims.sub).await?;
+        if !revoked {
+            return Err(AppError::NotFound("API key not found".into()));
+        }
+        tx.commit().await?;
+
+        tracing::info!(user_id = %claims.sub, api_key_id = id, "API key revoked");
+        Ok(())
+    }
+
+    /// Looks up an active key and builds claims equivalent to an access token of its user:
+    /// the user's current roles, and those of their current permissions the key is scoped to.
+    /// The key's ID serves as `jti` and its creation time as `iat`, so revoking the "token"
+    /// or all tokens of the user applies to API keys as well.
+    async fn authenticate(&self, key: &str) -> Result<Claims, AppError> {
+        if !key.starts_with(API_KEY_PREFIX) {
+            return Err(AppError::InvalidToken);
+        }
+
+        let api_key = self
+            .repo
+            .find_by_hash(&self.pool, &token_util::hash_token(key))
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving API key: {e}"))?
+            .filter(ApiKey::is_active)
+            .ok_or(AppError::InvalidToken)?;
+
+        let authorities = self
+            .user_auth_repo
+            .find_authorities(&self.pool, &api_key.user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user authorities: {e}"))?;
+        let permissions = authorities
+            .permissions
+            .into_iter()
+            .filter(|permission| api_key.scopes.contains(permission))
+            .collect();
+
+        if let Err(e) = self.repo.touch(&self.pool, &api_key.id).await {
+            tracing::warn!("Error recording API key use: {e}");
+        }
+
+        Ok(Claims {
+            sub: api_key.user_id,
+            exp: api_key.expires_at.timestamp() as usize,
+            iat: api_key.created_at.timestamp() as usize,
+            iat_ms: Some(api_key.created_at.timestamp_millis()),
+            jti: api_key.id,
+            iss: None,
+            aud: None,
+            nbf: None,
+            roles: authorities.roles,
+            permissions,
+            client_id: None,
+            sid: None,
+            act: None,
+        })
+    }
+}
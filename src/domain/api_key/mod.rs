@@ -0,0 +1,27 @@
+mod api {
+    mod handlers;
+    pub mod routes;
+}
+
+mod domain {
+    pub mod model;
+    pub mod repository;
+    pub mod service;
+}
+
+mod dto {
+    pub mod api_key_dto;
+}
+
+mod infra {
+    pub mod postgres_repository;
+    pub mod postgres_service;
+}
+
+// Re-export commonly used items for convenience
+pub use api::routes::{api_key_routes, ApiKeyApiDoc};
+pub use domain::model::ApiKey;
+pub use domain::repository::ApiKeyRepository;
+pub use domain::service::ApiKeyServiceTrait;
+pub use dto::api_key_dto::{ApiKeyDto, CreateApiKeyDto, CreatedApiKeyDto};
+pub use infra::postgres_service::PostgresApiKeyService as ApiKeyService;
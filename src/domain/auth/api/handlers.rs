@@ -1,14 +1,27 @@
 use crate::{
     common::{
         app_state::AppState,
+        client_ip::ClientInfo,
         dto::RestApiResponse,
         error::AppError,
-        jwt::{AuthBody, AuthPayload},
+        jwt::{AuthBody, AuthMethod, AuthPayload, Claims, KEYS},
+        validated_json::ValidatedJson,
     },
-    domain::auth::{AuthServiceTrait, AuthUserDto},
+    domain::auth::{
+        AuthServiceTrait, AuthUserDto, ChangePasswordDto, ImpersonateDto, ImportCredentialsDto,
+        ImportCredentialsResultDto, LoginResponse, LogoutDto, MagicLinkLoginDto,
+        MagicLinkRequestDto, MfaCodeDto, MfaLoginDto, MfaStatusDto, PasswordResetConfirmDto,
+        PasswordResetRequestDto, RecoveryCodesDto, RefreshTokenDto, RevokedSessionsDto, SessionDto,
+        SignupResponseDto, TotpEnrollmentDto,
+    },
+    domain::user::{SignupDto, UserServiceTrait},
+};
+use axum::extract::{Path, State};
+use axum::{
+    http::{header::CACHE_CONTROL, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
 };
-use axum::extract::State;
-use axum::{response::IntoResponse, Json};
 
 /// this function creates a router for creating user authentication registration
 /// it will create a new user in the database
@@ -16,30 +29,636 @@ use axum::{response::IntoResponse, Json};
     post,
     path = "/auth/register",
     request_body = AuthUserDto,
-    responses((status = 201, description = "User registered successfully")),
+    responses(
+        (status = 201, description = "User registered successfully"),
+        (status = 400, description = "Password does not satisfy the password policy"),
+        (status = 404, description = "User not found")
+    ),
     tag = "UserAuth"
 )]
 pub async fn create_user_auth(
     State(state): State<AppState>,
-    Json(payload): Json<AuthUserDto>,
+    ValidatedJson(payload): ValidatedJson<AuthUserDto>,
 ) -> Result<impl IntoResponse, AppError> {
     state.auth_service.create_user_auth(payload).await?;
     Ok(RestApiResponse::created(()))
 }
 
+/// this function creates a router for importing password hashes from other systems
+/// the users must exist and must not have a password yet
+#[utoipa::path(
+    post,
+    path = "/auth/credentials/import",
+    request_body = ImportCredentialsDto,
+    description = "Requires the `credentials:import` permission. Accepts bcrypt, PBKDF2-SHA256 \
+        (PHC or passlib format) and Argon2 hashes; they are replaced by an Argon2id hash when \
+        the user next logs in.",
+    responses(
+        (status = 200, description = "Imported and skipped credentials",
+            body = ImportCredentialsResultDto),
+        (status = 400, description = "Invalid input"),
+        (status = 403, description = "Missing `credentials:import` permission")
+    ),
+    security(("bearer_auth" = ["credentials:import"])),
+    tag = "UserAuth"
+)]
+pub async fn import_credentials(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<ImportCredentialsDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let result = state
+        .auth_service
+        .import_credentials(payload, claims.actor_id())
+        .await?;
+    Ok(RestApiResponse::success(result))
+}
+
+/// this function creates a router for signing up
+/// it creates the user and their password in one step and logs them in
+#[utoipa::path(
+    post,
+    path = "/auth/signup",
+    request_body = SignupDto,
+    description = "Available unless `SIGNUP_MODE` is `disabled`; with `invite-only`, \
+        `invitation` must hold an unused invitation. `login` is left out while the new \
+        email address has to be verified before logging in.",
+    responses(
+        (status = 201, description = "User created", body = SignupResponseDto),
+        (status = 400, description = "Invalid input, password rejected by the password policy, \
+            or a missing, invalid or expired invitation"),
+        (status = 403, description = "Signup is disabled"),
+        (status = 409, description = "Username is already taken")
+    ),
+    tag = "UserAuth"
+)]
+pub async fn signup(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<SignupDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = state.user_service.sign_up(payload).await?;
+
+    let login = if state.config.require_email_verification && user.email_verified_at.is_none() {
+        None
+    } else {
+        Some(
+            state
+                .auth_service
+                .login_authenticated_user(user.id.as_str(), &client)
+                .await?,
+        )
+    };
+    let response = SignupResponseDto {
+        user: user.into(),
+        login,
+    };
+    Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::created(response)))
+}
+
 /// this function creates a router for login user
-/// it will return a JWT token if the user is authenticated
+/// it will return a JWT token if the user is authenticated,
+/// or an MFA challenge if the user has two-factor authentication enabled
 #[utoipa::path(
     post,
     path = "/auth/login",
     request_body = AuthPayload,
-    responses((status = 200, description = "Login user", body = AuthBody)),
+    responses(
+        (
+            status = 200,
+            description = "Token pair, or an MFA challenge to complete at `/auth/login/mfa`",
+            body = LoginResponse
+        ),
+        (status = 401, description = "Wrong credentials, unknown user or temporarily locked account"),
+        (status = 429, description = "Too many failed logins from this client")
+    ),
     tag = "UserAuth"
 )]
 pub async fn login_user(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(payload): Json<AuthPayload>,
 ) -> Result<impl IntoResponse, AppError> {
-    let auth_body = state.auth_service.login_user(payload).await?;
+    let response = state.auth_service.login_user(payload, &client).await?;
+    Ok(RestApiResponse::success(response))
+}
+
+/// this function creates a router for requesting a magic login link
+/// it emails a single-use link if an account with the address exists
+#[utoipa::path(
+    post,
+    path = "/auth/magic-link",
+    request_body = MagicLinkRequestDto,
+    description = "Available when `MAGIC_LINK_ENABLED` is set.",
+    responses(
+        (status = 202, description = "Login link sent if an account with this email exists"),
+        (status = 400, description = "Invalid email address"),
+        (status = 403, description = "Magic link login is disabled"),
+        (status = 429, description = "Too many login links requested for this address")
+    ),
+    tag = "UserAuth"
+)]
+pub async fn request_magic_link(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<MagicLinkRequestDto>,
+) -> Result<StatusCode, AppError> {
+    state.auth_service.request_magic_link(payload).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// this function creates a router for logging in with a magic link
+/// it consumes the link's token and returns a JWT token or an MFA challenge like a password login
+#[utoipa::path(
+    post,
+    path = "/auth/magic-link/verify",
+    request_body = MagicLinkLoginDto,
+    responses(
+        (
+            status = 200,
+            description = "Token pair, or an MFA challenge to complete at `/auth/login/mfa`",
+            body = LoginResponse
+        ),
+        (status = 401, description = "Login token is invalid, expired or already used"),
+        (status = 403, description = "Magic link login is disabled, or the email address is \
+            not verified")
+    ),
+    tag = "UserAuth"
+)]
+pub async fn login_with_magic_link(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<MagicLinkLoginDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let response = state
+        .auth_service
+        .login_with_magic_link(payload, &client)
+        .await?;
+    Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::success(response)))
+}
+
+/// this function creates a router for the second step of a login with two-factor authentication
+/// it exchanges the MFA challenge and a TOTP or recovery code for a token pair
+#[utoipa::path(
+    post,
+    path = "/auth/login/mfa",
+    request_body = MfaLoginDto,
+    responses(
+        (status = 200, description = "Login completed", body = AuthBody),
+        (status = 401, description = "Wrong code, or the MFA token is invalid, expired or used up"),
+        (status = 429, description = "Too many failed logins from this client")
+    ),
+    tag = "UserAuth"
+)]
+pub async fn complete_mfa_login(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<MfaLoginDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_body = state
+        .auth_service
+        .complete_mfa_login(payload, &client)
+        .await?;
     Ok(RestApiResponse::success(auth_body))
 }
+
+/// this function creates a router for refreshing tokens
+/// it rotates the refresh token and returns a new token pair
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    request_body = RefreshTokenDto,
+    responses(
+        (status = 200, description = "Tokens refreshed", body = AuthBody),
+        (status = 401, description = "Refresh token is invalid, expired or was reused")
+    ),
+    tag = "UserAuth"
+)]
+pub async fn refresh_token(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<RefreshTokenDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_body = state.auth_service.refresh_token(payload, &client).await?;
+    Ok(RestApiResponse::success(auth_body))
+}
+
+/// this function creates a router for logging out
+/// it revokes the presented access token and its session and, optionally, the refresh token family
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    request_body(content = Option<LogoutDto>, description = "Optional refresh token to revoke"),
+    responses(
+        (status = 204, description = "Logged out"),
+        (status = 401, description = "Invalid token")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    payload: Option<Json<LogoutDto>>,
+) -> Result<StatusCode, AppError> {
+    let payload = payload
+        .map(|Json(payload)| payload)
+        .unwrap_or(LogoutDto { refresh_token: None });
+    state.auth_service.logout(&claims, payload).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// this function creates a router for listing the active sessions of the current user
+/// every login starts a session, which lasts as long as its refresh tokens
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    description = "Must be called with a user's access token.",
+    responses(
+        (status = 200, description = "Active sessions, most recently used first",
+            body = Vec<SessionDto>),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Called with an API key or a client token")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_method != AuthMethod::Bearer {
+        return Err(AppError::Forbidden);
+    }
+
+    let sessions: Vec<SessionDto> = state
+        .auth_service
+        .list_sessions(&claims.sub)
+        .await?
+        .into_iter()
+        .map(|session| SessionDto::from_session(session, claims.sid.as_deref()))
+        .collect();
+    Ok(RestApiResponse::success(sessions))
+}
+
+/// this function creates a router for revoking a session of the current user
+/// its refresh tokens stop working and its access tokens are rejected right away
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions/{id}",
+    description = "Must be called with a user's access token.",
+    params(("id" = String, Path, description = "ID of the session to revoke")),
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Called with an API key or a client token"),
+        (status = 404, description = "Session not found or already revoked")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    if auth_method != AuthMethod::Bearer {
+        return Err(AppError::Forbidden);
+    }
+
+    state.auth_service.revoke_session(&claims.sub, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// this function creates a router for revoking every other session of the current user
+/// the session the request is made with stays active
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions",
+    description = "Must be called with a user's access token.",
+    responses(
+        (status = 200, description = "Other sessions revoked", body = RevokedSessionsDto),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Called with an API key or a client token")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_method != AuthMethod::Bearer {
+        return Err(AppError::Forbidden);
+    }
+
+    let revoked = state.auth_service.revoke_other_sessions(&claims).await?;
+    Ok(RestApiResponse::success(RevokedSessionsDto { revoked }))
+}
+
+/// this function creates a router for changing the password of the current user
+/// it verifies the current password before storing the new one
+#[utoipa::path(
+    put,
+    path = "/auth/password",
+    request_body = ChangePasswordDto,
+    responses(
+        (
+            status = 200,
+            description = "Password changed, new token pair for the current session",
+            body = AuthBody
+        ),
+        (status = 204, description = "Password changed"),
+        (status = 400, description = "New password does not satisfy the password policy"),
+        (status = 401, description = "Current password is wrong or the token is invalid"),
+        (status = 403, description = "Not allowed with an impersonation token")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<ChangePasswordDto>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_method == AuthMethod::Impersonation {
+        return Err(AppError::Forbidden);
+    }
+
+    let auth_body = state
+        .auth_service
+        .change_password(&claims, payload, &client)
+        .await?;
+    Ok(match auth_body {
+        Some(auth_body) => {
+            ([(CACHE_CONTROL, "no-store")], RestApiResponse::success(auth_body)).into_response()
+        }
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
+
+/// this function creates a router for requesting a password reset
+/// it emails a reset link if an account with the address exists, and always returns 202
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/request",
+    request_body = PasswordResetRequestDto,
+    responses(
+        (status = 202, description = "Reset link sent if an account with this email exists"),
+        (status = 400, description = "Invalid email address")
+    ),
+    tag = "UserAuth"
+)]
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<PasswordResetRequestDto>,
+) -> Result<StatusCode, AppError> {
+    state.auth_service.request_password_reset(payload).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// this function creates a router for confirming a password reset
+/// it sets the new password and consumes the reset token
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/confirm",
+    request_body = PasswordResetConfirmDto,
+    responses(
+        (status = 204, description = "Password reset"),
+        (status = 400, description = "New password does not satisfy the password policy"),
+        (status = 401, description = "Reset token is invalid, expired or already used")
+    ),
+    tag = "UserAuth"
+)]
+pub async fn confirm_password_reset(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<PasswordResetConfirmDto>,
+) -> Result<StatusCode, AppError> {
+    state.auth_service.confirm_password_reset(payload).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// this function creates a router for the two-factor authentication status of the current user
+#[utoipa::path(
+    get,
+    path = "/auth/mfa",
+    responses(
+        (status = 200, description = "Two-factor authentication status", body = MfaStatusDto),
+        (status = 401, description = "Invalid token")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn mfa_status(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let status = state.auth_service.mfa_status(&claims).await?;
+    Ok(RestApiResponse::success(status))
+}
+
+/// this function creates a router for starting TOTP enrollment
+/// it returns a new secret that has to be confirmed with a code before it is used
+#[utoipa::path(
+    post,
+    path = "/auth/mfa/totp",
+    responses(
+        (status = 200, description = "Pending TOTP secret", body = TotpEnrollmentDto),
+        (status = 400, description = "Two-factor authentication is already enabled"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Not allowed with an impersonation token")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn enroll_totp(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_method == AuthMethod::Impersonation {
+        return Err(AppError::Forbidden);
+    }
+
+    let enrollment = state.auth_service.enroll_totp(&claims).await?;
+    Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::success(enrollment)))
+}
+
+/// this function creates a router for confirming TOTP enrollment
+/// it enables two-factor authentication and returns the recovery codes
+#[utoipa::path(
+    post,
+    path = "/auth/mfa/totp/confirm",
+    request_body = MfaCodeDto,
+    responses(
+        (status = 200, description = "Two-factor authentication enabled", body = RecoveryCodesDto),
+        (status = 400, description = "No pending TOTP secret, or already enabled"),
+        (status = 401, description = "Wrong code or invalid token"),
+        (status = 403, description = "Not allowed with an impersonation token")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+    ValidatedJson(payload): ValidatedJson<MfaCodeDto>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_method == AuthMethod::Impersonation {
+        return Err(AppError::Forbidden);
+    }
+
+    let recovery_codes = state.auth_service.confirm_totp(&claims, payload).await?;
+    Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::success(recovery_codes)))
+}
+
+/// this function creates a router for disabling two-factor authentication
+/// it requires a current TOTP code or an unused recovery code
+#[utoipa::path(
+    delete,
+    path = "/auth/mfa/totp",
+    request_body = MfaCodeDto,
+    responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 400, description = "Two-factor authentication is not enabled"),
+        (status = 401, description = "Wrong code, locked account or invalid token"),
+        (status = 403, description = "Not allowed with an impersonation token"),
+        (status = 429, description = "Too many failed logins from this client")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn disable_totp(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<MfaCodeDto>,
+) -> Result<StatusCode, AppError> {
+    if auth_method == AuthMethod::Impersonation {
+        return Err(AppError::Forbidden);
+    }
+
+    state
+        .auth_service
+        .disable_totp(&claims, payload, &client)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// this function creates a router for regenerating recovery codes
+/// it invalidates the previous codes and requires a current TOTP code or an unused recovery code
+#[utoipa::path(
+    post,
+    path = "/auth/mfa/recovery-codes",
+    request_body = MfaCodeDto,
+    responses(
+        (status = 200, description = "New recovery codes", body = RecoveryCodesDto),
+        (status = 400, description = "Two-factor authentication is not enabled"),
+        (status = 401, description = "Wrong code, locked account or invalid token"),
+        (status = 403, description = "Not allowed with an impersonation token"),
+        (status = 429, description = "Too many failed logins from this client")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "UserAuth"
+)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<MfaCodeDto>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_method == AuthMethod::Impersonation {
+        return Err(AppError::Forbidden);
+    }
+
+    let recovery_codes = state
+        .auth_service
+        .regenerate_recovery_codes(&claims, payload, &client)
+        .await?;
+    Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::success(recovery_codes)))
+}
+
+/// this function creates a router for revoking all tokens of a user
+/// it is meant for incident response, e.g. when an account is compromised
+#[utoipa::path(
+    post,
+    path = "/auth/users/{id}/revoke-tokens",
+    description = "Requires the `tokens:revoke` permission.",
+    params(("id" = String, Path, description = "ID of the user whose tokens are revoked")),
+    responses(
+        (status = 204, description = "All tokens of the user revoked"),
+        (status = 403, description = "Missing `tokens:revoke` permission"),
+        (status = 404, description = "User not found")
+    ),
+    security(("bearer_auth" = ["tokens:revoke"])),
+    tag = "UserAuth"
+)]
+pub async fn revoke_user_tokens(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    state
+        .auth_service
+        .revoke_all_tokens(&id, claims.actor_id())
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// this function creates a router for impersonating a user
+/// it returns a short-lived access token for the user that names the caller as its actor
+#[utoipa::path(
+    post,
+    path = "/auth/users/{id}/impersonate",
+    request_body = ImpersonateDto,
+    description = "Requires the `users:impersonate` permission and a user access token; \
+        impersonation tokens, API keys and OAuth clients cannot impersonate. The token carries \
+        the caller in an `act` claim (RFC 8693), expires after `IMPERSONATION_TOKEN_TTL_SECS` \
+        and cannot be refreshed.",
+    params(("id" = String, Path, description = "ID of the user to impersonate")),
+    responses(
+        (status = 200, description = "Impersonation token", body = AuthBody),
+        (status = 400, description = "Missing reason, or the caller's own ID"),
+        (status = 403, description = "Missing `users:impersonate` permission, not a user access \
+            token, or the user may impersonate others themselves"),
+        (status = 404, description = "User not found")
+    ),
+    security(("bearer_auth" = ["users:impersonate"])),
+    tag = "UserAuth"
+)]
+pub async fn impersonate_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<ImpersonateDto>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_method != AuthMethod::Bearer {
+        return Err(AppError::Forbidden);
+    }
+
+    let auth_body = state
+        .auth_service
+        .impersonate_user(&claims, &id, payload)
+        .await?;
+    Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::success(auth_body)))
+}
+
+/// this function creates a router for the JSON Web Key Set
+/// it publishes the public keys that downstream services can use to verify our tokens
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    responses((status = 200, description = "JSON Web Key Set (RFC 7517)", body = Object)),
+    tag = "UserAuth"
+)]
+pub async fn jwks() -> impl IntoResponse {
+    ([(CACHE_CONTROL, "public, max-age=300")], Json(KEYS.jwks()))
+}
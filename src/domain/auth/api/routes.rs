@@ -1,33 +1,154 @@
-use axum::{routing::post, Router};
-use crate::common::app_state::AppState;
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+use crate::common::{
+    app_state::AppState,
+    rbac::{permissions, RequirePermission},
+};
 
 use super::handlers;
 
-use utoipa::OpenApi;
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    OpenApi,
+};
 
 /// Import the necessary modules for OpenAPI documentation generation
 #[derive(OpenApi)]
 #[openapi(
     paths(
         super::handlers::login_user,
+        super::handlers::complete_mfa_login,
+        super::handlers::request_magic_link,
+        super::handlers::login_with_magic_link,
         super::handlers::create_user_auth,
+        super::handlers::signup,
+        super::handlers::refresh_token,
+        super::handlers::logout,
+        super::handlers::list_sessions,
+        super::handlers::revoke_session,
+        super::handlers::revoke_other_sessions,
+        super::handlers::change_password,
+        super::handlers::request_password_reset,
+        super::handlers::confirm_password_reset,
+        super::handlers::mfa_status,
+        super::handlers::enroll_totp,
+        super::handlers::confirm_totp,
+        super::handlers::disable_totp,
+        super::handlers::regenerate_recovery_codes,
+        super::handlers::revoke_user_tokens,
+        super::handlers::impersonate_user,
+        super::handlers::import_credentials,
+        super::handlers::jwks,
     ),
     components(schemas(
         crate::domain::auth::AuthUserDto,
+        crate::domain::auth::SignupResponseDto,
+        crate::domain::user::SignupDto,
+        crate::domain::auth::RefreshTokenDto,
+        crate::domain::auth::LogoutDto,
+        crate::domain::auth::SessionDto,
+        crate::domain::auth::RevokedSessionsDto,
+        crate::domain::auth::ImpersonateDto,
+        crate::domain::auth::ImportCredentialsDto,
+        crate::domain::auth::ImportedCredentialDto,
+        crate::domain::auth::ImportCredentialsResultDto,
+        crate::domain::auth::SkippedCredentialDto,
+        crate::domain::auth::ChangePasswordDto,
+        crate::domain::auth::PasswordResetRequestDto,
+        crate::domain::auth::PasswordResetConfirmDto,
+        crate::domain::auth::LoginResponse,
+        crate::domain::auth::MfaChallengeDto,
+        crate::domain::auth::MfaLoginDto,
+        crate::domain::auth::MagicLinkRequestDto,
+        crate::domain::auth::MagicLinkLoginDto,
+        crate::domain::auth::MfaCodeDto,
+        crate::domain::auth::MfaStatusDto,
+        crate::domain::auth::TotpEnrollmentDto,
+        crate::domain::auth::RecoveryCodesDto,
         crate::common::jwt::AuthPayload,
         crate::common::jwt::AuthBody,
     )),
     tags(
         (name = "UserAuth", description = "User authentication endpoints")
-    )
+    ),
+    modifiers(&UserAuthApiDoc)
 )]
 /// This struct is used to generate OpenAPI documentation for the user authentication routes.
 pub struct UserAuthApiDoc;
 
+impl utoipa::Modify for UserAuthApiDoc {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.as_mut().unwrap();
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("Input your `<your‑jwt>`"))
+                    .build(),
+            ),
+        )
+    }
+}
+
 /// This function creates a router for the user authentication routes.
 /// It defines the routes and their corresponding handlers.
 pub fn user_auth_routes() -> Router<AppState> {
     Router::new()
         .route("/login", post(handlers::login_user))
+        .route("/login/mfa", post(handlers::complete_mfa_login))
+        .route("/magic-link", post(handlers::request_magic_link))
+        .route("/magic-link/verify", post(handlers::login_with_magic_link))
         .route("/register", post(handlers::create_user_auth))
+        .route("/signup", post(handlers::signup))
+        .route("/refresh", post(handlers::refresh_token))
+        .route("/password-reset/request", post(handlers::request_password_reset))
+        .route("/password-reset/confirm", post(handlers::confirm_password_reset))
+}
+
+/// This function creates a router for the authentication routes that require a valid JWT.
+/// It is nested under `/auth` behind the JWT middleware.
+pub fn user_auth_protected_routes() -> Router<AppState> {
+    Router::new()
+        .route("/logout", post(handlers::logout))
+        .route(
+            "/sessions",
+            get(handlers::list_sessions).delete(handlers::revoke_other_sessions),
+        )
+        .route("/sessions/{id}", delete(handlers::revoke_session))
+        .route(
+            "/users/{id}/revoke-tokens",
+            post(handlers::revoke_user_tokens)
+                .route_layer(RequirePermission(permissions::TOKENS_REVOKE)),
+        )
+}
+
+/// This function creates a router for the authentication routes that require a valid JWT and
+/// carry credentials in their requests or responses. It is nested under `/auth` behind the JWT
+/// middleware, outside the router that logs bodies.
+pub fn user_auth_credential_routes() -> Router<AppState> {
+    Router::new()
+        .route("/password", put(handlers::change_password))
+        .route("/mfa", get(handlers::mfa_status))
+        .route("/mfa/totp", post(handlers::enroll_totp).delete(handlers::disable_totp))
+        .route("/mfa/totp/confirm", post(handlers::confirm_totp))
+        .route("/mfa/recovery-codes", post(handlers::regenerate_recovery_codes))
+        .route(
+            "/users/{id}/impersonate",
+            post(handlers::impersonate_user)
+                .route_layer(RequirePermission(permissions::USERS_IMPERSONATE)),
+        )
+        .route(
+            "/credentials/import",
+            post(handlers::import_credentials)
+                .route_layer(RequirePermission(permissions::CREDENTIALS_IMPORT)),
+        )
+}
+
+/// This function creates a router for the public `/.well-known` discovery routes.
+pub fn well_known_routes() -> Router<AppState> {
+    Router::new().route("/jwks.json", get(handlers::jwks))
 }
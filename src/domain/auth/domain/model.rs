@@ -1,6 +1,7 @@
 //! This module defines the `UserAuth` model used for representing
 //! authentication data tied to a user.
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
@@ -9,4 +10,149 @@ use sqlx::prelude::FromRow;
 pub struct UserAuth {
     pub user_id: String,
     pub password_hash: String,
+    /// Consecutive failed logins since the last successful one or lockout.
+    #[serde(skip)]
+    #[sqlx(default)]
+    pub failed_login_attempts: i32,
+    /// Logins are refused until this instant.
+    #[serde(skip)]
+    #[sqlx(default)]
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl UserAuth {
+    /// Creates an authentication record without failed logins.
+    pub fn new(user_id: impl Into<String>, password_hash: impl Into<String>) -> Self {
+        Self {
+            user_id: user_id.into(),
+            password_hash: password_hash.into(),
+            failed_login_attempts: 0,
+            locked_until: None,
+        }
+    }
+
+    /// Returns whether logins are currently refused.
+    pub fn is_locked(&self) -> bool {
+        self.locked_until.is_some_and(|until| until > Utc::now())
+    }
+}
+
+/// The identifiers of a user: used to reach them by email and
+/// checked against when they choose a password.
+#[derive(Debug, Clone, FromRow)]
+pub struct UserIdentity {
+    pub id: String,
+    pub username: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+}
+
+/// Roles of a user and the permissions they grant, as embedded in issued tokens.
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct UserAuthorities {
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+/// Represents a persisted refresh token.
+///
+/// Only the SHA-256 hash of the opaque token is stored. Every token belongs to a
+/// family that starts at login; rotating a token adds a new member to the family,
+/// which lets the whole chain be revoked if an already-used token is replayed.
+#[derive(Debug, Clone, FromRow)]
+pub struct RefreshToken {
+    pub id: String,
+    pub user_id: String,
+    pub family_id: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Revocation state of an access token, as recorded in the database.
+#[derive(Debug, Clone, FromRow)]
+pub struct TokenRevocationStatus {
+    /// Whether this specific token (`jti`) has been revoked.
+    pub revoked: bool,
+    /// Tokens issued to the user before this instant are revoked.
+    pub revoked_before: Option<DateTime<Utc>>,
+    /// Whether the session the token was issued for has been revoked.
+    pub session_revoked: bool,
+}
+
+/// A login session of a user, started by a login and kept alive by refreshing tokens.
+///
+/// Its ID is the ID of the refresh token family started by the login, and it is active
+/// while the family still holds a usable refresh token. Access tokens carry the ID as `sid`.
+#[derive(Debug, Clone, FromRow)]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    /// The most recent access token issued for the session.
+    pub access_jti: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Represents a persisted password reset token.
+///
+/// Like refresh tokens, only the SHA-256 hash of the emailed token is stored.
+/// A token can be used once and only until it expires.
+#[derive(Debug, Clone, FromRow)]
+pub struct PasswordResetToken {
+    pub id: String,
+    pub user_id: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+/// Represents a persisted magic link token, emailed for a login without a password.
+///
+/// Only the SHA-256 hash of the token is stored. A token can be used once and only
+/// until it expires.
+#[derive(Debug, Clone, FromRow)]
+pub struct MagicLinkToken {
+    pub id: String,
+    pub user_id: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+/// A user's TOTP secret. Two-factor authentication is enabled once the user has confirmed
+/// the secret with a first code; until then the secret is pending and can be replaced.
+#[derive(Debug, Clone, FromRow)]
+pub struct UserTotp {
+    pub user_id: String,
+    /// Base32-encoded shared secret.
+    pub secret: String,
+    pub enabled_at: Option<DateTime<Utc>>,
+    /// The latest time step whose code was accepted; older codes are rejected.
+    pub last_used_step: Option<i64>,
+}
+
+impl UserTotp {
+    /// Returns whether logins require a second factor.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
+
+/// Represents a persisted MFA challenge, issued by a password login of a user with
+/// two-factor authentication and exchanged for tokens together with a second factor.
+///
+/// Only the SHA-256 hash of the challenge token is stored.
+#[derive(Debug, Clone, FromRow)]
+pub struct MfaChallenge {
+    pub id: String,
+    pub user_id: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub failed_attempts: i32,
+    pub used_at: Option<DateTime<Utc>>,
 }
@@ -3,7 +3,12 @@
 
 use std::future::Future;
 
-use super::model::UserAuth;
+use chrono::{DateTime, Utc};
+
+use super::model::{
+    MagicLinkToken, MfaChallenge, PasswordResetToken, RefreshToken, Session,
+    TokenRevocationStatus, UserAuth, UserAuthorities, UserIdentity, UserTotp,
+};
 
 use sqlx::{PgPool, Postgres, Transaction};
 
@@ -11,17 +16,390 @@ use sqlx::{PgPool, Postgres, Transaction};
 /// Enables decoupling of business logic from direct database interaction.
 pub trait UserAuthRepository: Send + Sync {
     /// Finds a user authentication record by the user's username.
-    /// Returns `Ok(Some(UserAuth))` if found, or `Ok(None)` if not found.
+    /// Returns `Ok(Some(UserAuth))` if found, or `Ok(None)` if not found or the user is deleted.
     fn find_by_user_name(
         &self,
         pool: PgPool,
         user_name: String,
     ) -> impl Future<Output = Result<Option<UserAuth>, sqlx::Error>> + Send;
 
+    /// Finds a user authentication record by the user's ID.
+    fn find_by_user_id(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Option<UserAuth>, sqlx::Error>> + Send;
+
+    /// Finds the username and email address of a user that is not deleted.
+    fn find_identity(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Option<UserIdentity>, sqlx::Error>> + Send;
+
+    /// Finds a user with credentials by their email address, ignoring deleted users.
+    /// Returns `None` when the address belongs to more than one such user.
+    fn find_identity_by_email(
+        &self,
+        pool: &PgPool,
+        email: &str,
+    ) -> impl Future<Output = Result<Option<UserIdentity>, sqlx::Error>> + Send;
+
     /// Inserts a new user authentication record into the database using a transaction.
     fn create(
         &self,
         tx: &mut Transaction<'_, Postgres>,
         user_auth: UserAuth,
     ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Inserts an authentication record with a password hash made elsewhere, unless the user
+    /// does not exist, is deleted or already has one. Returns `true` if the record was inserted.
+    fn import(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_auth: &UserAuth,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Replaces the password hash of a user and clears any login lockout.
+    /// Returns `true` if a record was updated.
+    fn update_password(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        password_hash: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Replaces a password hash with a new hash of the same password, unless the password
+    /// was changed in the meantime. Returns `true` if the hash was replaced.
+    fn rehash_password(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+        old_hash: &str,
+        new_hash: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Counts a failed login. Once `max_attempts` consecutive failures are reached the
+    /// account is locked for `lockout_secs` and the counter starts over.
+    /// Returns the instant the account is locked until, if it is now locked.
+    fn record_failed_login(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+        max_attempts: i32,
+        lockout_secs: i64,
+    ) -> impl Future<Output = Result<Option<DateTime<Utc>>, sqlx::Error>> + Send;
+
+    /// Clears the failed login counter and lockout of a user after a successful login.
+    fn reset_failed_logins(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Finds the roles of a user and the permissions granted by them.
+    fn find_authorities(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> impl Future<Output = Result<UserAuthorities, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for refresh tokens.
+pub trait RefreshTokenRepository: Send + Sync {
+    /// Inserts a new refresh token record within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: &RefreshToken,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Finds a refresh token by its hash and locks the row for the rest of the transaction,
+    /// so concurrent rotations of the same token are serialized.
+    fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> impl Future<Output = Result<Option<RefreshToken>, sqlx::Error>> + Send;
+
+    /// Marks a refresh token as used (rotated).
+    fn mark_used(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Revokes every token in a family. Returns the number of tokens revoked.
+    fn revoke_family(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        family_id: &str,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+
+    /// Revokes every outstanding refresh token of a user. Returns the number of tokens revoked.
+    fn revoke_all_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for access token revocations.
+pub trait TokenRevocationRepository: Send + Sync {
+    /// Records a single revoked access token. The record can be discarded once `expires_at` passes.
+    fn revoke_token(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        jti: &str,
+        user_id: &str,
+        expires_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Revokes every access token issued to the user before `revoked_before`, which replaces
+    /// an earlier cut-off. The instant is taken from the clock the tokens are stamped with,
+    /// so tokens issued right after it in the same transaction stay valid.
+    fn revoke_all_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        revoked_before: DateTime<Utc>,
+        revoked_by: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Looks up the revocation status of a token, its user and, if given, its session.
+    fn find_status(
+        &self,
+        pool: &PgPool,
+        jti: &str,
+        user_id: &str,
+        session_id: Option<&str>,
+    ) -> impl Future<Output = Result<TokenRevocationStatus, sqlx::Error>> + Send;
+
+    /// Deletes revocation records of tokens that have expired anyway.
+    fn delete_expired(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for login sessions.
+pub trait SessionRepository: Send + Sync {
+    /// Inserts a session, or records a new access token, IP address and user agent
+    /// for an existing one.
+    fn save(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        session: &Session,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Records that a session was used.
+    fn touch(
+        &self,
+        pool: &PgPool,
+        id: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Lists the active sessions of a user, most recently used first.
+    fn find_active_by_user(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Vec<Session>, sqlx::Error>> + Send;
+
+    /// Marks an active session of a user as revoked. Returns `true` if a session was revoked.
+    fn revoke(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+        user_id: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Marks every session of a user as revoked, except `except` if given.
+    /// Returns the IDs of the sessions that were revoked.
+    fn revoke_all_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        except: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<String>, sqlx::Error>> + Send;
+
+    /// Deletes sessions of a user that have no usable refresh token left and were last seen
+    /// more than `max_token_age_secs` ago, so none of their access tokens are still valid.
+    fn delete_stale(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        max_token_age_secs: i64,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for password reset tokens.
+pub trait PasswordResetRepository: Send + Sync {
+    /// Inserts a new password reset token record within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: &PasswordResetToken,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Finds a password reset token by its hash and locks the row for the rest of the transaction.
+    fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> impl Future<Output = Result<Option<PasswordResetToken>, sqlx::Error>> + Send;
+
+    /// Marks every unused token of a user as used, so only the newest one or none stays valid.
+    fn invalidate_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+
+    /// Deletes tokens that have expired or were used.
+    fn delete_stale(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for magic link tokens.
+pub trait MagicLinkRepository: Send + Sync {
+    /// Inserts a new magic link token record within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: &MagicLinkToken,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Finds a magic link token by its hash and locks the row for the rest of the transaction.
+    fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> impl Future<Output = Result<Option<MagicLinkToken>, sqlx::Error>> + Send;
+
+    /// Marks every unused token of a user as used, so only the newest one or none stays valid.
+    fn invalidate_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+
+    /// Deletes tokens that have expired or were used.
+    fn delete_stale(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for TOTP secrets and recovery codes.
+pub trait MfaRepository: Send + Sync {
+    /// Finds the TOTP secret of a user.
+    fn find_totp(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Option<UserTotp>, sqlx::Error>> + Send;
+
+    /// Finds the TOTP secret of a user and locks the row for the rest of the transaction,
+    /// so concurrent requests cannot accept the same code twice.
+    fn find_totp_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Option<UserTotp>, sqlx::Error>> + Send;
+
+    /// Stores a new pending secret, replacing a pending one.
+    /// Returns `false` if two-factor authentication is already enabled for the user.
+    fn save_pending_totp(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        secret: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Marks the user's secret as confirmed, enabling two-factor authentication.
+    fn enable_totp(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        step: i64,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Records the time step of an accepted code.
+    fn record_totp_step(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        step: i64,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Deletes the user's secret and recovery codes, disabling two-factor authentication.
+    fn delete_totp(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Replaces the user's recovery codes with the given code hashes.
+    fn replace_recovery_codes(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        code_hashes: &[String],
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Marks an unused recovery code of the user as used.
+    /// Returns `false` if the user has no such unused code.
+    fn use_recovery_code(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        code_hash: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Counts the user's unused recovery codes.
+    fn count_recovery_codes(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> impl Future<Output = Result<i64, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for MFA challenges.
+pub trait MfaChallengeRepository: Send + Sync {
+    /// Inserts a new challenge record within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        challenge: &MfaChallenge,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Finds a challenge by its hash and locks the row for the rest of the transaction.
+    fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> impl Future<Output = Result<Option<MfaChallenge>, sqlx::Error>> + Send;
+
+    /// Counts a wrong code against the challenge.
+    fn record_failed_attempt(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Marks a challenge as used.
+    fn mark_used(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Deletes challenges that have expired or were used.
+    fn delete_stale(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
 }
@@ -5,10 +5,16 @@ use std::future::Future;
 
 use crate::{
     common::{
+        client_ip::ClientInfo,
         error::AppError,
-        jwt::{AuthBody, AuthPayload},
+        jwt::{AuthBody, AuthPayload, Claims},
+    },
+    domain::auth::{
+        AuthUserDto, ChangePasswordDto, ImpersonateDto, ImportCredentialsDto,
+        ImportCredentialsResultDto, LoginResponse, LogoutDto, MagicLinkLoginDto,
+        MagicLinkRequestDto, MfaCodeDto, MfaLoginDto, MfaStatusDto, PasswordResetConfirmDto,
+        PasswordResetRequestDto, RecoveryCodesDto, RefreshTokenDto, Session, TotpEnrollmentDto,
     },
-    domain::auth::AuthUserDto,
 };
 
 /// Trait defining the contract for authentication-related operations.
@@ -20,9 +26,174 @@ pub trait AuthServiceTrait: Send + Sync {
         auth_user: AuthUserDto,
     ) -> impl Future<Output = Result<(), AppError>> + Send;
 
-    /// Authenticates a user and returns a JWT token payload on success.
+    /// Stores password hashes made by other systems for existing users without credentials.
+    /// Unsupported hashes and users that do not exist or already have a password are skipped.
+    fn import_credentials(
+        &self,
+        payload: ImportCredentialsDto,
+        imported_by: &str,
+    ) -> impl Future<Output = Result<ImportCredentialsResultDto, AppError>> + Send;
+
+    /// Authenticates a user and returns a JWT token payload on success, or an MFA challenge
+    /// if the user has two-factor authentication enabled.
+    /// `client` is used to throttle clients with many failed logins and recorded with the session.
     fn login_user(
         &self,
         auth_payload: AuthPayload,
+        client: &ClientInfo,
+    ) -> impl Future<Output = Result<LoginResponse, AppError>> + Send;
+
+    /// Logs in a user whose identity was established some other way, e.g. by an external
+    /// identity provider or at signup, returning a JWT token payload or an MFA challenge
+    /// like `login_user`.
+    fn login_authenticated_user(
+        &self,
+        user_id: &str,
+        client: &ClientInfo,
+    ) -> impl Future<Output = Result<LoginResponse, AppError>> + Send;
+
+    /// Emails a single-use login link to the user with the given email address, if any.
+    /// Succeeds whether or not such a user exists, unless the address is rate limited.
+    fn request_magic_link(
+        &self,
+        payload: MagicLinkRequestDto,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Logs in with the token of a magic link, returning a JWT token payload or an MFA
+    /// challenge like `login_user`.
+    fn login_with_magic_link(
+        &self,
+        payload: MagicLinkLoginDto,
+        client: &ClientInfo,
+    ) -> impl Future<Output = Result<LoginResponse, AppError>> + Send;
+
+    /// Completes a login that returned an MFA challenge, using a TOTP or recovery code.
+    fn complete_mfa_login(
+        &self,
+        payload: MfaLoginDto,
+        client: &ClientInfo,
+    ) -> impl Future<Output = Result<AuthBody, AppError>> + Send;
+
+    /// Returns the two-factor authentication status of the user described by `claims`.
+    fn mfa_status(
+        &self,
+        claims: &Claims,
+    ) -> impl Future<Output = Result<MfaStatusDto, AppError>> + Send;
+
+    /// Generates a new TOTP secret for the user described by `claims`.
+    /// Two-factor authentication is only enabled once the secret is confirmed.
+    fn enroll_totp(
+        &self,
+        claims: &Claims,
+    ) -> impl Future<Output = Result<TotpEnrollmentDto, AppError>> + Send;
+
+    /// Enables two-factor authentication after checking a code of the pending secret,
+    /// and returns the initial recovery codes.
+    fn confirm_totp(
+        &self,
+        claims: &Claims,
+        payload: MfaCodeDto,
+    ) -> impl Future<Output = Result<RecoveryCodesDto, AppError>> + Send;
+
+    /// Disables two-factor authentication after checking a TOTP or recovery code.
+    /// Wrong codes count as failed logins of the user and of the client's IP.
+    fn disable_totp(
+        &self,
+        claims: &Claims,
+        payload: MfaCodeDto,
+        client: &ClientInfo,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Replaces the recovery codes after checking a TOTP or recovery code.
+    /// Wrong codes count as failed logins of the user and of the client's IP.
+    fn regenerate_recovery_codes(
+        &self,
+        claims: &Claims,
+        payload: MfaCodeDto,
+        client: &ClientInfo,
+    ) -> impl Future<Output = Result<RecoveryCodesDto, AppError>> + Send;
+
+    /// Rotates a refresh token and returns a new access/refresh token pair.
+    /// Presenting a token that was already rotated revokes its whole token family.
+    /// `client` is recorded as the latest client of the session.
+    fn refresh_token(
+        &self,
+        payload: RefreshTokenDto,
+        client: &ClientInfo,
     ) -> impl Future<Output = Result<AuthBody, AppError>> + Send;
+
+    /// Revokes the access token described by `claims` and its session, and, if supplied,
+    /// the refresh token family.
+    fn logout(
+        &self,
+        claims: &Claims,
+        payload: LogoutDto,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Changes the password of the user described by `claims` after verifying the current one.
+    /// All tokens of the user are revoked and the other sessions ended. If `claims` belong to
+    /// a session, it is kept and a new token pair for it is returned.
+    fn change_password(
+        &self,
+        claims: &Claims,
+        payload: ChangePasswordDto,
+        client: &ClientInfo,
+    ) -> impl Future<Output = Result<Option<AuthBody>, AppError>> + Send;
+
+    /// Emails a password reset link to the user with the given email address, if any.
+    /// Succeeds whether or not such a user exists.
+    fn request_password_reset(
+        &self,
+        payload: PasswordResetRequestDto,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Sets a new password using a single-use password reset token.
+    fn confirm_password_reset(
+        &self,
+        payload: PasswordResetConfirmDto,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Revokes every access and refresh token issued to a user so far.
+    /// Intended for incident response, e.g. after a credential leak.
+    fn revoke_all_tokens(
+        &self,
+        user_id: &str,
+        revoked_by: &str,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Issues a short-lived access token for another user, carrying the caller as its actor.
+    /// Users who may impersonate others themselves cannot be impersonated.
+    fn impersonate_user(
+        &self,
+        claims: &Claims,
+        user_id: &str,
+        payload: ImpersonateDto,
+    ) -> impl Future<Output = Result<AuthBody, AppError>> + Send;
+
+    /// Lists the active sessions of a user.
+    fn list_sessions(
+        &self,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Vec<Session>, AppError>> + Send;
+
+    /// Revokes a session of a user: its refresh tokens can no longer be used and
+    /// its access tokens are rejected by `jwt_auth`.
+    fn revoke_session(
+        &self,
+        user_id: &str,
+        session_id: &str,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Revokes every session of the user described by `claims` except the one the token
+    /// belongs to. Returns the number of sessions revoked.
+    fn revoke_other_sessions(
+        &self,
+        claims: &Claims,
+    ) -> impl Future<Output = Result<u64, AppError>> + Send;
+
+    /// Returns whether the token described by `claims` has been revoked.
+    fn is_token_revoked(
+        &self,
+        claims: &Claims,
+    ) -> impl Future<Output = Result<bool, AppError>> + Send;
 }
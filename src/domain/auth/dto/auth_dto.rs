@@ -1,9 +1,212 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
 
+use crate::{
+    common::{jwt::AuthBody, password_policy::validate_password},
+    domain::{auth::Session, user::UserDto},
+};
+
 #[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct AuthUserDto {
     pub user_id: String,
+    #[validate(custom(function = validate_password))]
     pub password: String,
 }
+
+/// A password hash exported from another system, to be stored for an existing user.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ImportedCredentialDto {
+    #[validate(length(min = 1, message = "User ID is required"))]
+    pub user_id: String,
+    /// bcrypt, PBKDF2-SHA256 (PHC or passlib format) or Argon2 hash of the user's password.
+    #[validate(length(min = 1, max = 255, message = "Password hash must be 1 to 255 characters"))]
+    pub password_hash: String,
+}
+
+/// Request body for importing password hashes.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ImportCredentialsDto {
+    #[validate(nested)]
+    pub credentials: Vec<ImportedCredentialDto>,
+}
+
+/// A credential that was not imported, and why.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SkippedCredentialDto {
+    pub user_id: String,
+    pub reason: String,
+}
+
+/// Outcome of a credential import.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportCredentialsResultDto {
+    /// IDs of the users whose password hash was stored.
+    pub imported: Vec<String>,
+    pub skipped: Vec<SkippedCredentialDto>,
+}
+
+/// Request body for changing the password of the authenticated user.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ChangePasswordDto {
+    #[validate(length(min = 1, message = "Current password is required"))]
+    pub current_password: String,
+    #[validate(custom(function = validate_password))]
+    pub new_password: String,
+}
+
+/// Request body for exchanging a refresh token for a new token pair.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct RefreshTokenDto {
+    #[validate(length(min = 1, message = "Refresh token is required"))]
+    pub refresh_token: String,
+}
+
+/// Optional request body for logging out.
+/// When a refresh token is supplied, every token issued from the same login is revoked too.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct LogoutDto {
+    pub refresh_token: Option<String>,
+}
+
+/// Request body for requesting a password reset email.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct PasswordResetRequestDto {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+}
+
+/// Request body for setting a new password with a password reset token.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct PasswordResetConfirmDto {
+    #[validate(length(min = 1, message = "Reset token is required"))]
+    pub token: String,
+    #[validate(custom(function = validate_password))]
+    pub new_password: String,
+}
+
+/// Request body for impersonating a user.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ImpersonateDto {
+    /// Why the user is impersonated, e.g. a support ticket; recorded in the logs.
+    #[validate(length(min = 1, max = 500, message = "Reason must be 1 to 500 characters"))]
+    pub reason: String,
+}
+
+/// Request body for emailing a magic login link.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct MagicLinkRequestDto {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+}
+
+/// Request body for logging in with the token of a magic link.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct MagicLinkLoginDto {
+    #[validate(length(min = 1, message = "Login token is required"))]
+    pub token: String,
+}
+
+/// Response of a password login: the token pair, or an MFA challenge when the user has
+/// two-factor authentication enabled.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthBody),
+    MfaRequired(MfaChallengeDto),
+}
+
+/// Response of a signup: the new user and, unless the email address has to be verified
+/// before logging in, the login result.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SignupResponseDto {
+    pub user: UserDto,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub login: Option<LoginResponse>,
+}
+
+/// A challenge to be completed at `/auth/login/mfa` with a second factor.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaChallengeDto {
+    /// Always `true`; lets clients tell a challenge from a token pair.
+    pub mfa_required: bool,
+    /// Opaque token identifying the pending login.
+    pub mfa_token: String,
+    /// Lifetime of the challenge in seconds.
+    pub expires_in: i64,
+}
+
+/// Request body for completing a login with a second factor.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct MfaLoginDto {
+    #[validate(length(min = 1, message = "MFA token is required"))]
+    pub mfa_token: String,
+    /// A current TOTP code or an unused recovery code.
+    #[validate(length(min = 1, message = "Code is required"))]
+    pub code: String,
+}
+
+/// Request body carrying a second factor: a TOTP code or, where accepted, a recovery code.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct MfaCodeDto {
+    #[validate(length(min = 1, message = "Code is required"))]
+    pub code: String,
+}
+
+/// A newly generated TOTP secret, to be added to an authenticator app.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TotpEnrollmentDto {
+    /// Base32-encoded secret for manual entry.
+    pub secret: String,
+    /// `otpauth://` URI, usually rendered as a QR code.
+    pub otpauth_uri: String,
+}
+
+/// Freshly generated recovery codes. They are only shown once.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesDto {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Two-factor authentication status of the current user.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaStatusDto {
+    pub totp_enabled: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+/// An active login session, as listed to its user.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SessionDto {
+    pub id: String,
+    /// IP address of the client at login or at the latest token refresh.
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "crate::common::ts_format")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::common::ts_format")]
+    pub last_seen_at: DateTime<Utc>,
+    /// Whether the request listing the sessions was made with a token of this session.
+    pub current: bool,
+}
+
+impl SessionDto {
+    /// Converts a session, marking it as current if `current_sid` is its ID.
+    pub fn from_session(session: Session, current_sid: Option<&str>) -> Self {
+        Self {
+            current: current_sid == Some(session.id.as_str()),
+            id: session.id,
+            ip_address: session.ip_address,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+        }
+    }
+}
+
+/// Number of sessions that were revoked.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RevokedSessionsDto {
+    pub revoked: u64,
+}
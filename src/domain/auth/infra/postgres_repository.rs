@@ -1,6 +1,13 @@
+use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
 
-use crate::domain::auth::{UserAuth, UserAuthRepository};
+use crate::domain::auth::{
+    MagicLinkRepository, MagicLinkToken, MfaChallenge, MfaChallengeRepository, MfaRepository,
+    PasswordResetRepository, PasswordResetToken, RefreshToken, RefreshTokenRepository, Session,
+    SessionRepository, TokenRevocationRepository, TokenRevocationStatus, UserAuth,
+    UserAuthRepository, UserAuthorities, UserIdentity, UserTotp,
+};
 
 #[derive(Clone)]
 pub struct UserAuthRepo;
@@ -13,10 +20,11 @@ impl UserAuthRepository for UserAuthRepo {
     ) -> Result<Option<UserAuth>, sqlx::Error> {
         let result = sqlx::query_as::<_, UserAuth>(
             r#"
-            SELECT ua.user_id, ua.password_hash
+            SELECT ua.user_id, ua.password_hash, ua.failed_login_attempts, ua.locked_until
               FROM user_auth ua
               JOIN users u ON ua.user_id = u.id
               WHERE u.username = $1
+                AND u.deleted_at IS NULL
             "#,
         )
         .bind(user_name)
@@ -26,6 +34,67 @@ impl UserAuthRepository for UserAuthRepo {
         Ok(result)
     }
 
+    async fn find_by_user_id(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> Result<Option<UserAuth>, sqlx::Error> {
+        sqlx::query_as::<_, UserAuth>(
+            r#"
+            SELECT user_id, password_hash, failed_login_attempts, locked_until
+              FROM user_auth
+             WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    async fn find_identity(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> Result<Option<UserIdentity>, sqlx::Error> {
+        sqlx::query_as::<_, UserIdentity>(
+            r#"
+            SELECT id, username, email, email_verified_at IS NOT NULL AS email_verified
+              FROM users
+             WHERE id = $1
+               AND deleted_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    async fn find_identity_by_email(
+        &self,
+        pool: &PgPool,
+        email: &str,
+    ) -> Result<Option<UserIdentity>, sqlx::Error> {
+        let mut identities = sqlx::query_as::<_, UserIdentity>(
+            r#"
+            SELECT u.id, u.username, u.email, u.email_verified_at IS NOT NULL AS email_verified
+              FROM users u
+              JOIN user_auth ua ON ua.user_id = u.id
+             WHERE LOWER(u.email) = LOWER($1)
+               AND u.deleted_at IS NULL
+             LIMIT 2
+            "#,
+        )
+        .bind(email)
+        .fetch_all(pool)
+        .await?;
+        // `users.email` is not unique, and a shared address does not identify an account
+        Ok(if identities.len() == 1 {
+            identities.pop()
+        } else {
+            None
+        })
+    }
+
     async fn create(
         &self,
         tx: &mut Transaction<'_, Postgres>,
@@ -46,4 +115,943 @@ impl UserAuthRepository for UserAuthRepo {
 
         Ok(())
     }
+
+    async fn import(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_auth: &UserAuth,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            INSERT INTO user_auth
+            (user_id, password_hash)
+            SELECT $1, $2
+             WHERE EXISTS (SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL)
+            ON CONFLICT (user_id) DO NOTHING
+            "#,
+        )
+        .bind(&user_auth.user_id)
+        .bind(&user_auth.password_hash)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn update_password(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        password_hash: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE user_auth
+               SET password_hash = $2,
+                   failed_login_attempts = 0,
+                   locked_until = NULL,
+                   modified_at = NOW()
+             WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(password_hash)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn rehash_password(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+        old_hash: &str,
+        new_hash: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE user_auth
+               SET password_hash = $3
+             WHERE user_id = $1
+               AND password_hash = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(old_hash)
+        .bind(new_hash)
+        .execute(pool)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn record_failed_login(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+        max_attempts: i32,
+        lockout_secs: i64,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar(
+            r#"
+            UPDATE user_auth
+               SET failed_login_attempts = CASE
+                       WHEN failed_login_attempts + 1 >= $2 THEN 0
+                       ELSE failed_login_attempts + 1
+                   END,
+                   locked_until = CASE
+                       WHEN failed_login_attempts + 1 >= $2
+                           THEN NOW() + make_interval(secs => $3::DOUBLE PRECISION)
+                       ELSE locked_until
+                   END
+             WHERE user_id = $1
+            RETURNING locked_until
+            "#,
+        )
+        .bind(user_id)
+        .bind(max_attempts)
+        .bind(lockout_secs)
+        .fetch_optional(pool)
+        .await?
+        .flatten();
+
+        Ok(locked_until.filter(|until| *until > Utc::now()))
+    }
+
+    async fn reset_failed_logins(&self, pool: &PgPool, user_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE user_auth
+               SET failed_login_attempts = 0,
+                   locked_until = NULL
+             WHERE user_id = $1
+               AND (failed_login_attempts > 0 OR locked_until IS NOT NULL)
+            "#,
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_authorities(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> Result<UserAuthorities, sqlx::Error> {
+        sqlx::query_as::<_, UserAuthorities>(
+            r#"
+            SELECT COALESCE(
+                       (SELECT ARRAY_AGG(role_name ORDER BY role_name)
+                          FROM user_roles
+                         WHERE user_id = $1),
+                       '{}'
+                   )::TEXT[] AS roles,
+                   COALESCE(
+                       (SELECT ARRAY_AGG(DISTINCT rp.permission_name ORDER BY rp.permission_name)
+                          FROM user_roles ur
+                          JOIN role_permissions rp ON rp.role_name = ur.role_name
+                         WHERE ur.user_id = $1),
+                       '{}'
+                   )::TEXT[] AS permissions
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+}
+
+#[derive(Clone)]
+pub struct RefreshTokenRepo;
+
+impl RefreshTokenRepository for RefreshTokenRepo {
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: &RefreshToken,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens
+            (id, user_id, family_id, token_hash, expires_at)
+            VALUES
+            ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(&token.id)
+        .bind(&token.user_id)
+        .bind(&token.family_id)
+        .bind(&token.token_hash)
+        .bind(token.expires_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<RefreshToken>, sqlx::Error> {
+        sqlx::query_as::<_, RefreshToken>(
+            r#"
+            SELECT id, user_id, family_id, token_hash, expires_at, used_at, revoked_at
+              FROM refresh_tokens
+              WHERE token_hash = $1
+              FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn mark_used(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(r#"UPDATE refresh_tokens SET used_at = NOW() WHERE id = $1"#)
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_family(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        family_id: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+               SET revoked_at = NOW()
+             WHERE family_id = $1
+               AND revoked_at IS NULL
+            "#,
+        )
+        .bind(family_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn revoke_all_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+               SET revoked_at = NOW()
+             WHERE user_id = $1
+               AND revoked_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+}
+
+#[derive(Clone)]
+pub struct TokenRevocationRepo;
+
+impl TokenRevocationRepository for TokenRevocationRepo {
+    async fn revoke_token(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        jti: &str,
+        user_id: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO revoked_tokens
+            (jti, user_id, expires_at)
+            VALUES
+            ($1, $2, $3)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+        )
+        .bind(jti)
+        .bind(user_id)
+        .bind(expires_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_all_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        revoked_before: DateTime<Utc>,
+        revoked_by: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_token_revocations
+            (user_id, revoked_before, revoked_by)
+            VALUES
+            ($1, $2, $3)
+            ON CONFLICT (user_id)
+            DO UPDATE SET revoked_before = EXCLUDED.revoked_before,
+                          revoked_by = EXCLUDED.revoked_by
+            "#,
+        )
+        .bind(user_id)
+        .bind(revoked_before)
+        .bind(revoked_by)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_status(
+        &self,
+        pool: &PgPool,
+        jti: &str,
+        user_id: &str,
+        session_id: Option<&str>,
+    ) -> Result<TokenRevocationStatus, sqlx::Error> {
+        sqlx::query_as::<_, TokenRevocationStatus>(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM revoked_tokens WHERE jti = $1) AS revoked,
+                   (SELECT revoked_before FROM user_token_revocations WHERE user_id = $2)
+                       AS revoked_before,
+                   EXISTS (SELECT 1 FROM sessions WHERE id = $3 AND revoked_at IS NOT NULL)
+                       AS session_revoked
+            "#,
+        )
+        .bind(jti)
+        .bind(user_id)
+        .bind(session_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    async fn delete_expired(&self, tx: &mut Transaction<'_, Postgres>) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(r#"DELETE FROM revoked_tokens WHERE expires_at < NOW()"#)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionRepo;
+
+impl SessionRepository for SessionRepo {
+    async fn save(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        session: &Session,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO sessions
+            (id, user_id, access_jti, ip_address, user_agent)
+            VALUES
+            ($1, $2, $3, $4, $5)
+            ON CONFLICT (id)
+            DO UPDATE SET access_jti = EXCLUDED.access_jti,
+                          ip_address = EXCLUDED.ip_address,
+                          user_agent = EXCLUDED.user_agent,
+                          last_seen_at = NOW()
+            "#,
+        )
+        .bind(&session.id)
+        .bind(&session.user_id)
+        .bind(&session.access_jti)
+        .bind(&session.ip_address)
+        .bind(&session.user_agent)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn touch(&self, pool: &PgPool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE sessions
+               SET last_seen_at = NOW()
+             WHERE id = $1
+               AND last_seen_at < NOW() - INTERVAL '1 minute'
+            "#,
+        )
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_active_by_user(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> Result<Vec<Session>, sqlx::Error> {
+        sqlx::query_as::<_, Session>(
+            r#"
+            SELECT s.id, s.user_id, s.access_jti, s.ip_address, s.user_agent,
+                   s.created_at, s.last_seen_at, s.revoked_at
+              FROM sessions s
+             WHERE s.user_id = $1
+               AND s.revoked_at IS NULL
+               AND EXISTS (
+                   SELECT 1 FROM refresh_tokens r
+                    WHERE r.family_id = s.id
+                      AND r.used_at IS NULL
+                      AND r.revoked_at IS NULL
+                      AND r.expires_at > NOW()
+               )
+             ORDER BY s.last_seen_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    async fn revoke(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+        user_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE sessions
+               SET revoked_at = NOW()
+             WHERE id = $1
+               AND user_id = $2
+               AND revoked_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn revoke_all_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        except: Option<&str>,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            UPDATE sessions
+               SET revoked_at = NOW()
+             WHERE user_id = $1
+               AND revoked_at IS NULL
+               AND ($2::VARCHAR IS NULL OR id <> $2)
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(except)
+        .fetch_all(&mut **tx)
+        .await
+    }
+
+    async fn delete_stale(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        max_token_age_secs: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            DELETE FROM sessions s
+             WHERE s.user_id = $1
+               AND s.last_seen_at < NOW() - make_interval(secs => $2::DOUBLE PRECISION)
+               AND NOT EXISTS (
+                   SELECT 1 FROM refresh_tokens r
+                    WHERE r.family_id = s.id
+                      AND r.used_at IS NULL
+                      AND r.revoked_at IS NULL
+                      AND r.expires_at > NOW()
+               )
+            "#,
+        )
+        .bind(user_id)
+        .bind(max_token_age_secs)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+}
+
+#[derive(Clone)]
+pub struct PasswordResetRepo;
+
+impl PasswordResetRepository for PasswordResetRepo {
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: &PasswordResetToken,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO password_reset_tokens
+            (id, user_id, token_hash, expires_at)
+            VALUES
+            ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(&token.id)
+        .bind(&token.user_id)
+        .bind(&token.token_hash)
+        .bind(token.expires_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<PasswordResetToken>, sqlx::Error> {
+        sqlx::query_as::<_, PasswordResetToken>(
+            r#"
+            SELECT id, user_id, token_hash, expires_at, used_at
+              FROM password_reset_tokens
+             WHERE token_hash = $1
+               FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn invalidate_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE password_reset_tokens
+               SET used_at = NOW()
+             WHERE user_id = $1
+               AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn delete_stale(&self, tx: &mut Transaction<'_, Postgres>) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            DELETE FROM password_reset_tokens
+             WHERE expires_at < NOW()
+                OR used_at IS NOT NULL
+            "#,
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+}
+
+#[derive(Clone)]
+pub struct MagicLinkRepo;
+
+impl MagicLinkRepository for MagicLinkRepo {
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: &MagicLinkToken,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO magic_link_tokens
+            (id, user_id, token_hash, expires_at)
+            VALUES
+            ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(&token.id)
+        .bind(&token.user_id)
+        .bind(&token.token_hash)
+        .bind(token.expires_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<MagicLinkToken>, sqlx::Error> {
+        sqlx::query_as::<_, MagicLinkToken>(
+            r#"
+            SELECT id, user_id, token_hash, expires_at, used_at
+              FROM magic_link_tokens
+             WHERE token_hash = $1
+               FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn invalidate_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE magic_link_tokens
+               SET used_at = NOW()
+             WHERE user_id = $1
+               AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn delete_stale(&self, tx: &mut Transaction<'_, Postgres>) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            DELETE FROM magic_link_tokens
+             WHERE expires_at < NOW()
+                OR used_at IS NOT NULL
+            "#,
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+}
+
+#[derive(Clone)]
+pub struct MfaRepo;
+
+impl MfaRepository for MfaRepo {
+    async fn find_totp(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> Result<Option<UserTotp>, sqlx::Error> {
+        sqlx::query_as::<_, UserTotp>(
+            r#"
+            SELECT user_id, secret, enabled_at, last_used_step
+              FROM user_totp
+             WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    async fn find_totp_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> Result<Option<UserTotp>, sqlx::Error> {
+        sqlx::query_as::<_, UserTotp>(
+            r#"
+            SELECT user_id, secret, enabled_at, last_used_step
+              FROM user_totp
+             WHERE user_id = $1
+               FOR UPDATE
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn save_pending_totp(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        secret: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            INSERT INTO user_totp (user_id, secret)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+               SET secret = EXCLUDED.secret,
+                   last_used_step = NULL,
+                   created_at = NOW()
+             WHERE user_totp.enabled_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(secret)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn enable_totp(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        step: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE user_totp
+               SET enabled_at = NOW(),
+                   last_used_step = $2
+             WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn record_totp_step(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        step: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE user_totp
+               SET last_used_step = $2
+             WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_totp(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query("DELETE FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn replace_recovery_codes(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        code_hashes: &[String],
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for code_hash in code_hashes {
+            sqlx::query(
+                r#"
+                INSERT INTO mfa_recovery_codes
+                (id, user_id, code_hash)
+                VALUES
+                ($1, $2, $3)
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(user_id)
+            .bind(code_hash)
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn use_recovery_code(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        code_hash: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE mfa_recovery_codes
+               SET used_at = NOW()
+             WHERE user_id = $1
+               AND code_hash = $2
+               AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn count_recovery_codes(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+              FROM mfa_recovery_codes
+             WHERE user_id = $1
+               AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+}
+
+#[derive(Clone)]
+pub struct MfaChallengeRepo;
+
+impl MfaChallengeRepository for MfaChallengeRepo {
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        challenge: &MfaChallenge,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO mfa_challenges
+            (id, user_id, token_hash, expires_at)
+            VALUES
+            ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(&challenge.id)
+        .bind(&challenge.user_id)
+        .bind(&challenge.token_hash)
+        .bind(challenge.expires_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<MfaChallenge>, sqlx::Error> {
+        sqlx::query_as::<_, MfaChallenge>(
+            r#"
+            SELECT id, user_id, token_hash, expires_at, failed_attempts, used_at
+              FROM mfa_challenges
+             WHERE token_hash = $1
+               FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn record_failed_attempt(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE mfa_challenges
+               SET failed_attempts = failed_attempts + 1
+             WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_used(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE mfa_challenges
+               SET used_at = NOW()
+             WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_stale(&self, tx: &mut Transaction<'_, Postgres>) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            DELETE FROM mfa_challenges
+             WHERE expires_at < NOW()
+                OR used_at IS NOT NULL
+            "#,
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
 }
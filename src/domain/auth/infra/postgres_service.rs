@@ -1,51 +1,514 @@
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
 
 use crate::{
     common::{
+        client_ip::ClientInfo,
+        config::Config,
         error::AppError,
-        hash_util,
-        jwt::{make_jwt_token, AuthBody, AuthPayload},
+        hash_util::{self, PasswordCheck},
+        jwt::{make_jwt_token, AuthBody, AuthPayload, Claims},
+        mailer::{self, AppMailer, Email},
+        password_policy::PASSWORD_POLICY,
+        rate_limit::RateLimiter,
+        rbac::permissions,
+        token_util,
+        totp::{self, Totp},
     },
     domain::auth::{
-        domain::{model::UserAuth, repository::UserAuthRepository, service::AuthServiceTrait},
-        dto::auth_dto::AuthUserDto,
-        infra::postgres_repository::UserAuthRepo,
+        domain::{
+            model::{
+                MagicLinkToken, MfaChallenge, PasswordResetToken, RefreshToken, Session, UserAuth,
+                UserIdentity, UserTotp,
+            },
+            repository::{
+                MagicLinkRepository, MfaChallengeRepository, MfaRepository,
+                PasswordResetRepository, RefreshTokenRepository, SessionRepository,
+                TokenRevocationRepository, UserAuthRepository,
+            },
+            service::AuthServiceTrait,
+        },
+        dto::auth_dto::{
+            AuthUserDto, ChangePasswordDto, ImpersonateDto, ImportCredentialsDto,
+            ImportCredentialsResultDto, LoginResponse, LogoutDto, MagicLinkLoginDto,
+            MagicLinkRequestDto, MfaChallengeDto, MfaCodeDto, MfaLoginDto, MfaStatusDto,
+            PasswordResetConfirmDto, PasswordResetRequestDto, RecoveryCodesDto, RefreshTokenDto,
+            SkippedCredentialDto, TotpEnrollmentDto,
+        },
+        infra::{
+            postgres_repository::{
+                MagicLinkRepo, MfaChallengeRepo, MfaRepo, PasswordResetRepo, RefreshTokenRepo,
+                SessionRepo, TokenRevocationRepo, UserAuthRepo,
+            },
+            revocation_cache::RevocationCache,
+        },
     },
 };
 
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// Number of recovery codes generated when two-factor authentication is enabled.
+const RECOVERY_CODE_COUNT: usize = 10;
+/// Wrong codes accepted per MFA challenge before the user has to log in again.
+const MFA_CHALLENGE_MAX_ATTEMPTS: i32 = 5;
+/// Longest user agent recorded for a session; longer ones are truncated.
+const MAX_USER_AGENT_LENGTH: usize = 512;
+/// Most credentials accepted by one import request.
+const MAX_IMPORTED_CREDENTIALS: usize = 1000;
+/// Longest lifetime of an impersonation token in seconds, whatever the configuration says.
+const MAX_IMPERSONATION_TOKEN_TTL_SECS: i64 = 3600;
 
 /// Service for handling user authentication
 /// and authorization logic.
 #[derive(Clone)]
 pub struct PostgresAuthService {
     pool: PgPool,
+    config: Config,
     repo: UserAuthRepo,
+    refresh_token_repo: RefreshTokenRepo,
+    revocation_repo: TokenRevocationRepo,
+    session_repo: SessionRepo,
+    password_reset_repo: PasswordResetRepo,
+    magic_link_repo: MagicLinkRepo,
+    mfa_repo: MfaRepo,
+    mfa_challenge_repo: MfaChallengeRepo,
+    revocation_cache: Arc<RevocationCache>,
+    login_limiter: Arc<RateLimiter>,
+    magic_link_limiter: Arc<RateLimiter>,
+    mailer: Arc<AppMailer>,
 }
 
 impl PostgresAuthService {
     /// constructor for the service.
-    pub fn new(pool: PgPool) -> Arc<Self> {
+    pub fn new(pool: PgPool, config: Config, mailer: Arc<AppMailer>) -> Arc<Self> {
+        let revocation_cache = Arc::new(RevocationCache::new(std::time::Duration::from_secs(
+            config.token_revocation_cache_ttl_secs,
+        )));
+        let login_limiter = Arc::new(RateLimiter::new(
+            config.login_ip_max_failures,
+            std::time::Duration::from_secs(config.login_ip_window_secs),
+        ));
+        let magic_link_limiter = Arc::new(RateLimiter::new(
+            config.magic_link_max_requests,
+            std::time::Duration::from_secs(config.magic_link_window_secs),
+        ));
+
         Arc::new(Self {
             pool,
+            config,
             repo: UserAuthRepo,
+            refresh_token_repo: RefreshTokenRepo,
+            revocation_repo: TokenRevocationRepo,
+            session_repo: SessionRepo,
+            password_reset_repo: PasswordResetRepo,
+            magic_link_repo: MagicLinkRepo,
+            mfa_repo: MfaRepo,
+            mfa_challenge_repo: MfaChallengeRepo,
+            revocation_cache,
+            login_limiter,
+            magic_link_limiter,
+            mailer,
+        })
+    }
+
+    /// Returns the revocation cache, to be shared with services that revoke tokens themselves.
+    pub fn revocation_cache(&self) -> Arc<RevocationCache> {
+        self.revocation_cache.clone()
+    }
+
+    /// Issues a new access token together with a refresh token belonging to `family_id`.
+    /// The user's current roles and permissions are embedded in the access token, and deleted
+    /// users are refused.
+    /// The refresh token and the session the family stands for are persisted within
+    /// the given transaction, recording `client` as the latest client of the session.
+    async fn issue_token_pair(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        family_id: &str,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, AppError> {
+        let active = self
+            .repo
+            .find_identity(&self.pool, user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user identity: {e}"))?
+            .is_some();
+        if !active {
+            tracing::warn!(user_id, "Refused to issue tokens to a deleted user");
+            return Err(AppError::Forbidden);
+        }
+
+        let refresh_token = token_util::generate_token();
+        let record = RefreshToken {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            family_id: family_id.to_string(),
+            token_hash: token_util::hash_token(&refresh_token),
+            expires_at: Utc::now() + Duration::seconds(self.config.refresh_token_ttl_secs),
+            used_at: None,
+            revoked_at: None,
+        };
+        self.refresh_token_repo
+            .create(tx, &record)
+            .await
+            .inspect_err(|e| tracing::error!("Error creating refresh token: {e}"))?;
+
+        let authorities = self
+            .repo
+            .find_authorities(&self.pool, user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user authorities: {e}"))?;
+
+        let ttl = self.config.access_token_ttl_secs;
+        let claims = Claims::new(user_id, Duration::seconds(ttl))
+            .with_authorities(authorities.roles, authorities.permissions)
+            .with_session_id(family_id.to_string());
+        let access_token = make_jwt_token(&claims, &self.config.jwt_settings)?;
+
+        let session = Session {
+            id: family_id.to_string(),
+            user_id: user_id.to_string(),
+            access_jti: claims.jti,
+            ip_address: client.ip.map(|ip| ip.to_string()),
+            user_agent: client
+                .user_agent
+                .as_ref()
+                .map(|user_agent| user_agent.chars().take(MAX_USER_AGENT_LENGTH).collect()),
+            created_at: Utc::now(),
+            last_seen_at: Utc::now(),
+            revoked_at: None,
+        };
+        self.session_repo
+            .save(tx, &session)
+            .await
+            .inspect_err(|e| tracing::error!("Error saving session: {e}"))?;
+
+        Ok(AuthBody::new(access_token, ttl).with_refresh_token(refresh_token))
+    }
+
+    /// Starts a new session with a token pair, first dropping the user's sessions that are over.
+    async fn start_session(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, AppError> {
+        self.session_repo
+            .delete_stale(tx, user_id, self.config.access_token_ttl_secs)
+            .await?;
+        let family_id = Uuid::new_v4().to_string();
+        self.issue_token_pair(tx, user_id, &family_id, client).await
+    }
+
+    /// Returns the instant (unix seconds) by which every access token issued so far expires.
+    fn issued_tokens_exp(&self) -> usize {
+        (Utc::now().timestamp() + self.config.access_token_ttl_secs) as usize
+    }
+
+    /// Replaces an outdated password hash with one made with the configured parameters.
+    /// Failures are logged but do not fail the login; the next login tries again.
+    async fn rehash_password(&self, user_auth: &UserAuth, password: &str) {
+        let new_hash = match hash_util::hash_password(password, &self.config.password_hash_params)
+        {
+            Ok(new_hash) => new_hash,
+            Err(e) => {
+                tracing::warn!("Error rehashing password: {e}");
+                return;
+            }
+        };
+
+        match self
+            .repo
+            .rehash_password(&self.pool, &user_auth.user_id, &user_auth.password_hash, &new_hash)
+            .await
+        {
+            Ok(true) => tracing::info!(user_id = %user_auth.user_id, "Password hash upgraded"),
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Error storing rehashed password: {e}"),
+        }
+    }
+
+    /// Refuses clients that exceeded the failed login limit.
+    fn check_login_throttle(&self, client_ip: Option<IpAddr>) -> Result<(), AppError> {
+        if let Some(retry_after) =
+            client_ip.and_then(|ip| self.login_limiter.retry_after(&ip.to_string()))
+        {
+            tracing::warn!(?client_ip, "Login throttled after repeated failures");
+            return Err(AppError::TooManyRequests(retry_after.as_secs().max(1)));
+        }
+        Ok(())
+    }
+
+    /// Counts a failed login against the client's IP and returns the uniform error
+    /// reported for every kind of failed login.
+    fn login_failed(&self, client_ip: Option<IpAddr>) -> AppError {
+        if let Some(ip) = client_ip {
+            self.login_limiter.hit(&ip.to_string());
+        }
+        AppError::WrongCredentials
+    }
+
+    /// Counts a wrong password or second factor against the account, locking it
+    /// once `login_max_failed_attempts` is reached.
+    async fn record_failed_login(
+        &self,
+        user_id: &str,
+        client_ip: Option<IpAddr>,
+    ) -> Result<(), AppError> {
+        let locked_until = self
+            .repo
+            .record_failed_login(
+                &self.pool,
+                user_id,
+                self.config.login_max_failed_attempts,
+                self.config.login_lockout_secs,
+            )
+            .await
+            .inspect_err(|e| tracing::error!("Error recording failed login: {e}"))?;
+        if let Some(until) = locked_until {
+            tracing::warn!(
+                user_id,
+                ?client_ip,
+                %until,
+                "Account locked after repeated failed logins"
+            );
+        }
+        Ok(())
+    }
+
+    /// Starts the second step of a login for a user with two-factor authentication.
+    async fn create_mfa_challenge(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> Result<MfaChallengeDto, AppError> {
+        let token = token_util::generate_token();
+        let ttl = self.config.mfa_challenge_ttl_secs;
+        let challenge = MfaChallenge {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            token_hash: token_util::hash_token(&token),
+            expires_at: Utc::now() + Duration::seconds(ttl),
+            failed_attempts: 0,
+            used_at: None,
+        };
+
+        self.mfa_challenge_repo.delete_stale(tx).await?;
+        self.mfa_challenge_repo
+            .create(tx, &challenge)
+            .await
+            .inspect_err(|e| tracing::error!("Error creating MFA challenge: {e}"))?;
+
+        Ok(MfaChallengeDto {
+            mfa_required: true,
+            mfa_token: token,
+            expires_in: ttl,
         })
     }
+
+    /// Builds the TOTP generator for a stored secret.
+    fn totp(&self, user_totp: &UserTotp) -> Result<Totp, AppError> {
+        Totp::new(&user_totp.secret, &self.config.mfa_issuer, "").ok_or_else(|| {
+            tracing::error!(user_id = %user_totp.user_id, "Stored TOTP secret is invalid");
+            AppError::InternalError
+        })
+    }
+
+    /// Checks a second factor of a user with two-factor authentication: a TOTP code that
+    /// was not used before, or an unused recovery code. The code is consumed on success.
+    async fn verify_second_factor(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_totp: &UserTotp,
+        code: &str,
+    ) -> Result<bool, AppError> {
+        let now = Utc::now().timestamp() as u64;
+        let step = self.totp(user_totp)?.verify(
+            code,
+            now,
+            self.config.totp_skew_steps,
+            user_totp.last_used_step,
+        );
+        if let Some(step) = step {
+            self.mfa_repo
+                .record_totp_step(tx, &user_totp.user_id, step)
+                .await?;
+            return Ok(true);
+        }
+
+        let code_hash = token_util::hash_token(&totp::normalize_recovery_code(code));
+        if self
+            .mfa_repo
+            .use_recovery_code(tx, &user_totp.user_id, &code_hash)
+            .await?
+        {
+            tracing::warn!(user_id = %user_totp.user_id, "Recovery code used");
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Loads the TOTP secret of a user with two-factor authentication enabled, locking it,
+    /// and checks a second factor. As for `/auth/login/mfa`, wrong codes count as failed
+    /// logins for the account lockout and the client's IP, and are refused once either
+    /// limit is reached, so a stolen access token cannot be used to guess codes.
+    async fn require_second_factor(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        code: &str,
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
+        let client_ip = client.ip;
+        self.check_login_throttle(client_ip)?;
+
+        let locked = self
+            .repo
+            .find_by_user_id(&self.pool, user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user auth: {e}"))?
+            .is_some_and(|user_auth| user_auth.is_locked());
+        if locked {
+            return Err(self.login_failed(client_ip));
+        }
+
+        let user_totp = self
+            .mfa_repo
+            .find_totp_for_update(tx, user_id)
+            .await?
+            .filter(UserTotp::is_enabled)
+            .ok_or_else(|| {
+                AppError::ValidationError("Two-factor authentication is not enabled".into())
+            })?;
+
+        if !self.verify_second_factor(tx, &user_totp, code).await? {
+            self.record_failed_login(user_id, client_ip).await?;
+            return Err(self.login_failed(client_ip));
+        }
+        Ok(())
+    }
+
+    /// Generates recovery codes and replaces the stored ones with their hashes.
+    async fn replace_recovery_codes(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> Result<RecoveryCodesDto, AppError> {
+        let recovery_codes = totp::generate_recovery_codes(RECOVERY_CODE_COUNT);
+        let code_hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| token_util::hash_token(&totp::normalize_recovery_code(code)))
+            .collect();
+        self.mfa_repo
+            .replace_recovery_codes(tx, user_id, &code_hashes)
+            .await
+            .inspect_err(|e| tracing::error!("Error storing recovery codes: {e}"))?;
+
+        Ok(RecoveryCodesDto { recovery_codes })
+    }
+
+    /// Completes a login once the user has proven their identity: refuses unverified email
+    /// addresses with `require_email_verification`, and returns an MFA challenge for users
+    /// with two-factor authentication or a token pair starting a new session otherwise.
+    async fn finish_login(
+        &self,
+        user_id: &str,
+        client: &ClientInfo,
+    ) -> Result<LoginResponse, AppError> {
+        if self.config.require_email_verification {
+            let verified = self
+                .repo
+                .find_identity(&self.pool, user_id)
+                .await
+                .inspect_err(|e| tracing::error!("Error retrieving user identity: {e}"))?
+                .is_some_and(|identity| identity.email_verified);
+            if !verified {
+                return Err(AppError::EmailNotVerified);
+            }
+        }
+
+        let mfa_enabled = self
+            .mfa_repo
+            .find_totp(&self.pool, user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving TOTP secret: {e}"))?
+            .is_some_and(|user_totp| user_totp.is_enabled());
+
+        let mut tx = self.pool.begin().await?;
+        if mfa_enabled {
+            let challenge = self.create_mfa_challenge(&mut tx, user_id).await?;
+            tx.commit().await?;
+            return Ok(LoginResponse::MfaRequired(challenge));
+        }
+
+        let auth_body = self.start_session(&mut tx, user_id, client).await?;
+        tx.commit().await?;
+
+        Ok(LoginResponse::Authenticated(auth_body))
+    }
+
+    /// Returns whether every token of the user acting through an impersonation token was
+    /// revoked after the token was issued, which ends the impersonation as well.
+    async fn actor_revoked(&self, claims: &Claims, actor_id: &str) -> Result<bool, AppError> {
+        let revoked_before = match self.revocation_cache.user_revoked_before(actor_id) {
+            Some(revoked_before) => revoked_before,
+            None => {
+                let status = self
+                    .revocation_repo
+                    .find_status(&self.pool, &claims.jti, actor_id, None)
+                    .await
+                    .inspect_err(|e| tracing::error!("Error checking token revocation: {e}"))?;
+                let revoked_before = status.revoked_before.map(|t| t.timestamp_millis());
+                self.revocation_cache.store_user(actor_id, revoked_before);
+                revoked_before
+            }
+        };
+
+        Ok(revoked_before.is_some_and(|cutoff| claims.issued_before(cutoff)))
+    }
+
+    /// Enforces the identifier rule of the password policy, which needs the user's
+    /// username and email address and therefore cannot run inside `ValidatedJson`.
+    async fn check_password_identifiers(
+        &self,
+        user_id: &str,
+        password: &str,
+    ) -> Result<(), AppError> {
+        let identity = self
+            .repo
+            .find_identity(&self.pool, user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user identity: {e}"))?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+        let identifiers: Vec<&str> = std::iter::once(identity.username.as_str())
+            .chain(identity.email.as_deref())
+            .collect();
+
+        PASSWORD_POLICY
+            .check_identifiers(password, &identifiers)
+            .map_err(|e| AppError::ValidationError(e.to_string()))
+    }
 }
 
 impl AuthServiceTrait for PostgresAuthService {
 
     /// It hashes the password and stores it in the database.
     async fn create_user_auth(&self, auth_user: AuthUserDto) -> Result<(), AppError> {
+        self.check_password_identifiers(&auth_user.user_id, &auth_user.password)
+            .await?;
+
         let mut tx = self.pool.begin().await?;
 
         let password_hash =
-            hash_util::hash_password(&auth_user.password).map_err(|_| AppError::InternalError)?;
+            hash_util::hash_password(&auth_user.password, &self.config.password_hash_params)
+                .map_err(|_| AppError::InternalError)?;
 
-        let user_auth = UserAuth {
-            user_id: auth_user.user_id,
-            password_hash,
-        };
+        let user_auth = UserAuth::new(auth_user.user_id, password_hash);
 
         match self.repo.create(&mut tx, user_auth).await {
             Ok(()) => {
@@ -60,29 +523,920 @@ impl AuthServiceTrait for PostgresAuthService {
         }
     }
 
+    /// Imports all credentials in one transaction. Hashes are stored as given and replaced
+    /// by an Argon2id hash when the user first logs in with their password.
+    async fn import_credentials(
+        &self,
+        payload: ImportCredentialsDto,
+        imported_by: &str,
+    ) -> Result<ImportCredentialsResultDto, AppError> {
+        // Checked here rather than with `validator`, whose length errors would echo the hashes.
+        if payload.credentials.is_empty() || payload.credentials.len() > MAX_IMPORTED_CREDENTIALS {
+            return Err(AppError::ValidationError(format!(
+                "Import 1 to {MAX_IMPORTED_CREDENTIALS} credentials at a time"
+            )));
+        }
+
+        let mut imported = Vec::new();
+        let mut skipped = Vec::new();
+
+        let mut tx = self.pool.begin().await?;
+        for credential in payload.credentials {
+            if !hash_util::is_supported_hash(&credential.password_hash) {
+                skipped.push(SkippedCredentialDto {
+                    user_id: credential.user_id,
+                    reason: "Unsupported password hash format".into(),
+                });
+                continue;
+            }
+
+            let user_auth = UserAuth::new(credential.user_id, credential.password_hash);
+            let inserted = self
+                .repo
+                .import(&mut tx, &user_auth)
+                .await
+                .inspect_err(|e| tracing::error!("Error importing credential: {e}"))?;
+            if inserted {
+                imported.push(user_auth.user_id);
+            } else {
+                skipped.push(SkippedCredentialDto {
+                    user_id: user_auth.user_id,
+                    reason: "User not found or already has a password".into(),
+                });
+            }
+        }
+        tx.commit().await?;
+
+        tracing::info!(
+            imported_by,
+            imported = imported.len(),
+            skipped = skipped.len(),
+            "Credentials imported"
+        );
+        Ok(ImportCredentialsResultDto { imported, skipped })
+    }
+
     /// Authenticates a user by checking the provided credentials
     /// against the stored credentials in the database.
-    /// If the credentials are valid, it generates a JWT token for the user.
+    /// If the credentials are valid, it generates a JWT access token and a refresh token
+    /// starting a new session.
     /// If the credentials are invalid, it returns an error.
-    async fn login_user(&self, auth_payload: AuthPayload) -> Result<AuthBody, AppError> {
+    /// With `require_email_verification`, users with an unverified email address are refused.
+    /// For users with two-factor authentication, an MFA challenge is returned instead of tokens.
+    ///
+    /// Unknown users, wrong passwords and locked accounts all yield `WrongCredentials` after
+    /// the same password hashing work, so responses do not reveal whether an account exists.
+    /// Repeated failures lock the account temporarily and throttle the client IP.
+    /// A password hash made with other parameters than the configured ones is replaced.
+    async fn login_user(
+        &self,
+        auth_payload: AuthPayload,
+        client: &ClientInfo,
+    ) -> Result<LoginResponse, AppError> {
+        let client_ip = client.ip;
         if auth_payload.client_id.is_empty() || auth_payload.client_secret.is_empty() {
             return Err(AppError::MissingCredentials);
         }
 
+        self.check_login_throttle(client_ip)?;
+
         let user_auth = self
             .repo
             .find_by_user_name(self.pool.clone(), auth_payload.client_id.clone())
             .await
             .map_err(AppError::DatabaseError)?;
 
-        let user_auth = user_auth.ok_or(AppError::NotFound("User not found".to_string()))?;
+        let Some(user_auth) = user_auth else {
+            hash_util::verify_dummy_password(
+                &auth_payload.client_secret,
+                &self.config.password_hash_params,
+            );
+            return Err(self.login_failed(client_ip));
+        };
+
+        let password_check = hash_util::check_password(
+            &user_auth.password_hash,
+            &auth_payload.client_secret,
+            &self.config.password_hash_params,
+        );
+
+        if user_auth.is_locked() {
+            tracing::warn!(
+                user_id = %user_auth.user_id,
+                ?client_ip,
+                "Login attempt on locked account"
+            );
+            return Err(self.login_failed(client_ip));
+        }
+
+        if password_check == PasswordCheck::Mismatch {
+            self.record_failed_login(&user_auth.user_id, client_ip)
+                .await?;
+            return Err(self.login_failed(client_ip));
+        }
+
+        if password_check == PasswordCheck::Outdated {
+            self.rehash_password(&user_auth, &auth_payload.client_secret)
+                .await;
+        }
+
+        if user_auth.failed_login_attempts > 0 || user_auth.locked_until.is_some() {
+            self.repo
+                .reset_failed_logins(&self.pool, &user_auth.user_id)
+                .await
+                .inspect_err(|e| tracing::error!("Error resetting failed logins: {e}"))?;
+        }
+
+        self.finish_login(&user_auth.user_id, client).await
+    }
+
+    /// Logs in a user whose identity was established without a password login.
+    /// Email verification and two-factor authentication apply as for a password login.
+    async fn login_authenticated_user(
+        &self,
+        user_id: &str,
+        client: &ClientInfo,
+    ) -> Result<LoginResponse, AppError> {
+        self.finish_login(user_id, client).await
+    }
+
+    /// Emails a single-use login link if a user with credentials has the given email address.
+    /// Earlier links of the user are invalidated. Requests count against the address whether
+    /// or not it is known, and unknown addresses are silently ignored, so the endpoint can
+    /// neither flood a mailbox nor be used to probe accounts.
+    async fn request_magic_link(&self, payload: MagicLinkRequestDto) -> Result<(), AppError> {
+        if !self.config.magic_link_enabled {
+            return Err(AppError::Forbidden);
+        }
+
+        let limiter_key = payload.email.to_lowercase();
+        if let Some(retry_after) = self.magic_link_limiter.retry_after(&limiter_key) {
+            tracing::warn!("Magic link requests throttled for an email address");
+            return Err(AppError::TooManyRequests(retry_after.as_secs().max(1)));
+        }
+        self.magic_link_limiter.hit(&limiter_key);
+
+        let identity = self
+            .repo
+            .find_identity_by_email(&self.pool, &payload.email)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user by email: {e}"))?;
+        let Some(UserIdentity {
+            id: user_id,
+            username,
+            email,
+            ..
+        }) = identity
+        else {
+            tracing::info!("Magic link requested for an unknown or shared email address");
+            return Ok(());
+        };
+
+        let token = token_util::generate_token();
+        let ttl = self.config.magic_link_ttl_secs;
+        let record = MagicLinkToken {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.clone(),
+            token_hash: token_util::hash_token(&token),
+            expires_at: Utc::now() + Duration::seconds(ttl),
+            used_at: None,
+        };
+
+        let mut tx = self.pool.begin().await?;
+        self.magic_link_repo
+            .invalidate_for_user(&mut tx, &user_id)
+            .await?;
+        self.magic_link_repo.delete_stale(&mut tx).await?;
+        self.magic_link_repo
+            .create(&mut tx, &record)
+            .await
+            .inspect_err(|e| tracing::error!("Error creating magic link token: {e}"))?;
+        tx.commit().await?;
+
+        let link = format!("{}?token={token}", self.config.magic_link_url);
+        let email = Email {
+            to: email.unwrap_or(payload.email),
+            subject: "Your login link".into(),
+            body: format!(
+                "Hello {username},\n\n\
+                 Use the link below to log in. It can be used once and expires in {} minutes.\n\n\
+                 {link}\n\n\
+                 If you did not request a login link, you can ignore this email.\n",
+                ttl / 60
+            ),
+        };
+        mailer::send_in_background(self.mailer.clone(), email);
+
+        tracing::info!(user_id, "Magic link requested");
+        Ok(())
+    }
+
+    /// Consumes a magic link token and logs its user in. Email verification and two-factor
+    /// authentication apply as for a password login; the account lockout does not, since
+    /// it guards against password guessing.
+    async fn login_with_magic_link(
+        &self,
+        payload: MagicLinkLoginDto,
+        client: &ClientInfo,
+    ) -> Result<LoginResponse, AppError> {
+        if !self.config.magic_link_enabled {
+            return Err(AppError::Forbidden);
+        }
+
+        let token_hash = token_util::hash_token(&payload.token);
+
+        let mut tx = self.pool.begin().await?;
+        let token = self
+            .magic_link_repo
+            .find_by_hash_for_update(&mut tx, &token_hash)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving magic link token: {e}"))?
+            .ok_or(AppError::InvalidToken)?;
+
+        if token.used_at.is_some() || token.expires_at <= Utc::now() {
+            return Err(AppError::InvalidToken);
+        }
+
+        self.magic_link_repo
+            .invalidate_for_user(&mut tx, &token.user_id)
+            .await?;
+        tx.commit().await?;
+
+        tracing::info!(user_id = %token.user_id, "Magic link used");
+        self.finish_login(&token.user_id, client).await
+    }
+
+    /// Exchanges an MFA challenge and a second factor for a token pair.
+    /// Wrong codes count against the challenge, the account lockout and the client IP;
+    /// after `MFA_CHALLENGE_MAX_ATTEMPTS` wrong codes the challenge is void.
+    async fn complete_mfa_login(
+        &self,
+        payload: MfaLoginDto,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, AppError> {
+        let client_ip = client.ip;
+        self.check_login_throttle(client_ip)?;
+
+        let token_hash = token_util::hash_token(&payload.mfa_token);
+
+        let mut tx = self.pool.begin().await?;
+
+        let challenge = self
+            .mfa_challenge_repo
+            .find_by_hash_for_update(&mut tx, &token_hash)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving MFA challenge: {e}"))?
+            .ok_or(AppError::InvalidToken)?;
+
+        if challenge.used_at.is_some()
+            || challenge.expires_at <= Utc::now()
+            || challenge.failed_attempts >= MFA_CHALLENGE_MAX_ATTEMPTS
+        {
+            return Err(AppError::InvalidToken);
+        }
+
+        let locked = self
+            .repo
+            .find_by_user_id(&self.pool, &challenge.user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user auth: {e}"))?
+            .is_none_or(|user_auth| user_auth.is_locked());
+        if locked {
+            return Err(self.login_failed(client_ip));
+        }
+
+        // Two-factor authentication may have been disabled since the challenge was issued.
+        let user_totp = self
+            .mfa_repo
+            .find_totp_for_update(&mut tx, &challenge.user_id)
+            .await?
+            .filter(UserTotp::is_enabled)
+            .ok_or(AppError::InvalidToken)?;
+
+        if !self
+            .verify_second_factor(&mut tx, &user_totp, &payload.code)
+            .await?
+        {
+            self.mfa_challenge_repo
+                .record_failed_attempt(&mut tx, &challenge.id)
+                .await?;
+            tx.commit().await?;
+
+            self.record_failed_login(&challenge.user_id, client_ip)
+                .await?;
+            return Err(self.login_failed(client_ip));
+        }
+
+        self.mfa_challenge_repo
+            .mark_used(&mut tx, &challenge.id)
+            .await?;
+        let auth_body = self
+            .start_session(&mut tx, &challenge.user_id, client)
+            .await?;
+        tx.commit().await?;
+
+        Ok(auth_body)
+    }
+
+    /// Reports whether two-factor authentication is enabled and how many recovery codes are left.
+    async fn mfa_status(&self, claims: &Claims) -> Result<MfaStatusDto, AppError> {
+        let totp_enabled = self
+            .mfa_repo
+            .find_totp(&self.pool, &claims.sub)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving TOTP secret: {e}"))?
+            .is_some_and(|user_totp| user_totp.is_enabled());
+        let recovery_codes_remaining = if totp_enabled {
+            self.mfa_repo
+                .count_recovery_codes(&self.pool, &claims.sub)
+                .await?
+        } else {
+            0
+        };
+
+        Ok(MfaStatusDto {
+            totp_enabled,
+            recovery_codes_remaining,
+        })
+    }
+
+    /// Stores a new pending secret, replacing an unconfirmed one, and returns it together
+    /// with its `otpauth://` URI labelled with the username.
+    async fn enroll_totp(&self, claims: &Claims) -> Result<TotpEnrollmentDto, AppError> {
+        let identity = self
+            .repo
+            .find_identity(&self.pool, &claims.sub)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user identity: {e}"))?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+        let secret = totp::generate_secret();
+        let totp = Totp::new(&secret, &self.config.mfa_issuer, &identity.username)
+            .ok_or(AppError::InternalError)?;
+
+        let mut tx = self.pool.begin().await?;
+        let saved = self
+            .mfa_repo
+            .save_pending_totp(&mut tx, &claims.sub, &secret)
+            .await
+            .inspect_err(|e| tracing::error!("Error storing TOTP secret: {e}"))?;
+        if !saved {
+            return Err(AppError::ValidationError(
+                "Two-factor authentication is already enabled".into(),
+            ));
+        }
+        tx.commit().await?;
+
+        Ok(TotpEnrollmentDto {
+            otpauth_uri: totp.otpauth_uri(),
+            secret,
+        })
+    }
+
+    /// Enables two-factor authentication once the user proves their authenticator app
+    /// produces codes for the pending secret.
+    async fn confirm_totp(
+        &self,
+        claims: &Claims,
+        payload: MfaCodeDto,
+    ) -> Result<RecoveryCodesDto, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let user_totp = self
+            .mfa_repo
+            .find_totp_for_update(&mut tx, &claims.sub)
+            .await?
+            .ok_or_else(|| {
+                AppError::ValidationError("Two-factor authentication has not been set up".into())
+            })?;
+        if user_totp.is_enabled() {
+            return Err(AppError::ValidationError(
+                "Two-factor authentication is already enabled".into(),
+            ));
+        }
+
+        let now = Utc::now().timestamp() as u64;
+        let step = self
+            .totp(&user_totp)?
+            .verify(&payload.code, now, self.config.totp_skew_steps, None)
+            .ok_or(AppError::WrongCredentials)?;
+
+        self.mfa_repo
+            .enable_totp(&mut tx, &claims.sub, step)
+            .await
+            .inspect_err(|e| tracing::error!("Error enabling TOTP: {e}"))?;
+        let recovery_codes = self.replace_recovery_codes(&mut tx, &claims.sub).await?;
+        tx.commit().await?;
+
+        tracing::info!(user_id = %claims.sub, "Two-factor authentication enabled");
+        Ok(recovery_codes)
+    }
+
+    /// Removes the TOTP secret and recovery codes.
+    async fn disable_totp(
+        &self,
+        claims: &Claims,
+        payload: MfaCodeDto,
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        self.require_second_factor(&mut tx, &claims.sub, &payload.code, client)
+            .await?;
+        self.mfa_repo
+            .delete_totp(&mut tx, &claims.sub)
+            .await
+            .inspect_err(|e| tracing::error!("Error deleting TOTP secret: {e}"))?;
+        tx.commit().await?;
 
-        if !hash_util::verify_password(&user_auth.password_hash, &auth_payload.client_secret) {
+        tracing::warn!(user_id = %claims.sub, "Two-factor authentication disabled");
+        Ok(())
+    }
+
+    /// Invalidates all recovery codes and returns new ones.
+    async fn regenerate_recovery_codes(
+        &self,
+        claims: &Claims,
+        payload: MfaCodeDto,
+        client: &ClientInfo,
+    ) -> Result<RecoveryCodesDto, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        self.require_second_factor(&mut tx, &claims.sub, &payload.code, client)
+            .await?;
+        let recovery_codes = self.replace_recovery_codes(&mut tx, &claims.sub).await?;
+        tx.commit().await?;
+
+        tracing::info!(user_id = %claims.sub, "Recovery codes regenerated");
+        Ok(recovery_codes)
+    }
+
+    /// Rotates the presented refresh token.
+    /// A token that was already used or revoked indicates the token leaked, so the whole
+    /// family is revoked and the caller has to log in again.
+    async fn refresh_token(
+        &self,
+        payload: RefreshTokenDto,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, AppError> {
+        let token_hash = token_util::hash_token(&payload.refresh_token);
+
+        let mut tx = self.pool.begin().await?;
+
+        let token = self
+            .refresh_token_repo
+            .find_by_hash_for_update(&mut tx, &token_hash)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving refresh token: {e}"))?
+            .ok_or(AppError::InvalidToken)?;
+
+        if token.used_at.is_some() || token.revoked_at.is_some() {
+            let revoked = self
+                .refresh_token_repo
+                .revoke_family(&mut tx, &token.family_id)
+                .await?;
+            tx.commit().await?;
+
+            tracing::warn!(
+                user_id = %token.user_id,
+                family_id = %token.family_id,
+                revoked,
+                "Refresh token reuse detected, token family revoked"
+            );
+            return Err(AppError::InvalidToken);
+        }
+
+        if token.expires_at <= Utc::now() {
+            return Err(AppError::InvalidToken);
+        }
+
+        self.refresh_token_repo.mark_used(&mut tx, &token.id).await?;
+        let auth_body = self
+            .issue_token_pair(&mut tx, &token.user_id, &token.family_id, client)
+            .await?;
+        tx.commit().await?;
+
+        Ok(auth_body)
+    }
+
+    /// Revokes the current access token so it is rejected by `jwt_auth` until it expires,
+    /// together with its session and the session's refresh tokens.
+    /// If a refresh token of the same user is supplied, its whole family is revoked as well.
+    async fn logout(&self, claims: &Claims, payload: LogoutDto) -> Result<(), AppError> {
+        let expires_at =
+            DateTime::<Utc>::from_timestamp(claims.exp as i64, 0).ok_or(AppError::InvalidToken)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        self.revocation_repo
+            .revoke_token(&mut tx, &claims.jti, &claims.sub, expires_at)
+            .await
+            .inspect_err(|e| tracing::error!("Error revoking token: {e}"))?;
+
+        if let Some(sid) = &claims.sid {
+            self.session_repo.revoke(&mut tx, sid, &claims.sub).await?;
+            self.refresh_token_repo.revoke_family(&mut tx, sid).await?;
+        }
+
+        if let Some(refresh_token) = payload.refresh_token.filter(|t| !t.is_empty()) {
+            let token_hash = token_util::hash_token(&refresh_token);
+            let token = self
+                .refresh_token_repo
+                .find_by_hash_for_update(&mut tx, &token_hash)
+                .await?;
+            if let Some(token) = token.filter(|t| t.user_id == claims.sub) {
+                self.refresh_token_repo
+                    .revoke_family(&mut tx, &token.family_id)
+                    .await?;
+            }
+        }
+
+        // Opportunistically drop revocation records that are no longer needed.
+        self.revocation_repo.delete_expired(&mut tx).await?;
+
+        tx.commit().await?;
+
+        self.revocation_cache.store_token(&claims.jti, true, claims.exp);
+        if let Some(sid) = &claims.sid {
+            self.revocation_cache
+                .store_revoked_session(sid, self.issued_tokens_exp());
+        }
+        Ok(())
+    }
+
+    /// Verifies the current password, enforces the password policy on the new one
+    /// and stores its hash. Every token of the user is revoked and the other sessions are
+    /// ended, while the caller's session continues with a new token pair.
+    async fn change_password(
+        &self,
+        claims: &Claims,
+        payload: ChangePasswordDto,
+        client: &ClientInfo,
+    ) -> Result<Option<AuthBody>, AppError> {
+        let user_auth = self
+            .repo
+            .find_by_user_id(&self.pool, &claims.sub)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user auth: {e}"))?
+            .ok_or(AppError::WrongCredentials)?;
+
+        if !hash_util::verify_password(&user_auth.password_hash, &payload.current_password) {
             return Err(AppError::WrongCredentials);
         }
+        if payload.new_password == payload.current_password {
+            return Err(AppError::ValidationError(
+                "New password must differ from the current password".into(),
+            ));
+        }
+        self.check_password_identifiers(&claims.sub, &payload.new_password)
+            .await?;
 
-        let token = make_jwt_token(&user_auth.user_id).map_err(|_| AppError::InternalError)?;
+        let password_hash =
+            hash_util::hash_password(&payload.new_password, &self.config.password_hash_params)
+                .map_err(|_| AppError::InternalError)?;
+
+        let mut tx = self.pool.begin().await?;
+        self.repo
+            .update_password(&mut tx, &claims.sub, &password_hash)
+            .await
+            .inspect_err(|e| tracing::error!("Error updating password: {e}"))?;
+        let revoked_before = Utc::now();
+        self.revocation_repo
+            .revoke_all_for_user(&mut tx, &claims.sub, revoked_before, &claims.sub)
+            .await?;
+        self.refresh_token_repo
+            .revoke_all_for_user(&mut tx, &claims.sub)
+            .await?;
+        let session_ids = self
+            .session_repo
+            .revoke_all_for_user(&mut tx, &claims.sub, claims.sid.as_deref())
+            .await
+            .inspect_err(|e| tracing::error!("Error revoking sessions: {e}"))?;
+        let auth_body = match &claims.sid {
+            Some(sid) => Some(
+                self.issue_token_pair(&mut tx, &claims.sub, sid, client)
+                    .await?,
+            ),
+            None => None,
+        };
+        tx.commit().await?;
+
+        self.revocation_cache
+            .store_user(&claims.sub, Some(revoked_before.timestamp_millis()));
+        let exp = self.issued_tokens_exp();
+        for session_id in &session_ids {
+            self.revocation_cache.store_revoked_session(session_id, exp);
+        }
+        tracing::info!(
+            user_id = %claims.sub,
+            sessions = session_ids.len(),
+            "Password changed, other sessions revoked"
+        );
+        Ok(auth_body)
+    }
+
+    /// Emails a single-use password reset link if a user with credentials has the given
+    /// email address. Earlier reset tokens of the user are invalidated.
+    /// Unknown addresses are silently ignored so the endpoint cannot be used to probe accounts.
+    async fn request_password_reset(
+        &self,
+        payload: PasswordResetRequestDto,
+    ) -> Result<(), AppError> {
+        let identity = self
+            .repo
+            .find_identity_by_email(&self.pool, &payload.email)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user by email: {e}"))?;
+        let Some(UserIdentity {
+            id: user_id,
+            username,
+            email,
+            ..
+        }) = identity
+        else {
+            tracing::info!("Password reset requested for an unknown or shared email address");
+            return Ok(());
+        };
+
+        let token = token_util::generate_token();
+        let ttl = self.config.password_reset_token_ttl_secs;
+        let record = PasswordResetToken {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.clone(),
+            token_hash: token_util::hash_token(&token),
+            expires_at: Utc::now() + Duration::seconds(ttl),
+            used_at: None,
+        };
+
+        let mut tx = self.pool.begin().await?;
+        self.password_reset_repo
+            .invalidate_for_user(&mut tx, &user_id)
+            .await?;
+        self.password_reset_repo.delete_stale(&mut tx).await?;
+        self.password_reset_repo
+            .create(&mut tx, &record)
+            .await
+            .inspect_err(|e| tracing::error!("Error creating password reset token: {e}"))?;
+        tx.commit().await?;
+
+        let link = format!("{}?token={token}", self.config.password_reset_url);
+        let email = Email {
+            to: email.unwrap_or(payload.email),
+            subject: "Reset your password".into(),
+            body: format!(
+                "Hello {username},\n\n\
+                 We received a request to reset your password. Use the link below to choose a \
+                 new one. It can be used once and expires in {} minutes.\n\n\
+                 {link}\n\n\
+                 If you did not request a password reset, you can ignore this email.\n",
+                ttl / 60
+            ),
+        };
+        mailer::send_in_background(self.mailer.clone(), email);
+
+        tracing::info!(user_id, "Password reset requested");
+        Ok(())
+    }
+
+    /// Sets a new password using a password reset token. The token is consumed, and every
+    /// access and refresh token of the user is revoked since the account may have been taken over.
+    async fn confirm_password_reset(
+        &self,
+        payload: PasswordResetConfirmDto,
+    ) -> Result<(), AppError> {
+        let token_hash = token_util::hash_token(&payload.token);
+
+        let mut tx = self.pool.begin().await?;
+
+        let token = self
+            .password_reset_repo
+            .find_by_hash_for_update(&mut tx, &token_hash)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving password reset token: {e}"))?
+            .ok_or(AppError::InvalidToken)?;
+
+        if token.used_at.is_some() || token.expires_at <= Utc::now() {
+            return Err(AppError::InvalidToken);
+        }
+
+        self.check_password_identifiers(&token.user_id, &payload.new_password)
+            .await?;
+        let password_hash =
+            hash_util::hash_password(&payload.new_password, &self.config.password_hash_params)
+                .map_err(|_| AppError::InternalError)?;
+
+        self.repo
+            .update_password(&mut tx, &token.user_id, &password_hash)
+            .await
+            .inspect_err(|e| tracing::error!("Error updating password: {e}"))?;
+        self.password_reset_repo
+            .invalidate_for_user(&mut tx, &token.user_id)
+            .await?;
+        let revoked_before = Utc::now();
+        self.revocation_repo
+            .revoke_all_for_user(&mut tx, &token.user_id, revoked_before, &token.user_id)
+            .await?;
+        self.refresh_token_repo
+            .revoke_all_for_user(&mut tx, &token.user_id)
+            .await?;
+        self.session_repo
+            .revoke_all_for_user(&mut tx, &token.user_id, None)
+            .await?;
+        tx.commit().await?;
+
+        self.revocation_cache
+            .store_user(&token.user_id, Some(revoked_before.timestamp_millis()));
+        tracing::warn!(user_id = %token.user_id, "Password reset, all tokens revoked");
+        Ok(())
+    }
+
+    /// Revokes every token of a user by recording a cut-off instant:
+    /// access tokens issued before it are rejected, and all refresh tokens and
+    /// sessions are revoked.
+    async fn revoke_all_tokens(&self, user_id: &str, revoked_by: &str) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let revoked_before = Utc::now();
+        match self
+            .revocation_repo
+            .revoke_all_for_user(&mut tx, user_id, revoked_before, revoked_by)
+            .await
+        {
+            Ok(()) => {}
+            Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+                return Err(AppError::NotFound("User not found".into()));
+            }
+            Err(err) => return Err(err.into()),
+        }
+        let refresh_tokens = self
+            .refresh_token_repo
+            .revoke_all_for_user(&mut tx, user_id)
+            .await?;
+        self.session_repo
+            .revoke_all_for_user(&mut tx, user_id, None)
+            .await?;
+
+        tx.commit().await?;
+
+        self.revocation_cache
+            .store_user(user_id, Some(revoked_before.timestamp_millis()));
+        tracing::warn!(user_id, revoked_by, refresh_tokens, "All tokens revoked for user");
+        Ok(())
+    }
+
+    /// The token has the impersonated user's current roles and permissions, no refresh token
+    /// and no session, and lives `impersonation_token_ttl_secs`, at most an hour. It is revoked
+    /// together with the tokens of either user.
+    async fn impersonate_user(
+        &self,
+        claims: &Claims,
+        user_id: &str,
+        payload: ImpersonateDto,
+    ) -> Result<AuthBody, AppError> {
+        if user_id == claims.sub {
+            return Err(AppError::ValidationError("Cannot impersonate yourself".into()));
+        }
+
+        self.repo
+            .find_identity(&self.pool, user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user identity: {e}"))?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+        let authorities = self
+            .repo
+            .find_authorities(&self.pool, user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user authorities: {e}"))?;
+        if authorities
+            .permissions
+            .iter()
+            .any(|p| p == permissions::USERS_IMPERSONATE)
+        {
+            tracing::warn!(actor = %claims.sub, subject = user_id, "Impersonation refused");
+            return Err(AppError::Forbidden);
+        }
+
+        let ttl = self
+            .config
+            .impersonation_token_ttl_secs
+            .clamp(1, MAX_IMPERSONATION_TOKEN_TTL_SECS);
+        let token_claims = Claims::new(user_id, Duration::seconds(ttl))
+            .with_authorities(authorities.roles, authorities.permissions)
+            .with_actor(claims.sub.clone());
+        let access_token = make_jwt_token(&token_claims, &self.config.jwt_settings)?;
+
+        tracing::warn!(
+            actor = %claims.sub,
+            subject = user_id,
+            jti = %token_claims.jti,
+            reason = %payload.reason,
+            "Impersonation token issued"
+        );
+        Ok(AuthBody::new(access_token, ttl))
+    }
+
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<Session>, AppError> {
+        let sessions = self
+            .session_repo
+            .find_active_by_user(&self.pool, user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error listing sessions: {e}"))?;
+        Ok(sessions)
+    }
+
+    async fn revoke_session(&self, user_id: &str, session_id: &str) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        if !self.session_repo.revoke(&mut tx, session_id, user_id).await? {
+            return Err(AppError::NotFound("Session not found".into()));
+        }
+        self.refresh_token_repo
+            .revoke_family(&mut tx, session_id)
+            .await?;
+        tx.commit().await?;
+
+        self.revocation_cache
+            .store_revoked_session(session_id, self.issued_tokens_exp());
+        tracing::info!(user_id, session_id, "Session revoked");
+        Ok(())
+    }
+
+    async fn revoke_other_sessions(&self, claims: &Claims) -> Result<u64, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let session_ids = self
+            .session_repo
+            .revoke_all_for_user(&mut tx, &claims.sub, claims.sid.as_deref())
+            .await
+            .inspect_err(|e| tracing::error!("Error revoking sessions: {e}"))?;
+        for session_id in &session_ids {
+            self.refresh_token_repo
+                .revoke_family(&mut tx, session_id)
+                .await?;
+        }
+        tx.commit().await?;
+
+        let exp = self.issued_tokens_exp();
+        for session_id in &session_ids {
+            self.revocation_cache.store_revoked_session(session_id, exp);
+        }
+        tracing::info!(
+            user_id = %claims.sub,
+            sessions = session_ids.len(),
+            "Other sessions revoked"
+        );
+        Ok(session_ids.len() as u64)
+    }
 
-        Ok(AuthBody::new(token))
+    /// Checks the revocation state of a token and its session, consulting the in-process
+    /// cache first. Looking a token up in the database also records that its session was seen.
+    async fn is_token_revoked(&self, claims: &Claims) -> Result<bool, AppError> {
+        let cached_token = self.revocation_cache.token_revoked(&claims.jti);
+        let session_revoked = claims
+            .sid
+            .as_deref()
+            .is_some_and(|sid| self.revocation_cache.session_revoked(sid));
+        if cached_token == Some(true) || session_revoked {
+            return Ok(true);
+        }
+
+        let cached_user = self.revocation_cache.user_revoked_before(&claims.sub);
+        let revoked_before = match (cached_token, cached_user) {
+            (Some(false), Some(revoked_before)) => revoked_before,
+            _ => {
+                let status = self
+                    .revocation_repo
+                    .find_status(&self.pool, &claims.jti, &claims.sub, claims.sid.as_deref())
+                    .await
+                    .inspect_err(|e| tracing::error!("Error checking token revocation: {e}"))?;
+                let revoked_before = status.revoked_before.map(|t| t.timestamp_millis());
+
+                self.revocation_cache.store_token(&claims.jti, status.revoked, claims.exp);
+                self.revocation_cache.store_user(&claims.sub, revoked_before);
+
+                if let Some(sid) = &claims.sid {
+                    if status.session_revoked {
+                        self.revocation_cache
+                            .store_revoked_session(sid, self.issued_tokens_exp());
+                        return Ok(true);
+                    }
+                    if let Err(e) = self.session_repo.touch(&self.pool, sid).await {
+                        tracing::warn!("Error recording session use: {e}");
+                    }
+                }
+
+                if status.revoked {
+                    return Ok(true);
+                }
+                revoked_before
+            }
+        };
+
+        if revoked_before.is_some_and(|cutoff| claims.issued_before(cutoff)) {
+            return Ok(true);
+        }
+        match &claims.act {
+            Some(act) => self.actor_revoked(claims, &act.sub).await,
+            None => Ok(false),
+        }
     }
 }
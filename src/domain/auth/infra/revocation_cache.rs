@@ -0,0 +1,176 @@
+//! In-process cache in front of the token revocation tables, so `jwt_auth`
+//! does not have to query Postgres on every authenticated request.
+//!
+//! Revoked tokens and sessions are cached until their tokens expire, since a revocation
+//! never goes away.
+//! Negative answers ("not revoked") and per-user cut-offs are only trusted for the
+//! configured TTL, which bounds how long a revocation made by another instance
+//! can go unnoticed.
+
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+
+/// Entries are pruned once the cache grows beyond this many tokens.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+struct CachedToken {
+    revoked: bool,
+    exp: usize,
+    checked_at: Instant,
+}
+
+struct CachedUser {
+    revoked_before: Option<i64>,
+    checked_at: Instant,
+}
+
+/// Cache of token, session and user revocation state.
+pub struct RevocationCache {
+    ttl: Duration,
+    tokens: RwLock<HashMap<String, CachedToken>>,
+    users: RwLock<HashMap<String, CachedUser>>,
+    /// Revoked sessions and the instant (unix seconds) their last access token expires.
+    sessions: RwLock<HashMap<String, usize>>,
+}
+
+impl RevocationCache {
+    /// Creates an empty cache trusting negative answers for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            tokens: RwLock::new(HashMap::new()),
+            users: RwLock::new(HashMap::new()),
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cached revocation state of a token, or `None` if unknown or stale.
+    pub fn token_revoked(&self, jti: &str) -> Option<bool> {
+        let tokens = self.tokens.read().ok()?;
+        let entry = tokens.get(jti)?;
+        if entry.revoked || entry.checked_at.elapsed() < self.ttl {
+            Some(entry.revoked)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the cached revocation cut-off (unix milliseconds) of a user, or `None` if unknown
+    /// or stale.
+    pub fn user_revoked_before(&self, user_id: &str) -> Option<Option<i64>> {
+        let users = self.users.read().ok()?;
+        let entry = users.get(user_id)?;
+        (entry.checked_at.elapsed() < self.ttl).then_some(entry.revoked_before)
+    }
+
+    /// Returns whether a session is known to be revoked. Sessions that are not
+    /// cached may still have been revoked.
+    pub fn session_revoked(&self, sid: &str) -> bool {
+        self.sessions
+            .read()
+            .is_ok_and(|sessions| sessions.contains_key(sid))
+    }
+
+    /// Records the revocation state of a token expiring at `exp` (unix seconds).
+    pub fn store_token(&self, jti: &str, revoked: bool, exp: usize) {
+        let Ok(mut tokens) = self.tokens.write() else {
+            return;
+        };
+
+        if tokens.len() >= PRUNE_THRESHOLD {
+            let now = Utc::now().timestamp() as usize;
+            let ttl = self.ttl;
+            tokens.retain(|_, entry| {
+                entry.exp > now && (entry.revoked || entry.checked_at.elapsed() < ttl)
+            });
+        }
+
+        tokens.insert(
+            jti.to_string(),
+            CachedToken {
+                revoked,
+                exp,
+                checked_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Records a revoked session whose access tokens all expire by `exp` (unix seconds).
+    pub fn store_revoked_session(&self, sid: &str, exp: usize) {
+        let Ok(mut sessions) = self.sessions.write() else {
+            return;
+        };
+
+        if sessions.len() >= PRUNE_THRESHOLD {
+            let now = Utc::now().timestamp() as usize;
+            sessions.retain(|_, entry_exp| *entry_exp > now);
+        }
+
+        sessions.insert(sid.to_string(), exp);
+    }
+
+    /// Records the revocation cut-off (unix milliseconds) of a user.
+    pub fn store_user(&self, user_id: &str, revoked_before: Option<i64>) {
+        let Ok(mut users) = self.users.write() else {
+            return;
+        };
+
+        if users.len() >= PRUNE_THRESHOLD {
+            let ttl = self.ttl;
+            users.retain(|_, entry| entry.checked_at.elapsed() < ttl);
+        }
+
+        users.insert(
+            user_id.to_string(),
+            CachedUser {
+                revoked_before,
+                checked_at: Instant::now(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn future_exp() -> usize {
+        (Utc::now().timestamp() + 3600) as usize
+    }
+
+    #[test]
+    fn test_revoked_tokens_never_go_stale() {
+        let cache = RevocationCache::new(Duration::ZERO);
+        cache.store_token("revoked", true, future_exp());
+        cache.store_token("valid", false, future_exp());
+
+        assert_eq!(cache.token_revoked("revoked"), Some(true));
+        assert_eq!(cache.token_revoked("valid"), None);
+        assert_eq!(cache.token_revoked("unknown"), None);
+    }
+
+    #[test]
+    fn test_fresh_entries_are_returned() {
+        let cache = RevocationCache::new(Duration::from_secs(60));
+        cache.store_token("valid", false, future_exp());
+        cache.store_user("user", Some(42));
+
+        assert_eq!(cache.token_revoked("valid"), Some(false));
+        assert_eq!(cache.user_revoked_before("user"), Some(Some(42)));
+        assert_eq!(cache.user_revoked_before("other"), None);
+    }
+
+    #[test]
+    fn test_revoked_sessions_never_go_stale() {
+        let cache = RevocationCache::new(Duration::ZERO);
+        cache.store_revoked_session("revoked", future_exp());
+
+        assert!(cache.session_revoked("revoked"));
+        assert!(!cache.session_revoked("unknown"));
+    }
+}
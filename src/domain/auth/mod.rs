@@ -16,12 +16,33 @@ mod dto {
 mod infra {
     pub mod postgres_repository;
     pub mod postgres_service;
+    pub mod revocation_cache;
 }
 
 // Re-export commonly used items for convenience
-pub use api::routes::{user_auth_routes, UserAuthApiDoc};
-pub use domain::model::UserAuth;
-pub use domain::repository::UserAuthRepository;
+pub use api::routes::{
+    user_auth_credential_routes, user_auth_protected_routes, user_auth_routes, well_known_routes,
+    UserAuthApiDoc,
+};
+pub use domain::model::{
+    MagicLinkToken, MfaChallenge, PasswordResetToken, RefreshToken, Session,
+    TokenRevocationStatus, UserAuth, UserAuthorities, UserIdentity, UserTotp,
+};
+pub use domain::repository::{
+    MagicLinkRepository, MfaChallengeRepository, MfaRepository, PasswordResetRepository,
+    RefreshTokenRepository, SessionRepository, TokenRevocationRepository, UserAuthRepository,
+};
 pub use domain::service::AuthServiceTrait;
-pub use dto::auth_dto::AuthUserDto;
+pub use dto::auth_dto::{
+    AuthUserDto, ChangePasswordDto, ImpersonateDto, ImportCredentialsDto,
+    ImportCredentialsResultDto, ImportedCredentialDto, LoginResponse, LogoutDto,
+    MagicLinkLoginDto, MagicLinkRequestDto, MfaChallengeDto, MfaCodeDto, MfaLoginDto,
+    MfaStatusDto, PasswordResetConfirmDto, PasswordResetRequestDto, RecoveryCodesDto,
+    RefreshTokenDto, RevokedSessionsDto, SessionDto, SignupResponseDto, SkippedCredentialDto,
+    TotpEnrollmentDto,
+};
+pub use infra::postgres_repository::{
+    RefreshTokenRepo, SessionRepo, TokenRevocationRepo, UserAuthRepo,
+};
+pub use infra::revocation_cache::RevocationCache;
 pub use infra::postgres_service::PostgresAuthService as AuthService;
@@ -0,0 +1,234 @@
+use crate::{
+    common::{
+        app_state::AppState,
+        dto::RestApiResponse,
+        error::AppError,
+        jwt::Claims,
+        validated_json::ValidatedJson,
+    },
+    domain::oauth::{
+        CreateOAuthClientDto, CreatedOAuthClientDto, OAuthClientDto, OAuthErrorDto,
+        OAuthServiceTrait, TokenRequestDto, TokenResponseDto,
+    },
+};
+
+use axum::{
+    extract::{rejection::FormRejection, Path, State},
+    http::{
+        header::{AUTHORIZATION, CACHE_CONTROL, PRAGMA, WWW_AUTHENTICATE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Extension, Form, Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// The only grant type supported by the token endpoint.
+const CLIENT_CREDENTIALS_GRANT: &str = "client_credentials";
+
+/// Error of the token endpoint, rendered as described in RFC 6749, section 5.2.
+enum OAuthError {
+    InvalidRequest(String),
+    InvalidClient,
+    UnsupportedGrantType,
+    InvalidScope(String),
+    ServerError,
+}
+
+impl From<AppError> for OAuthError {
+    fn from(err: AppError) -> Self {
+        match err {
+            AppError::WrongCredentials => OAuthError::InvalidClient,
+            AppError::ValidationError(msg) => OAuthError::InvalidScope(msg),
+            err => {
+                tracing::error!("Error issuing client credentials token: {err}");
+                OAuthError::ServerError
+            }
+        }
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        let (status, error, description) = match self {
+            OAuthError::InvalidRequest(msg) => (StatusCode::BAD_REQUEST, "invalid_request", Some(msg)),
+            OAuthError::InvalidClient => (
+                StatusCode::UNAUTHORIZED,
+                "invalid_client",
+                Some("Client authentication failed".to_string()),
+            ),
+            OAuthError::UnsupportedGrantType => (
+                StatusCode::BAD_REQUEST,
+                "unsupported_grant_type",
+                Some(format!("Only `{CLIENT_CREDENTIALS_GRANT}` is supported")),
+            ),
+            OAuthError::InvalidScope(msg) => (StatusCode::BAD_REQUEST, "invalid_scope", Some(msg)),
+            OAuthError::ServerError => (StatusCode::INTERNAL_SERVER_ERROR, "server_error", None),
+        };
+        let body = Json(OAuthErrorDto {
+            error: error.to_string(),
+            error_description: description,
+        });
+        let headers = [(CACHE_CONTROL, "no-store"), (PRAGMA, "no-cache")];
+
+        if status == StatusCode::UNAUTHORIZED {
+            (status, headers, [(WWW_AUTHENTICATE, "Basic realm=\"oauth\"")], body).into_response()
+        } else {
+            (status, headers, body).into_response()
+        }
+    }
+}
+
+/// Extracts client credentials sent with HTTP Basic authentication, if any.
+fn basic_credentials(headers: &HeaderMap) -> Result<Option<(String, String)>, OAuthError> {
+    let Some(encoded) = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|header| header.strip_prefix("Basic "))
+    else {
+        return Ok(None);
+    };
+
+    // Client IDs and secrets are UUIDs and URL-safe base64, so the form encoding that
+    // RFC 6749 applies to them before Basic encoding never changes them.
+    let decoded = STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or(OAuthError::InvalidClient)?;
+    let (client_id, client_secret) = decoded.split_once(':').ok_or(OAuthError::InvalidClient)?;
+
+    Ok(Some((client_id.to_string(), client_secret.to_string())))
+}
+
+/// this function creates a router for the OAuth 2.0 token endpoint
+/// it supports the `client_credentials` grant
+#[utoipa::path(
+    post,
+    path = "/oauth/token",
+    request_body(content = TokenRequestDto, content_type = "application/x-www-form-urlencoded"),
+    description = "Issues an access token to a registered client (RFC 6749, section 4.4). \
+        The client authenticates with HTTP Basic authentication or with `client_id` and \
+        `client_secret` in the form body.",
+    responses(
+        (status = 200, description = "Access token issued", body = TokenResponseDto),
+        (status = 400, description = "Invalid request, unsupported grant type or invalid scope",
+            body = OAuthErrorDto),
+        (status = 401, description = "Client authentication failed", body = OAuthErrorDto)
+    ),
+    security((), ("basic_auth" = [])),
+    tag = "OAuth"
+)]
+pub async fn token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    payload: Result<Form<TokenRequestDto>, FormRejection>,
+) -> Response {
+    match issue_token(&state, &headers, payload).await {
+        Ok(token) => (
+            [(CACHE_CONTROL, "no-store"), (PRAGMA, "no-cache")],
+            Json(token),
+        )
+            .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+async fn issue_token(
+    state: &AppState,
+    headers: &HeaderMap,
+    payload: Result<Form<TokenRequestDto>, FormRejection>,
+) -> Result<TokenResponseDto, OAuthError> {
+    let Form(payload) = payload.map_err(|e| OAuthError::InvalidRequest(e.body_text()))?;
+
+    match payload.grant_type.as_deref() {
+        Some(CLIENT_CREDENTIALS_GRANT) => {}
+        Some(_) => return Err(OAuthError::UnsupportedGrantType),
+        None => return Err(OAuthError::InvalidRequest("Missing `grant_type`".to_string())),
+    }
+
+    let (client_id, client_secret) =
+        match (basic_credentials(headers)?, payload.client_id, payload.client_secret) {
+            (Some(_), _, Some(_)) => {
+                return Err(OAuthError::InvalidRequest(
+                    "Use only one client authentication method".to_string(),
+                ));
+            }
+            (Some(credentials), _, None) => credentials,
+            (None, Some(client_id), Some(client_secret)) => (client_id, client_secret),
+            (None, _, _) => return Err(OAuthError::InvalidClient),
+        };
+
+    let scope = payload.scope.as_deref().filter(|scope| !scope.trim().is_empty());
+    let token = state
+        .oauth_service
+        .client_credentials_token(&client_id, &client_secret, scope)
+        .await?;
+    Ok(token)
+}
+
+/// this function creates a router for registering an OAuth client
+/// it returns the client secret once; only its hash is stored
+#[utoipa::path(
+    post,
+    path = "/oauth/clients",
+    request_body = CreateOAuthClientDto,
+    description = "Requires the `clients:manage` permission.",
+    responses(
+        (status = 201, description = "Client registered", body = CreatedOAuthClientDto),
+        (status = 400, description = "Invalid name, or a scope the caller does not hold"),
+        (status = 403, description = "Missing `clients:manage` permission")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "OAuth"
+)]
+pub async fn create_client(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<CreateOAuthClientDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let client = state.oauth_service.create_client(&claims, payload).await?;
+    Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::created(client)))
+}
+
+/// this function creates a router for listing the registered OAuth clients
+#[utoipa::path(
+    get,
+    path = "/oauth/clients",
+    description = "Requires the `clients:manage` permission.",
+    responses(
+        (status = 200, description = "Registered clients", body = [OAuthClientDto]),
+        (status = 403, description = "Missing `clients:manage` permission")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "OAuth"
+)]
+pub async fn list_clients(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let clients = state.oauth_service.list_clients().await?;
+    let clients: Vec<OAuthClientDto> = clients.into_iter().map(OAuthClientDto::from).collect();
+    Ok(RestApiResponse::success(clients))
+}
+
+/// this function creates a router for revoking an OAuth client
+#[utoipa::path(
+    delete,
+    path = "/oauth/clients/{id}",
+    params(("id" = String, Path, description = "Client ID")),
+    description = "Requires the `clients:manage` permission. The client can no longer obtain \
+        tokens; tokens already issued stay valid until they expire.",
+    responses(
+        (status = 204, description = "Client revoked"),
+        (status = 403, description = "Missing `clients:manage` permission"),
+        (status = 404, description = "No active client with this ID")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "OAuth"
+)]
+pub async fn revoke_client(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    state.oauth_service.revoke_client(&claims, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
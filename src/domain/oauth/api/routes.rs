@@ -0,0 +1,87 @@
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+
+use crate::common::{
+    app_state::AppState,
+    rbac::{permissions, RequirePermission},
+};
+
+use super::handlers;
+
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    OpenApi,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        super::handlers::token,
+        super::handlers::create_client,
+        super::handlers::list_clients,
+        super::handlers::revoke_client,
+    ),
+    components(schemas(
+        crate::domain::oauth::TokenRequestDto,
+        crate::domain::oauth::TokenResponseDto,
+        crate::domain::oauth::OAuthErrorDto,
+        crate::domain::oauth::CreateOAuthClientDto,
+        crate::domain::oauth::OAuthClientDto,
+        crate::domain::oauth::CreatedOAuthClientDto,
+    )),
+    tags(
+        (name = "OAuth", description = "OAuth 2.0 client registry and token endpoint")
+    ),
+    modifiers(&OAuthApiDoc)
+)]
+/// This struct is used to generate OpenAPI documentation for the OAuth routes.
+pub struct OAuthApiDoc;
+
+impl utoipa::Modify for OAuthApiDoc {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.as_mut().unwrap();
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("Input your `<your‑jwt>`"))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "basic_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Basic)
+                    .description(Some("Client ID and client secret"))
+                    .build(),
+            ),
+        )
+    }
+}
+
+/// This function creates a router for the public OAuth routes (the token endpoint).
+pub fn oauth_routes() -> Router<AppState> {
+    Router::new().route("/token", post(handlers::token))
+}
+
+/// This function creates a router for managing OAuth clients.
+/// It is nested under `/oauth/clients` behind the JWT middleware.
+pub fn oauth_client_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(handlers::list_clients)
+                .post(handlers::create_client)
+                .route_layer(RequirePermission(permissions::CLIENTS_MANAGE)),
+        )
+        .route(
+            "/{id}",
+            delete(handlers::revoke_client)
+                .route_layer(RequirePermission(permissions::CLIENTS_MANAGE)),
+        )
+}
@@ -0,0 +1,27 @@
+//! This module defines the `OAuthClient` model: a registered machine client that obtains
+//! access tokens with the `client_credentials` grant.
+
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+
+/// Represents a persisted OAuth client.
+///
+/// `id` is the `client_id` of the client. Only the SHA-256 hash of its secret is stored.
+/// Tokens issued to the client grant its scopes as permissions and no roles.
+#[derive(Debug, Clone, FromRow)]
+pub struct OAuthClient {
+    pub id: String,
+    pub name: String,
+    pub secret_hash: String,
+    pub scopes: Vec<String>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl OAuthClient {
+    /// Returns whether the client may obtain tokens.
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+}
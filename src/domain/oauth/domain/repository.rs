@@ -0,0 +1,38 @@
+//! This module defines the `OAuthClientRepository` trait, which abstracts
+//! the persistence of OAuth clients.
+
+use std::future::Future;
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::model::OAuthClient;
+
+/// Trait representing the repository contract for OAuth clients.
+pub trait OAuthClientRepository: Send + Sync {
+    /// Inserts a new client record within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        client: &OAuthClient,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Finds a client by its client ID.
+    fn find_by_id(
+        &self,
+        pool: &PgPool,
+        id: &str,
+    ) -> impl Future<Output = Result<Option<OAuthClient>, sqlx::Error>> + Send;
+
+    /// Lists all clients, newest first.
+    fn find_all(
+        &self,
+        pool: &PgPool,
+    ) -> impl Future<Output = Result<Vec<OAuthClient>, sqlx::Error>> + Send;
+
+    /// Revokes a client. Returns `false` if there is no such active client.
+    fn revoke(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+}
@@ -0,0 +1,42 @@
+//! This module defines the OAuth service trait: maintaining the client registry and
+//! issuing tokens to clients.
+
+use std::future::Future;
+
+use crate::{
+    common::{error::AppError, jwt::Claims},
+    domain::oauth::{CreateOAuthClientDto, CreatedOAuthClientDto, OAuthClient, TokenResponseDto},
+};
+
+/// Trait defining the contract for OAuth operations.
+pub trait OAuthServiceTrait: Send + Sync {
+    /// Registers a client on behalf of the caller described by `claims`.
+    /// The client secret is only returned here; afterwards only its hash is known.
+    fn create_client(
+        &self,
+        claims: &Claims,
+        payload: CreateOAuthClientDto,
+    ) -> impl Future<Output = Result<CreatedOAuthClientDto, AppError>> + Send;
+
+    /// Lists all registered clients.
+    fn list_clients(&self) -> impl Future<Output = Result<Vec<OAuthClient>, AppError>> + Send;
+
+    /// Revokes a client so that it can no longer obtain tokens.
+    fn revoke_client(
+        &self,
+        claims: &Claims,
+        id: &str,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Issues an access token for the `client_credentials` grant.
+    ///
+    /// `scope` is the space-delimited list of requested scopes; all scopes of the client are
+    /// granted if it is `None`. Returns `AppError::WrongCredentials` if the client cannot be
+    /// authenticated and `AppError::ValidationError` if a requested scope is not granted to it.
+    fn client_credentials_token(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        scope: Option<&str>,
+    ) -> impl Future<Output = Result<TokenResponseDto, AppError>> + Send;
+}
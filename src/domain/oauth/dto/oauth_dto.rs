@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::domain::oauth::OAuthClient;
+
+/// Request body for registering an OAuth client.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateOAuthClientDto {
+    /// Describes the client, e.g. "billing service".
+    #[validate(length(min = 1, max = 100, message = "Name must be 1 to 100 characters"))]
+    pub name: String,
+    /// Permissions the client may request; each must be held by the caller.
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<String>,
+}
+
+/// An OAuth client as listed to administrators. The secret is never shown again.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct OAuthClientDto {
+    pub client_id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_by: Option<String>,
+    #[serde(with = "crate::common::ts_format")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::common::ts_format::option")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<OAuthClient> for OAuthClientDto {
+    fn from(client: OAuthClient) -> Self {
+        Self {
+            client_id: client.id,
+            name: client.name,
+            scopes: client.scopes,
+            created_by: client.created_by,
+            created_at: client.created_at,
+            revoked_at: client.revoked_at,
+        }
+    }
+}
+
+/// A newly registered OAuth client, including its secret.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedOAuthClientDto {
+    /// Presented at `/oauth/token` together with `client_id`. It is only shown once.
+    pub client_secret: String,
+    #[serde(flatten)]
+    pub client: OAuthClientDto,
+}
+
+/// Form-encoded token request (RFC 6749, section 4.4.2).
+///
+/// The client authenticates either with HTTP Basic authentication or with `client_id` and
+/// `client_secret` in the body, not both.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct TokenRequestDto {
+    /// Must be `client_credentials`.
+    pub grant_type: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Space-delimited scopes to request. Defaults to all scopes of the client.
+    pub scope: Option<String>,
+}
+
+/// Successful token response (RFC 6749, section 5.1).
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TokenResponseDto {
+    pub access_token: String,
+    pub token_type: String,
+    /// Lifetime of the access token in seconds.
+    pub expires_in: i64,
+    /// Space-delimited scopes granted by the token.
+    pub scope: String,
+}
+
+/// Error response of the token endpoint (RFC 6749, section 5.2).
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OAuthErrorDto {
+    /// One of `invalid_request`, `invalid_client`, `unsupported_grant_type`, `invalid_scope`
+    /// or `server_error`.
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+}
@@ -0,0 +1,78 @@
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::domain::oauth::{OAuthClient, OAuthClientRepository};
+
+#[derive(Clone)]
+pub struct OAuthClientRepo;
+
+impl OAuthClientRepository for OAuthClientRepo {
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        client: &OAuthClient,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO oauth_clients
+            (id, name, secret_hash, scopes, created_by, created_at)
+            VALUES
+            ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(&client.id)
+        .bind(&client.name)
+        .bind(&client.secret_hash)
+        .bind(&client.scopes)
+        .bind(&client.created_by)
+        .bind(client.created_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, pool: &PgPool, id: &str) -> Result<Option<OAuthClient>, sqlx::Error> {
+        sqlx::query_as::<_, OAuthClient>(
+            r#"
+            SELECT id, name, secret_hash, scopes, created_by, created_at, revoked_at
+              FROM oauth_clients
+             WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    async fn find_all(&self, pool: &PgPool) -> Result<Vec<OAuthClient>, sqlx::Error> {
+        sqlx::query_as::<_, OAuthClient>(
+            r#"
+            SELECT id, name, secret_hash, scopes, created_by, created_at, revoked_at
+              FROM oauth_clients
+             ORDER BY created_at DESC
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    async fn revoke(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE oauth_clients
+               SET revoked_at = NOW()
+             WHERE id = $1
+               AND revoked_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    common::{
+        config::Config,
+        error::AppError,
+        jwt::{make_jwt_token, Claims},
+        token_util,
+    },
+    domain::oauth::{
+        domain::{
+            model::OAuthClient, repository::OAuthClientRepository, service::OAuthServiceTrait,
+        },
+        dto::oauth_dto::{CreateOAuthClientDto, CreatedOAuthClientDto, TokenResponseDto},
+        infra::postgres_repository::OAuthClientRepo,
+    },
+};
+
+/// Service for the OAuth client registry and the token endpoint.
+#[derive(Clone)]
+pub struct PostgresOAuthService {
+    pool: PgPool,
+    config: Config,
+    repo: OAuthClientRepo,
+}
+
+impl PostgresOAuthService {
+    /// constructor for the service.
+    pub fn new(pool: PgPool, config: Config) -> Arc<Self> {
+        Arc::new(Self {
+            pool,
+            config,
+            repo: OAuthClientRepo,
+        })
+    }
+}
+
+impl OAuthServiceTrait for PostgresOAuthService {
+    /// Registers a client limited to the requested scopes, each of which must be a
+    /// permission of the caller.
+    async fn create_client(
+        &self,
+        claims: &Claims,
+        payload: CreateOAuthClientDto,
+    ) -> Result<CreatedOAuthClientDto, AppError> {
+        let mut scopes = payload.scopes;
+        scopes.sort();
+        scopes.dedup();
+        if let Some(scope) = scopes.iter().find(|scope| !claims.has_permission(scope)) {
+            return Err(AppError::ValidationError(format!(
+                "Scope `{scope}` is not one of your permissions"
+            )));
+        }
+
+        let client_secret = token_util::generate_token();
+        let client = OAuthClient {
+            id: Uuid::new_v4().to_string(),
+            name: payload.name.trim().to_string(),
+            secret_hash: token_util::hash_token(&client_secret),
+            scopes,
+            created_by: Some(claims.actor_id().to_string()),
+            created_at: Utc::now(),
+            revoked_at: None,
+        };
+
+        let mut tx = self.pool.begin().await?;
+        self.repo
+            .create(&mut tx, &client)
+            .await
+            .inspect_err(|e| tracing::error!("Error creating OAuth client: {e}"))?;
+        tx.commit().await?;
+
+        tracing::info!(user_id = %claims.sub, client_id = %client.id, "OAuth client created");
+        Ok(CreatedOAuthClientDto {
+            client_secret,
+            client: client.into(),
+        })
+    }
+
+    async fn list_clients(&self) -> Result<Vec<OAuthClient>, AppError> {
+        let clients = self
+            .repo
+            .find_all(&self.pool)
+            .await
+            .inspect_err(|e| tracing::error!("Error listing OAuth clients: {e}"))?;
+        Ok(clients)
+    }
+
+    async fn revoke_client(&self, claims: &Claims, id: &str) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        let revoked = self.repo.revoke(&mut tx, id).await?;
+        if !revoked {
+            return Err(AppError::NotFound("OAuth client not found".into()));
+        }
+        tx.commit().await?;
+
+        tracing::info!(user_id = %claims.sub, client_id = id, "OAuth client revoked");
+        Ok(())
+    }
+
+    /// Authenticates the client and signs claims with the client as `sub` and `client_id`,
+    /// no roles, and the granted scopes as permissions.
+    async fn client_credentials_token(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        scope: Option<&str>,
+    ) -> Result<TokenResponseDto, AppError> {
+        let client = self
+            .repo
+            .find_by_id(&self.pool, client_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving OAuth client: {e}"))?
+            .filter(OAuthClient::is_active)
+            .filter(|client| client.secret_hash == token_util::hash_token(client_secret))
+            .ok_or(AppError::WrongCredentials)?;
+
+        let scopes = match scope {
+            Some(scope) => {
+                let mut requested: Vec<String> =
+                    scope.split_whitespace().map(str::to_string).collect();
+                requested.sort();
+                requested.dedup();
+                if let Some(scope) = requested.iter().find(|s| !client.scopes.contains(s)) {
+                    return Err(AppError::ValidationError(format!(
+                        "Scope `{scope}` is not granted to this client"
+                    )));
+                }
+                requested
+            }
+            None => client.scopes,
+        };
+
+        let ttl = self.config.access_token_ttl_secs;
+        let scope = scopes.join(" ");
+        let claims = Claims::new(&client.id, Duration::seconds(ttl))
+            .with_authorities(Vec::new(), scopes)
+            .with_client_id(client.id.clone());
+        let access_token = make_jwt_token(&claims, &self.config.jwt_settings)?;
+
+        tracing::info!(client_id = %client.id, scope, "Client credentials token issued");
+        Ok(TokenResponseDto {
+            access_token,
+            token_type: "Bearer".to_string(),
+            expires_in: ttl,
+            scope,
+        })
+    }
+}
@@ -0,0 +1,30 @@
+mod api {
+    mod handlers;
+    pub mod routes;
+}
+
+mod domain {
+    pub mod model;
+    pub mod repository;
+    pub mod service;
+}
+
+mod dto {
+    pub mod oauth_dto;
+}
+
+mod infra {
+    pub mod postgres_repository;
+    pub mod postgres_service;
+}
+
+// Re-export commonly used items for convenience
+pub use api::routes::{oauth_client_routes, oauth_routes, OAuthApiDoc};
+pub use domain::model::OAuthClient;
+pub use domain::repository::OAuthClientRepository;
+pub use domain::service::OAuthServiceTrait;
+pub use dto::oauth_dto::{
+    CreateOAuthClientDto, CreatedOAuthClientDto, OAuthClientDto, OAuthErrorDto, TokenRequestDto,
+    TokenResponseDto,
+};
+pub use infra::postgres_service::PostgresOAuthService as OAuthService;
@@ -0,0 +1,178 @@
+use crate::{
+    common::{
+        app_state::AppState,
+        client_ip::ClientInfo,
+        dto::RestApiResponse,
+        error::AppError,
+        jwt::{AuthMethod, Claims},
+    },
+    domain::{
+        auth::{AuthServiceTrait, LoginResponse},
+        oidc::{
+            AuthorizationUrlDto, ExternalIdentityDto, OidcCallbackOutcome, OidcCallbackQuery,
+            OidcServiceTrait,
+        },
+    },
+};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::CACHE_CONTROL, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Extension,
+};
+
+/// this function creates a router for starting a login at an identity provider
+/// it redirects the user to the provider
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/{provider}/authorize",
+    params(("provider" = String, Path, description = "Name of the identity provider")),
+    description = "Starts the authorization code flow with PKCE. \
+        The provider sends the user back to `/auth/oidc/{provider}/callback`.",
+    responses(
+        (status = 303, description = "Redirect to the provider's authorization endpoint"),
+        (status = 404, description = "Unknown identity provider")
+    ),
+    tag = "Oidc"
+)]
+pub async fn authorize(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+) -> Result<Redirect, AppError> {
+    let url = state.oidc_service.authorization_url(&provider, None).await?;
+    Ok(Redirect::to(&url))
+}
+
+/// this function creates a router for the callback of an identity provider
+/// it logs the user in, or links the identity if the authorization was started by `link`
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/{provider}/callback",
+    params(
+        ("provider" = String, Path, description = "Name of the identity provider"),
+        OidcCallbackQuery
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Token pair or MFA challenge as for `/auth/login`, \
+                or the linked identity",
+            body = LoginResponse
+        ),
+        (status = 400, description = "Provider returned an error, or invalid or expired state"),
+        (status = 401, description = "Code or ID token rejected"),
+        (status = 403, description = "No user for this identity"),
+        (status = 404, description = "Unknown identity provider")
+    ),
+    tag = "Oidc"
+)]
+pub async fn callback(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Path(provider): Path<String>,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<Response, AppError> {
+    if let Some(error) = query.error {
+        let message = match query.error_description {
+            Some(description) => format!("Identity provider returned `{error}`: {description}"),
+            None => format!("Identity provider returned `{error}`"),
+        };
+        return Err(AppError::ValidationError(message));
+    }
+    let (Some(code), Some(oidc_state)) = (query.code, query.state) else {
+        return Err(AppError::ValidationError("Missing `code` or `state`".into()));
+    };
+
+    let outcome = state
+        .oidc_service
+        .complete_authorization(&provider, &code, &oidc_state)
+        .await?;
+    match outcome {
+        OidcCallbackOutcome::Login { user_id } => {
+            let response = state
+                .auth_service
+                .login_authenticated_user(&user_id, &client)
+                .await?;
+            Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::success(response)).into_response())
+        }
+        OidcCallbackOutcome::Linked(identity) => {
+            Ok(RestApiResponse::success(ExternalIdentityDto::from(identity)).into_response())
+        }
+    }
+}
+
+/// this function creates a router for linking an identity at a provider to the current user
+/// it returns the URL to send the user to
+#[utoipa::path(
+    post,
+    path = "/auth/oidc/{provider}/link",
+    params(("provider" = String, Path, description = "Name of the identity provider")),
+    description = "Must be called with a user's access token. Once the user has logged in at \
+        the provider, the callback links the identity instead of logging in.",
+    responses(
+        (status = 200, description = "Authorization URL", body = AuthorizationUrlDto),
+        (status = 403, description = "Called with an API key or a client token"),
+        (status = 404, description = "Unknown identity provider")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Oidc"
+)]
+pub async fn link(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Extension(auth_method): Extension<AuthMethod>,
+    Path(provider): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_method != AuthMethod::Bearer {
+        return Err(AppError::Forbidden);
+    }
+
+    let authorization_url = state
+        .oidc_service
+        .authorization_url(&provider, Some(&claims.sub))
+        .await?;
+    Ok(RestApiResponse::success(AuthorizationUrlDto { authorization_url }))
+}
+
+/// this function creates a router for listing the identities linked to the current user
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/identities",
+    responses(
+        (status = 200, description = "Linked identities", body = [ExternalIdentityDto])
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Oidc"
+)]
+pub async fn list_identities(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let identities = state.oidc_service.list_identities(&claims).await?;
+    let identities: Vec<ExternalIdentityDto> =
+        identities.into_iter().map(ExternalIdentityDto::from).collect();
+    Ok(RestApiResponse::success(identities))
+}
+
+/// this function creates a router for unlinking an identity from the current user
+#[utoipa::path(
+    delete,
+    path = "/auth/oidc/identities/{id}",
+    params(("id" = String, Path, description = "ID of the linked identity")),
+    responses(
+        (status = 204, description = "Identity unlinked"),
+        (status = 400, description = "The identity is the user's only way to log in"),
+        (status = 404, description = "No such identity is linked to the caller")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Oidc"
+)]
+pub async fn unlink_identity(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    state.oidc_service.unlink_identity(&claims, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,67 @@
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+
+use crate::common::app_state::AppState;
+
+use super::handlers;
+
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    OpenApi,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        super::handlers::authorize,
+        super::handlers::callback,
+        super::handlers::link,
+        super::handlers::list_identities,
+        super::handlers::unlink_identity,
+    ),
+    components(schemas(
+        crate::domain::auth::LoginResponse,
+        crate::domain::oidc::AuthorizationUrlDto,
+        crate::domain::oidc::ExternalIdentityDto,
+    )),
+    tags(
+        (name = "Oidc", description = "Login through external OpenID Connect providers")
+    ),
+    modifiers(&OidcApiDoc)
+)]
+/// This struct is used to generate OpenAPI documentation for the OpenID Connect routes.
+pub struct OidcApiDoc;
+
+impl utoipa::Modify for OidcApiDoc {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.as_mut().unwrap();
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("Input your `<your‑jwt>`"))
+                    .build(),
+            ),
+        )
+    }
+}
+
+/// This function creates a router for the OpenID Connect routes that do not require a JWT.
+pub fn oidc_routes() -> Router<AppState> {
+    Router::new()
+        .route("/{provider}/authorize", get(handlers::authorize))
+        .route("/{provider}/callback", get(handlers::callback))
+}
+
+/// This function creates a router for the OpenID Connect routes that require a valid JWT.
+/// It is nested under `/auth/oidc` behind the JWT middleware.
+pub fn oidc_protected_routes() -> Router<AppState> {
+    Router::new()
+        .route("/{provider}/link", post(handlers::link))
+        .route("/identities", get(handlers::list_identities))
+        .route("/identities/{id}", delete(handlers::unlink_identity))
+}
@@ -0,0 +1,44 @@
+//! This module defines the models of logins through external OpenID Connect providers.
+
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+
+/// An identity at an external provider, linked to a user.
+#[derive(Debug, Clone, FromRow)]
+pub struct ExternalIdentity {
+    pub id: String,
+    pub user_id: String,
+    /// Name of the provider as configured in `OIDC_PROVIDERS`.
+    pub provider: String,
+    /// The provider's identifier of the user (the `sub` claim).
+    pub subject: String,
+    /// Email address the provider reported when the identity was linked.
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+/// A login started at a provider that has not returned yet.
+///
+/// Only the hash of the `state` parameter is stored. The nonce and the PKCE code verifier
+/// are only useful together with the authorization code and are consumed with the state.
+#[derive(Debug, Clone, FromRow)]
+pub struct OidcLoginState {
+    pub id: String,
+    pub state_hash: String,
+    pub provider: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    /// Set when a signed-in user links a new identity instead of logging in.
+    pub link_user_id: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Result of a completed authorization at a provider.
+#[derive(Debug, Clone)]
+pub enum OidcCallbackOutcome {
+    /// The identity belongs to this user, who is to be logged in.
+    Login { user_id: String },
+    /// The identity was linked to the user who started the authorization.
+    Linked(ExternalIdentity),
+}
@@ -0,0 +1,95 @@
+//! This module defines the repository traits for external identities and
+//! logins in progress at identity providers.
+
+use std::future::Future;
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::model::{ExternalIdentity, OidcLoginState};
+
+/// Trait representing the repository contract for external identities.
+pub trait ExternalIdentityRepository: Send + Sync {
+    /// Finds the identity with the given subject at a provider.
+    fn find_by_subject(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        provider: &str,
+        subject: &str,
+    ) -> impl Future<Output = Result<Option<ExternalIdentity>, sqlx::Error>> + Send;
+
+    /// Lists the identities linked to a user.
+    fn find_by_user(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Vec<ExternalIdentity>, sqlx::Error>> + Send;
+
+    /// Inserts a new identity within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        identity: &ExternalIdentity,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Records a login through an identity.
+    fn record_login(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Removes an identity of a user. Returns `false` if the user has no such identity.
+    fn delete(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+        user_id: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Counts the ways a user can log in: a password plus linked identities.
+    fn count_login_methods(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> impl Future<Output = Result<i64, sqlx::Error>> + Send;
+
+    /// Finds the users that verified the given email address, compared case-insensitively,
+    /// ignoring deleted users.
+    fn find_user_ids_by_email(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        email: &str,
+    ) -> impl Future<Output = Result<Vec<String>, sqlx::Error>> + Send;
+
+    /// Creates a user with the default role. Returns `None` if the username is taken.
+    fn create_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        username: &str,
+        email: &str,
+        email_verified: bool,
+    ) -> impl Future<Output = Result<Option<String>, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for logins in progress.
+pub trait OidcLoginStateRepository: Send + Sync {
+    /// Inserts a new login state within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        state: &OidcLoginState,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Removes and returns the login state with the given hash, so it can only be used once.
+    fn take_by_hash(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        state_hash: &str,
+    ) -> impl Future<Output = Result<Option<OidcLoginState>, sqlx::Error>> + Send;
+
+    /// Deletes login states that have expired.
+    fn delete_expired(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+}
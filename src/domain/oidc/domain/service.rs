@@ -0,0 +1,44 @@
+//! This module defines the OpenID Connect service trait: logging users in through external
+//! identity providers and managing the identities linked to them.
+
+use std::future::Future;
+
+use crate::{
+    common::{error::AppError, jwt::Claims},
+    domain::oidc::{ExternalIdentity, OidcCallbackOutcome},
+};
+
+/// Trait defining the contract for OpenID Connect operations.
+pub trait OidcServiceTrait: Send + Sync {
+    /// Starts an authorization at `provider` and returns the URL to send the user to.
+    /// With `link_user_id`, the resulting identity is linked to that user instead of
+    /// logging in.
+    fn authorization_url(
+        &self,
+        provider: &str,
+        link_user_id: Option<&str>,
+    ) -> impl Future<Output = Result<String, AppError>> + Send;
+
+    /// Completes an authorization with the code and state the provider sent the user back
+    /// with: exchanges the code, validates the ID token and resolves the identity to a user.
+    fn complete_authorization(
+        &self,
+        provider: &str,
+        code: &str,
+        state: &str,
+    ) -> impl Future<Output = Result<OidcCallbackOutcome, AppError>> + Send;
+
+    /// Lists the identities linked to the user described by `claims`.
+    fn list_identities(
+        &self,
+        claims: &Claims,
+    ) -> impl Future<Output = Result<Vec<ExternalIdentity>, AppError>> + Send;
+
+    /// Unlinks an identity from the user described by `claims`.
+    /// The last way for a user to log in cannot be removed.
+    fn unlink_identity(
+        &self,
+        claims: &Claims,
+        id: &str,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::domain::oidc::ExternalIdentity;
+
+/// Query parameters the provider sends the user back with (OpenID Connect Core,
+/// sections 3.1.2.5 and 3.1.2.6).
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct OidcCallbackQuery {
+    /// Authorization code, present on success.
+    pub code: Option<String>,
+    /// The `state` sent with the authorization request.
+    pub state: Option<String>,
+    /// Error code, present if the user or the provider refused the request.
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// URL to send the user to for linking an identity.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthorizationUrlDto {
+    pub authorization_url: String,
+}
+
+/// An identity at an external provider, as listed to the user it is linked to.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ExternalIdentityDto {
+    pub id: String,
+    pub provider: String,
+    /// The provider's identifier of the user.
+    pub subject: String,
+    pub email: Option<String>,
+    #[serde(with = "crate::common::ts_format")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::common::ts_format::option")]
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+impl From<ExternalIdentity> for ExternalIdentityDto {
+    fn from(identity: ExternalIdentity) -> Self {
+        Self {
+            id: identity.id,
+            provider: identity.provider,
+            subject: identity.subject,
+            email: identity.email,
+            created_at: identity.created_at,
+            last_login_at: identity.last_login_at,
+        }
+    }
+}
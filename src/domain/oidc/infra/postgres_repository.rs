@@ -0,0 +1,230 @@
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    common::rbac::DEFAULT_ROLE,
+    domain::oidc::{
+        ExternalIdentity, ExternalIdentityRepository, OidcLoginState, OidcLoginStateRepository,
+    },
+};
+
+#[derive(Clone)]
+pub struct ExternalIdentityRepo;
+
+impl ExternalIdentityRepository for ExternalIdentityRepo {
+    async fn find_by_subject(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<ExternalIdentity>, sqlx::Error> {
+        sqlx::query_as::<_, ExternalIdentity>(
+            r#"
+            SELECT id, user_id, provider, subject, email, created_at, last_login_at
+              FROM user_identities
+             WHERE provider = $1
+               AND subject = $2
+            "#,
+        )
+        .bind(provider)
+        .bind(subject)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn find_by_user(
+        &self,
+        pool: &PgPool,
+        user_id: &str,
+    ) -> Result<Vec<ExternalIdentity>, sqlx::Error> {
+        sqlx::query_as::<_, ExternalIdentity>(
+            r#"
+            SELECT id, user_id, provider, subject, email, created_at, last_login_at
+              FROM user_identities
+             WHERE user_id = $1
+             ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        identity: &ExternalIdentity,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_identities
+            (id, user_id, provider, subject, email, created_at, last_login_at)
+            VALUES
+            ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(&identity.id)
+        .bind(&identity.user_id)
+        .bind(&identity.provider)
+        .bind(&identity.subject)
+        .bind(&identity.email)
+        .bind(identity.created_at)
+        .bind(identity.last_login_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn record_login(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(r#"UPDATE user_identities SET last_login_at = NOW() WHERE id = $1"#)
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+        user_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(r#"DELETE FROM user_identities WHERE id = $1 AND user_id = $2"#)
+            .bind(id)
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn count_login_methods(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT (SELECT COUNT(*) FROM user_auth WHERE user_id = $1)
+                 + (SELECT COUNT(*) FROM user_identities WHERE user_id = $1)
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&mut **tx)
+        .await
+    }
+
+    async fn find_user_ids_by_email(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        email: &str,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT id
+              FROM users
+             WHERE LOWER(email) = LOWER($1)
+               AND email_verified_at IS NOT NULL
+               AND deleted_at IS NULL
+            "#,
+        )
+        .bind(email)
+        .fetch_all(&mut **tx)
+        .await
+    }
+
+    async fn create_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        username: &str,
+        email: &str,
+        email_verified: bool,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let id = sqlx::query_scalar::<_, String>(
+            r#"
+            INSERT INTO users (id, username, email, email_verified_at)
+            VALUES ($1, $2, $3, CASE WHEN $4 THEN NOW() END)
+            ON CONFLICT (username) DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(username)
+        .bind(email)
+        .bind(email_verified)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        if let Some(id) = &id {
+            sqlx::query(r#"INSERT INTO user_roles (user_id, role_name) VALUES ($1, $2)"#)
+                .bind(id)
+                .bind(DEFAULT_ROLE)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(id)
+    }
+}
+
+#[derive(Clone)]
+pub struct OidcLoginStateRepo;
+
+impl OidcLoginStateRepository for OidcLoginStateRepo {
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        state: &OidcLoginState,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO oidc_login_states
+            (id, state_hash, provider, nonce, code_verifier, link_user_id, expires_at)
+            VALUES
+            ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(&state.id)
+        .bind(&state.state_hash)
+        .bind(&state.provider)
+        .bind(&state.nonce)
+        .bind(&state.code_verifier)
+        .bind(&state.link_user_id)
+        .bind(state.expires_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn take_by_hash(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        state_hash: &str,
+    ) -> Result<Option<OidcLoginState>, sqlx::Error> {
+        sqlx::query_as::<_, OidcLoginState>(
+            r#"
+            DELETE FROM oidc_login_states
+             WHERE state_hash = $1
+            RETURNING id, state_hash, provider, nonce, code_verifier, link_user_id, expires_at
+            "#,
+        )
+        .bind(state_hash)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn delete_expired(&self, tx: &mut Transaction<'_, Postgres>) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(r#"DELETE FROM oidc_login_states WHERE expires_at < NOW()"#)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+}
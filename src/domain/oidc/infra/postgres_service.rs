@@ -0,0 +1,308 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    common::{
+        config::Config,
+        error::AppError,
+        jwt::Claims,
+        oidc::{IdTokenClaims, OidcClient, OidcError},
+        token_util,
+    },
+    domain::oidc::{
+        domain::{
+            model::{ExternalIdentity, OidcCallbackOutcome, OidcLoginState},
+            repository::{ExternalIdentityRepository, OidcLoginStateRepository},
+            service::OidcServiceTrait,
+        },
+        infra::postgres_repository::{ExternalIdentityRepo, OidcLoginStateRepo},
+    },
+};
+
+/// Longest username derived from a provider identity, leaving room for a suffix.
+const MAX_DERIVED_USERNAME_LENGTH: usize = 50;
+/// Usernames tried for a new user before giving up.
+const USERNAME_ATTEMPTS: usize = 5;
+
+/// Service for logins through external OpenID Connect providers.
+pub struct PostgresOidcService {
+    pool: PgPool,
+    config: Config,
+    clients: HashMap<String, OidcClient>,
+    identity_repo: ExternalIdentityRepo,
+    state_repo: OidcLoginStateRepo,
+}
+
+impl PostgresOidcService {
+    /// constructor for the service.
+    /// Creates a relying party for every provider in `oidc_providers`.
+    pub fn new(pool: PgPool, config: Config) -> Arc<Self> {
+        let clients = config
+            .oidc_providers
+            .iter()
+            .map(|provider| (provider.name.clone(), OidcClient::new(provider.clone())))
+            .collect();
+
+        Arc::new(Self {
+            pool,
+            config,
+            clients,
+            identity_repo: ExternalIdentityRepo,
+            state_repo: OidcLoginStateRepo,
+        })
+    }
+
+    fn client(&self, provider: &str) -> Result<&OidcClient, AppError> {
+        self.clients
+            .get(provider)
+            .ok_or_else(|| AppError::NotFound("Identity provider not found".into()))
+    }
+
+    /// Finds or creates the user an unknown identity belongs to, as far as
+    /// `oidc_link_by_email` and `oidc_auto_provision` allow.
+    async fn resolve_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        provider: &str,
+        claims: &IdTokenClaims,
+    ) -> Result<String, AppError> {
+        if self.config.oidc_link_by_email
+            && claims.email_verified
+            && let Some(email) = &claims.email
+        {
+            let user_ids = self.identity_repo.find_user_ids_by_email(tx, email).await?;
+            // Several users with the same address would make the link a guess.
+            if let [user_id] = user_ids.as_slice() {
+                return Ok(user_id.clone());
+            }
+        }
+
+        if self.config.oidc_auto_provision
+            && let Some(email) = &claims.email
+        {
+            return self.provision_user(tx, email, claims).await;
+        }
+
+        tracing::warn!(provider, subject = %claims.sub, "No user for external identity");
+        Err(AppError::Forbidden)
+    }
+
+    /// Creates a user for an identity, named after the identity's preferred username or the
+    /// local part of its email address. A random suffix is added if the name is taken.
+    async fn provision_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        email: &str,
+        claims: &IdTokenClaims,
+    ) -> Result<String, AppError> {
+        let base: String = claims
+            .preferred_username
+            .as_deref()
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default())
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+            .take(MAX_DERIVED_USERNAME_LENGTH)
+            .collect();
+        let base = if base.is_empty() { "user".to_string() } else { base };
+
+        for attempt in 0..USERNAME_ATTEMPTS {
+            let username = match attempt {
+                0 => base.clone(),
+                _ => format!("{base}-{}", &token_util::generate_token()[..6]),
+            };
+            let created = self
+                .identity_repo
+                .create_user(tx, &username, email, claims.email_verified)
+                .await
+                .inspect_err(|e| tracing::error!("Error creating user: {e}"))?;
+            if let Some(user_id) = created {
+                tracing::info!(%user_id, username, "User created for external identity");
+                return Ok(user_id);
+            }
+        }
+
+        tracing::error!(base, "No free username for external identity");
+        Err(AppError::InternalError)
+    }
+}
+
+/// Maps a protocol error: a rejected code or token means the login failed, anything else
+/// is a problem reaching or understanding the provider.
+fn map_oidc_error(provider: &str, err: OidcError) -> AppError {
+    match err {
+        OidcError::TokenExchange(_) | OidcError::InvalidIdToken(_) => {
+            tracing::warn!(provider, "External login rejected: {err}");
+            AppError::WrongCredentials
+        }
+        OidcError::Http(_) | OidcError::Discovery(_) => {
+            tracing::error!(provider, "Identity provider error: {err}");
+            AppError::InternalError
+        }
+    }
+}
+
+impl OidcServiceTrait for PostgresOidcService {
+    async fn authorization_url(
+        &self,
+        provider: &str,
+        link_user_id: Option<&str>,
+    ) -> Result<String, AppError> {
+        let client = self.client(provider)?;
+
+        let state = token_util::generate_token();
+        let login_state = OidcLoginState {
+            id: Uuid::new_v4().to_string(),
+            state_hash: token_util::hash_token(&state),
+            provider: provider.to_string(),
+            nonce: token_util::generate_token(),
+            code_verifier: token_util::generate_token(),
+            link_user_id: link_user_id.map(str::to_string),
+            expires_at: Utc::now() + Duration::seconds(self.config.oidc_login_ttl_secs),
+        };
+        let url = client
+            .authorization_url(&state, &login_state.nonce, &login_state.code_verifier)
+            .await
+            .map_err(|e| map_oidc_error(provider, e))?;
+
+        let mut tx = self.pool.begin().await?;
+        self.state_repo.delete_expired(&mut tx).await?;
+        self.state_repo
+            .create(&mut tx, &login_state)
+            .await
+            .inspect_err(|e| tracing::error!("Error creating OIDC login state: {e}"))?;
+        tx.commit().await?;
+
+        Ok(url)
+    }
+
+    /// The login state is consumed before the code is exchanged, so a failed attempt cannot
+    /// be retried with the same state.
+    async fn complete_authorization(
+        &self,
+        provider: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<OidcCallbackOutcome, AppError> {
+        let client = self.client(provider)?;
+
+        let mut tx = self.pool.begin().await?;
+        let login_state = self
+            .state_repo
+            .take_by_hash(&mut tx, &token_util::hash_token(state))
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving OIDC login state: {e}"))?;
+        tx.commit().await?;
+
+        let login_state = login_state
+            .filter(|s| s.provider == provider && s.expires_at > Utc::now())
+            .ok_or_else(|| AppError::ValidationError("Invalid or expired login state".into()))?;
+
+        let id_token = client
+            .exchange_code(code, &login_state.code_verifier)
+            .await
+            .map_err(|e| map_oidc_error(provider, e))?;
+        let claims = client
+            .validate_id_token(&id_token, &login_state.nonce)
+            .await
+            .map_err(|e| map_oidc_error(provider, e))?;
+
+        let mut tx = self.pool.begin().await?;
+        let existing = self
+            .identity_repo
+            .find_by_subject(&mut tx, provider, &claims.sub)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving external identity: {e}"))?;
+
+        if let Some(link_user_id) = login_state.link_user_id {
+            if let Some(identity) = existing {
+                if identity.user_id != link_user_id {
+                    return Err(AppError::ValidationError(
+                        "This identity is linked to another account".into(),
+                    ));
+                }
+                return Ok(OidcCallbackOutcome::Linked(identity));
+            }
+
+            let identity = new_identity(link_user_id, provider, &claims, None);
+            self.identity_repo
+                .create(&mut tx, &identity)
+                .await
+                .inspect_err(|e| tracing::error!("Error linking external identity: {e}"))?;
+            tx.commit().await?;
+
+            tracing::info!(user_id = %identity.user_id, provider, "External identity linked");
+            return Ok(OidcCallbackOutcome::Linked(identity));
+        }
+
+        let user_id = match existing {
+            Some(identity) => {
+                self.identity_repo.record_login(&mut tx, &identity.id).await?;
+                identity.user_id
+            }
+            None => {
+                let user_id = self.resolve_user(&mut tx, provider, &claims).await?;
+                let identity = new_identity(user_id, provider, &claims, Some(Utc::now()));
+                self.identity_repo
+                    .create(&mut tx, &identity)
+                    .await
+                    .inspect_err(|e| tracing::error!("Error linking external identity: {e}"))?;
+                tracing::info!(user_id = %identity.user_id, provider, "External identity linked");
+                identity.user_id
+            }
+        };
+        tx.commit().await?;
+
+        Ok(OidcCallbackOutcome::Login { user_id })
+    }
+
+    async fn list_identities(&self, claims: &Claims) -> Result<Vec<ExternalIdentity>, AppError> {
+        let identities = self
+            .identity_repo
+            .find_by_user(&self.pool, &claims.sub)
+            .await
+            .inspect_err(|e| tracing::error!("Error listing external identities: {e}"))?;
+        Ok(identities)
+    }
+
+    async fn unlink_identity(&self, claims: &Claims, id: &str) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        let deleted = self.identity_repo.delete(&mut tx, id, &claims.sub).await?;
+        if !deleted {
+            return Err(AppError::NotFound("Identity not found".into()));
+        }
+
+        let remaining = self
+            .identity_repo
+            .count_login_methods(&mut tx, &claims.sub)
+            .await?;
+        if remaining == 0 {
+            return Err(AppError::ValidationError(
+                "Cannot unlink the only way to log in".into(),
+            ));
+        }
+        tx.commit().await?;
+
+        tracing::info!(user_id = %claims.sub, identity_id = id, "External identity unlinked");
+        Ok(())
+    }
+}
+
+fn new_identity(
+    user_id: String,
+    provider: &str,
+    claims: &IdTokenClaims,
+    last_login_at: Option<chrono::DateTime<Utc>>,
+) -> ExternalIdentity {
+    ExternalIdentity {
+        id: Uuid::new_v4().to_string(),
+        user_id,
+        provider: provider.to_string(),
+        subject: claims.sub.clone(),
+        email: claims.email.clone(),
+        created_at: Utc::now(),
+        last_login_at,
+    }
+}
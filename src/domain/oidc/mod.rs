@@ -0,0 +1,28 @@
+mod api {
+    mod handlers;
+    pub mod routes;
+}
+
+mod domain {
+    pub mod model;
+    pub mod repository;
+    pub mod service;
+}
+
+mod dto {
+    pub mod oidc_dto;
+}
+
+mod infra {
+    pub mod postgres_repository;
+    pub mod postgres_service;
+}
+
+// Re-export commonly used items for convenience
+pub use api::routes::{oidc_protected_routes, oidc_routes, OidcApiDoc};
+pub use domain::model::{ExternalIdentity, OidcCallbackOutcome, OidcLoginState};
+pub use domain::repository::{ExternalIdentityRepository, OidcLoginStateRepository};
+pub use domain::service::OidcServiceTrait;
+pub use dto::oidc_dto::{AuthorizationUrlDto, ExternalIdentityDto, OidcCallbackQuery};
+pub use infra::postgres_repository::ExternalIdentityRepo;
+pub use infra::postgres_service::PostgresOidcService as OidcService;
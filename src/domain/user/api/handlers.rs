@@ -3,19 +3,25 @@ use crate::{
         app_state::AppState,
         dto::RestApiResponse,
         error::AppError,
+        etag::{etag, IfMatch},
         jwt::Claims,
+        merge_patch::MergePatch,
         pagination::{PageRequest, PageResponse},
         validated_json::ValidatedJson,
     },
     domain::user::{
-        CreateUserDto, PagedUserDto, SearchUserDto, UpdateUserDto, UserDto, UserId,
-        UserServiceTrait,
+        Actor, CreateInvitationDto, CreateUserDto, CreatedInvitationDto, PagedUserDto,
+        PatchUserDto, SearchUserDto, UpdateUserDto, UserDto, UserId, UserListDto, UserLookupDto,
+        UserServiceTrait, VerifyEmailDto,
     },
 };
 
 use axum::{
     extract::{Query, State},
-    http::StatusCode,
+    http::{
+        header::{CACHE_CONTROL, ETAG},
+        StatusCode,
+    },
     response::IntoResponse,
     Extension,
 };
@@ -23,46 +29,95 @@ use axum::{
 #[utoipa::path(
     get,
     path = "/users/{id}",
-    responses((status = 200, description = "Get user by ID", body = UserDto)),
+    params(
+        ("include_deleted" = Option<bool>, Query, description = "Admins only: also return a deleted user"),
+    ),
+    description = "Requires the `users:read` permission. \
+        Non-admin users may only read their own record.",
+    responses(
+        (status = 200, description = "Get user by ID", body = UserDto,
+            headers(("ETag" = String, description = "Current version of the user, for `If-Match`"))),
+        (status = 403, description = "Missing `users:read` permission or not the caller's record")
+    ),
+    security(("bearer_auth" = ["users:read"])),
     tag = "Users"
 )]
 pub async fn get_user_by_id(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     axum::extract::Path(id): axum::extract::Path<String>,
+    Query(params): Query<UserLookupDto>,
 ) -> Result<impl IntoResponse, AppError> {
     let user_id = UserId::from(id);
-    let user = state.user_service.get_user_by_id(&user_id).await?;
-    Ok(RestApiResponse::success(UserDto::from(user)))
+    let include_deleted = params.include_deleted.unwrap_or(false);
+    let user = state
+        .user_service
+        .get_user_by_id(&Actor::from(&claims), &user_id, include_deleted)
+        .await?;
+    Ok(([(ETAG, etag(user.version))], RestApiResponse::success(UserDto::from(user))))
 }
 
 #[utoipa::path(
     get,
     path = "/users",
     params(
-        ("id" = Option<String>, Query, description = "Filter by user ID"),
-        ("username" = Option<String>, Query, description = "Filter by username"),
-        ("email" = Option<String>, Query, description = "Filter by email"),
+        ("id" = Option<String>, Query, description = "Filter by user ID; exact by default, or `in:id1,id2`"),
+        ("username" = Option<String>, Query, description = "Filter by username; case-insensitive contains by default, or `eq:name`, `in:a,b`"),
+        ("email" = Option<String>, Query, description = "Filter by email; case-insensitive contains by default, or `eq:address`, `in:a,b`"),
+        ("created_at" = Option<String>, Query, description = "Filter by creation time: `from..to` in RFC 3339, from inclusive, to exclusive, either optional"),
+        ("modified_at" = Option<String>, Query, description = "Filter by modification time, like `created_at`"),
+        ("include_deleted" = Option<bool>, Query, description = "Admins only: also list deleted users"),
+        ("sort" = Option<String>, Query, description = "Comma-separated fields to sort by, descending with a leading `-`: \
+            `username`, `email`, `created_at`, `modified_at`. Defaults to `-created_at`", example = "-created_at,username"),
         PageRequest,
     ),
-    responses((status = 200, description = "List users with optional filters", body = PagedUserDto)),
+    description = "Requires the `users:read` permission. \
+        Non-admin users only see their own record. \
+        Sending `cursor`, empty for the first page, switches from offset to cursor pagination.",
+    responses(
+        (status = 200, description = "List users with optional filters; a `PagedUserDto`, \
+            or a `CursorPagedUserDto` with cursor pagination", body = UserListDto),
+        (status = 400, description = "Invalid filter, sort field or cursor"),
+        (status = 403, description = "Missing `users:read` permission")
+    ),
+    security(("bearer_auth" = ["users:read"])),
     tag = "Users"
 )]
 pub async fn get_user_list(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Query(params): Query<SearchUserDto>,
     Query(page_request): Query<PageRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    let (users, total) = state.user_service.get_user_list(params, &page_request).await?;
+    let actor = Actor::from(&claims);
+    if page_request.is_cursor() {
+        let page = state
+            .user_service
+            .get_user_list_by_cursor(&actor, params, &page_request)
+            .await?;
+        let response = UserListDto::Cursor(page.map(UserDto::from).into());
+        return Ok(RestApiResponse::success(response));
+    }
+
+    let (users, total) = state
+        .user_service
+        .get_user_list(&actor, params, &page_request)
+        .await?;
     let user_dtos: Vec<UserDto> = users.into_iter().map(UserDto::from).collect();
     let response: PagedUserDto = PageResponse::new(user_dtos, total, &page_request).into();
-    Ok(RestApiResponse::success(response))
+    Ok(RestApiResponse::success(UserListDto::Page(response)))
 }
 
 #[utoipa::path(
     post,
     path = "/users",
     request_body = CreateUserDto,
-    responses((status = 201, description = "User created successfully", body = UserDto)),
+    description = "Requires the `users:create` permission.",
+    responses(
+        (status = 201, description = "User created successfully", body = UserDto),
+        (status = 403, description = "Missing `users:create` permission")
+    ),
+    security(("bearer_auth" = ["users:create"])),
     tag = "Users"
 )]
 pub async fn create_user(
@@ -70,45 +125,245 @@ pub async fn create_user(
     Extension(claims): Extension<Claims>,
     ValidatedJson(mut payload): ValidatedJson<CreateUserDto>,
 ) -> Result<impl IntoResponse, AppError> {
-    payload.modified_by = claims.sub.clone();
+    payload.modified_by = claims.actor_id().to_string();
 
-    let user = state.user_service.create_user(payload).await?;
+    let user = state
+        .user_service
+        .create_user(&Actor::from(&claims), payload)
+        .await?;
 
     Ok(RestApiResponse::created(UserDto::from(user)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/users/invitations",
+    request_body = CreateInvitationDto,
+    description = "Requires the `users:create` permission and the admin role. \
+        Invitations are needed to sign up when `SIGNUP_MODE` is `invite-only`.",
+    responses(
+        (status = 201, description = "Invitation created", body = CreatedInvitationDto),
+        (status = 403, description = "Missing `users:create` permission")
+    ),
+    security(("bearer_auth" = ["users:create"])),
+    tag = "Users"
+)]
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<CreateInvitationDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let invitation = state
+        .user_service
+        .create_invitation(&Actor::from(&claims), payload, claims.actor_id())
+        .await?;
+    Ok(([(CACHE_CONTROL, "no-store")], RestApiResponse::created(invitation)))
+}
+
 #[utoipa::path(
     put,
     path = "/users/{id}",
     request_body = UpdateUserDto,
-    responses((status = 200, description = "Update user", body = UserDto)),
+    params(
+        ("If-Match" = Option<String>, Header, description = "ETag the user must still have"),
+    ),
+    description = "Requires the `users:update` permission. \
+        Non-admin users may only update their own record.",
+    responses(
+        (status = 200, description = "Update user", body = UserDto,
+            headers(("ETag" = String, description = "New version of the user"))),
+        (status = 403, description = "Missing `users:update` permission or not the caller's record"),
+        (status = 412, description = "`If-Match` does not match the user's current ETag"),
+        (status = 428, description = "`If-Match` is missing and `REQUIRE_IF_MATCH` is enabled")
+    ),
+    security(("bearer_auth" = ["users:update"])),
     tag = "Users"
 )]
 pub async fn update_user(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     axum::extract::Path(id): axum::extract::Path<String>,
+    if_match: IfMatch,
     ValidatedJson(mut payload): ValidatedJson<UpdateUserDto>,
 ) -> Result<impl IntoResponse, AppError> {
     // Set the modified_by field to the current user's ID.
-    payload.modified_by = claims.sub.clone();
+    payload.modified_by = claims.actor_id().to_string();
 
     let user_id = UserId::from(id);
-    let user = state.user_service.update_user(&user_id, payload).await?;
-    Ok(RestApiResponse::success(UserDto::from(user)))
+    let user = state
+        .user_service
+        .update_user(&Actor::from(&claims), &user_id, payload, &if_match)
+        .await?;
+    Ok(([(ETAG, etag(user.version))], RestApiResponse::success(UserDto::from(user))))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/users/{id}",
+    request_body(content = PatchUserDto, content_type = "application/merge-patch+json"),
+    params(
+        ("If-Match" = Option<String>, Header, description = "ETag the user must still have"),
+    ),
+    description = "Requires the `users:update` permission. \
+        Non-admin users may only update their own record. \
+        The body is a JSON Merge Patch (RFC 7396): only the fields it contains are changed.",
+    responses(
+        (status = 200, description = "Patched user", body = UserDto,
+            headers(("ETag" = String, description = "New version of the user"))),
+        (status = 400, description = "Malformed patch or invalid merged result"),
+        (status = 403, description = "Missing `users:update` permission or not the caller's record"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "Username is already taken"),
+        (status = 412, description = "`If-Match` does not match the user's current ETag"),
+        (status = 415, description = "Content type is not `application/merge-patch+json`"),
+        (status = 428, description = "`If-Match` is missing and `REQUIRE_IF_MATCH` is enabled")
+    ),
+    security(("bearer_auth" = ["users:update"])),
+    tag = "Users"
+)]
+pub async fn patch_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    if_match: IfMatch,
+    MergePatch(patch): MergePatch,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = UserId::from(id);
+    let user = state
+        .user_service
+        .patch_user(&Actor::from(&claims), &user_id, patch, claims.actor_id(), &if_match)
+        .await?;
+    Ok(([(ETAG, etag(user.version))], RestApiResponse::success(UserDto::from(user))))
 }
 
 #[utoipa::path(
     delete,
     path = "/users/{id}",
-    responses((status = 204, description = "User deleted")),
+    params(
+        ("If-Match" = Option<String>, Header, description = "ETag the user must still have"),
+    ),
+    description = "Requires the `users:delete` permission. Only admins may delete users. \
+        The user is deactivated and its tokens are revoked; it can be restored until it is purged.",
+    responses(
+        (status = 204, description = "User deleted"),
+        (status = 403, description = "Missing `users:delete` permission or not an admin"),
+        (status = 404, description = "User not found or already deleted"),
+        (status = 412, description = "`If-Match` does not match the user's current ETag"),
+        (status = 428, description = "`If-Match` is missing and `REQUIRE_IF_MATCH` is enabled")
+    ),
+    security(("bearer_auth" = ["users:delete"])),
     tag = "Users"
 )]
 pub async fn delete_user(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     axum::extract::Path(id): axum::extract::Path<String>,
+    if_match: IfMatch,
 ) -> Result<StatusCode, AppError> {
     let user_id = UserId::from(id);
-    state.user_service.delete_user(&user_id).await?;
+    state
+        .user_service
+        .delete_user(&Actor::from(&claims), &user_id, claims.actor_id(), &if_match)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/restore",
+    description = "Requires the `users:delete` permission. Only admins may restore users. \
+        Tokens revoked by the deletion stay revoked.",
+    responses(
+        (status = 200, description = "User restored", body = UserDto,
+            headers(("ETag" = String, description = "New version of the user"))),
+        (status = 403, description = "Missing `users:delete` permission or not an admin"),
+        (status = 404, description = "No deleted user with this ID")
+    ),
+    security(("bearer_auth" = ["users:delete"])),
+    tag = "Users"
+)]
+pub async fn restore_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = UserId::from(id);
+    let user = state
+        .user_service
+        .restore_user(&Actor::from(&claims), &user_id, claims.actor_id())
+        .await?;
+    Ok(([(ETAG, etag(user.version))], RestApiResponse::success(UserDto::from(user))))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/purge",
+    description = "Requires the `users:purge` permission. Only admins may purge users. \
+        Permanently removes a deleted user together with its credentials, tokens, sessions, \
+        roles and linked identities.",
+    responses(
+        (status = 204, description = "User purged"),
+        (status = 403, description = "Missing `users:purge` permission or not an admin"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "User is not deleted, or its tokens have not expired yet")
+    ),
+    security(("bearer_auth" = ["users:purge"])),
+    tag = "Users"
+)]
+pub async fn purge_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<StatusCode, AppError> {
+    let user_id = UserId::from(id);
+    state
+        .user_service
+        .purge_user(&Actor::from(&claims), &user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/verification-email",
+    description = "Requires the `users:update` permission. \
+        Non-admin users may only request a link for their own address.",
+    responses(
+        (status = 202, description = "Verification email sent"),
+        (status = 400, description = "Email address is already verified"),
+        (status = 403, description = "Missing `users:update` permission or not the caller's record")
+    ),
+    security(("bearer_auth" = ["users:update"])),
+    tag = "Users"
+)]
+pub async fn resend_verification_email(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<StatusCode, AppError> {
+    let user_id = UserId::from(id);
+    state
+        .user_service
+        .resend_verification_email(&Actor::from(&claims), &user_id)
+        .await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/verify-email",
+    request_body = VerifyEmailDto,
+    responses(
+        (status = 204, description = "Email address verified"),
+        (status = 401, description = "Token is invalid, expired, already used or for a previous address")
+    ),
+    security(()),
+    tag = "Users"
+)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<VerifyEmailDto>,
+) -> Result<StatusCode, AppError> {
+    state.user_service.verify_email(payload).await?;
     Ok(StatusCode::NO_CONTENT)
 }
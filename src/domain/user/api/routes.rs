@@ -1,11 +1,18 @@
 use super::handlers::*;
 use crate::{
-    common::app_state::AppState,
-    domain::user::{CreateUserDto, PagedUserDto, SearchUserDto, UpdateUserDto, UserDto},
+    common::{
+        app_state::AppState,
+        rbac::{permissions, RequirePermission},
+    },
+    domain::user::{
+        CreateInvitationDto, CreateUserDto, CreatedInvitationDto, CursorPagedUserDto,
+        PagedUserDto, PatchUserDto, SearchUserDto, UpdateUserDto, UserDto, UserListDto,
+        UserLookupDto, VerifyEmailDto,
+    },
 };
 
 use axum::{
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 
@@ -20,10 +27,29 @@ use utoipa::{
         get_user_by_id,
         get_user_list,
         create_user,
+        create_invitation,
         update_user,
+        patch_user,
         delete_user,
+        restore_user,
+        purge_user,
+        resend_verification_email,
+        verify_email,
     ),
-    components(schemas(UserDto, SearchUserDto, CreateUserDto, UpdateUserDto, PagedUserDto)),
+    components(schemas(
+        UserDto,
+        SearchUserDto,
+        UserLookupDto,
+        CreateUserDto,
+        UpdateUserDto,
+        PatchUserDto,
+        PagedUserDto,
+        CursorPagedUserDto,
+        UserListDto,
+        VerifyEmailDto,
+        CreateInvitationDto,
+        CreatedInvitationDto
+    )),
     tags(
         (name = "Users", description = "User management endpoints")
     ),
@@ -53,9 +79,50 @@ impl utoipa::Modify for UserApiDoc {
 
 pub fn user_routes() -> Router<AppState> {
     Router::new()
-        .route("/", get(get_user_list))
-        .route("/", post(create_user))
-        .route("/{id}", get(get_user_by_id))
-        .route("/{id}", put(update_user))
-        .route("/{id}", delete(delete_user))
+        .route(
+            "/",
+            get(get_user_list).route_layer(RequirePermission(permissions::USERS_READ)),
+        )
+        .route(
+            "/",
+            post(create_user).route_layer(RequirePermission(permissions::USERS_CREATE)),
+        )
+        .route(
+            "/invitations",
+            post(create_invitation).route_layer(RequirePermission(permissions::USERS_CREATE)),
+        )
+        .route(
+            "/{id}",
+            get(get_user_by_id).route_layer(RequirePermission(permissions::USERS_READ)),
+        )
+        .route(
+            "/{id}",
+            put(update_user).route_layer(RequirePermission(permissions::USERS_UPDATE)),
+        )
+        .route(
+            "/{id}",
+            patch(patch_user).route_layer(RequirePermission(permissions::USERS_UPDATE)),
+        )
+        .route(
+            "/{id}",
+            delete(delete_user).route_layer(RequirePermission(permissions::USERS_DELETE)),
+        )
+        .route(
+            "/{id}/restore",
+            post(restore_user).route_layer(RequirePermission(permissions::USERS_DELETE)),
+        )
+        .route(
+            "/{id}/purge",
+            post(purge_user).route_layer(RequirePermission(permissions::USERS_PURGE)),
+        )
+        .route(
+            "/{id}/verification-email",
+            post(resend_verification_email)
+                .route_layer(RequirePermission(permissions::USERS_UPDATE)),
+        )
+}
+
+/// This function creates a router for the user routes that do not require a JWT.
+pub fn user_public_routes() -> Router<AppState> {
+    Router::new().route("/verify-email", post(verify_email))
 }
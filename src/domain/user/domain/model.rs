@@ -1,9 +1,12 @@
 use std::fmt;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use sqlx::FromRow;
 
+use crate::common::query_spec::SortKey;
+
 /// A strongly-typed user identifier.
 ///
 /// Using a newtype instead of raw String provides:
@@ -56,6 +59,9 @@ impl AsRef<str> for UserId {
 }
 
 /// Domain model representing a user in the application.
+///
+/// Deleting a user only sets `deleted_at`; deleted users cannot log in and are hidden
+/// unless explicitly requested, until they are restored or purged.
 #[derive(Debug, Clone, FromRow)]
 pub struct User {
     pub id: UserId,
@@ -65,4 +71,53 @@ pub struct User {
     pub created_at: Option<DateTime<Utc>>,
     pub modified_by: Option<UserId>,
     pub modified_at: Option<DateTime<Utc>>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<UserId>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Incremented on every change; exposed as the user's `ETag`.
+    pub version: i64,
+}
+
+impl SortKey for User {
+    fn sort_key(&self, column: &str) -> Value {
+        let timestamp = |t: Option<DateTime<Utc>>| {
+            json!(t.map(|t| t.to_rfc3339_opts(SecondsFormat::Micros, true)))
+        };
+        match column {
+            "id" => json!(self.id.as_str()),
+            "username" => json!(self.username),
+            "email" => json!(self.email),
+            "created_at" => timestamp(self.created_at),
+            "modified_at" => timestamp(self.modified_at),
+            _ => unreachable!("users cannot be sorted by `{column}`"),
+        }
+    }
+}
+
+/// Represents a persisted email verification token.
+///
+/// The token confirms one specific address: if the user's email changes before the
+/// token is used, the token no longer verifies anything. Only its SHA-256 hash is stored.
+#[derive(Debug, Clone, FromRow)]
+pub struct EmailVerificationToken {
+    pub id: String,
+    pub user_id: UserId,
+    pub email: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+/// Represents a persisted invitation to sign up while signup is invite-only.
+///
+/// An invitation with an email address only admits that address. Only the SHA-256 hash of
+/// the token is stored.
+#[derive(Debug, Clone, FromRow)]
+pub struct SignupInvitation {
+    pub id: String,
+    pub token_hash: String,
+    pub email: Option<String>,
+    pub created_by: Option<UserId>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
 }
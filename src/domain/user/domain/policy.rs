@@ -0,0 +1,165 @@
+//! Authorization policy for user operations.
+//!
+//! Route permissions (see `common::rbac`) decide whether a caller may use an endpoint at all;
+//! this policy decides which records they may act on. Administrators can act on any user,
+//! everyone else may only read and update their own record and never sees deleted users.
+
+use crate::{
+    common::{error::AppError, jwt::Claims, rbac::ADMIN_ROLE},
+    domain::user::SearchUserDto,
+};
+
+use super::model::UserId;
+
+/// The authenticated caller on whose behalf a user operation runs.
+#[derive(Debug, Clone)]
+pub struct Actor {
+    pub id: UserId,
+    pub is_admin: bool,
+}
+
+impl Actor {
+    /// Creates an actor for the given user.
+    pub fn new(id: impl Into<UserId>, is_admin: bool) -> Self {
+        Self {
+            id: id.into(),
+            is_admin,
+        }
+    }
+}
+
+impl From<&Claims> for Actor {
+    fn from(claims: &Claims) -> Self {
+        Self::new(claims.sub.as_str(), claims.has_role(ADMIN_ROLE))
+    }
+}
+
+/// An operation on users, together with the user it targets.
+#[derive(Debug, Clone, Copy)]
+pub enum UserAction<'a> {
+    Create,
+    List,
+    Read(&'a UserId),
+    Update(&'a UserId),
+    Delete(&'a UserId),
+    Restore(&'a UserId),
+    Purge(&'a UserId),
+}
+
+/// Decides whether `actor` may perform `action`.
+/// Returns `AppError::Forbidden` if not.
+pub fn authorize(actor: &Actor, action: UserAction<'_>) -> Result<(), AppError> {
+    let allowed = actor.is_admin
+        || match action {
+            UserAction::List => true,
+            UserAction::Read(target) | UserAction::Update(target) => *target == actor.id,
+            UserAction::Create
+            | UserAction::Delete(_)
+            | UserAction::Restore(_)
+            | UserAction::Purge(_) => false,
+        };
+
+    if allowed {
+        Ok(())
+    } else {
+        tracing::warn!(actor = %actor.id, ?action, "User operation denied by policy");
+        Err(AppError::Forbidden)
+    }
+}
+
+/// Restricts a user search to the records `actor` may read.
+/// Non-admins only ever see their own record, and only while it is not deleted.
+pub fn scope_search(actor: &Actor, mut search: SearchUserDto) -> SearchUserDto {
+    if !actor.is_admin {
+        search.id = Some(actor.id.to_string());
+        search.include_deleted = None;
+    }
+    search
+}
+
+/// Returns whether a lookup by `actor` may return a deleted user.
+pub fn may_include_deleted(actor: &Actor, include_deleted: bool) -> bool {
+    actor.is_admin && include_deleted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin() -> Actor {
+        Actor::new("admin-id", true)
+    }
+
+    fn user() -> Actor {
+        Actor::new("user-id", false)
+    }
+
+    #[test]
+    fn test_admin_can_act_on_anyone() {
+        let other = UserId::from("other-id");
+
+        assert!(authorize(&admin(), UserAction::Create).is_ok());
+        assert!(authorize(&admin(), UserAction::List).is_ok());
+        assert!(authorize(&admin(), UserAction::Read(&other)).is_ok());
+        assert!(authorize(&admin(), UserAction::Update(&other)).is_ok());
+        assert!(authorize(&admin(), UserAction::Delete(&other)).is_ok());
+        assert!(authorize(&admin(), UserAction::Restore(&other)).is_ok());
+        assert!(authorize(&admin(), UserAction::Purge(&other)).is_ok());
+    }
+
+    #[test]
+    fn test_user_can_read_and_update_self_only() {
+        let own = UserId::from("user-id");
+        let other = UserId::from("other-id");
+
+        assert!(authorize(&user(), UserAction::Read(&own)).is_ok());
+        assert!(authorize(&user(), UserAction::Update(&own)).is_ok());
+        assert!(matches!(
+            authorize(&user(), UserAction::Read(&other)),
+            Err(AppError::Forbidden)
+        ));
+        assert!(matches!(
+            authorize(&user(), UserAction::Update(&other)),
+            Err(AppError::Forbidden)
+        ));
+    }
+
+    #[test]
+    fn test_user_cannot_create_delete_restore_or_purge() {
+        let own = UserId::from("user-id");
+
+        assert!(authorize(&user(), UserAction::Create).is_err());
+        assert!(authorize(&user(), UserAction::Delete(&own)).is_err());
+        assert!(authorize(&user(), UserAction::Restore(&own)).is_err());
+        assert!(authorize(&user(), UserAction::Purge(&own)).is_err());
+    }
+
+    #[test]
+    fn test_search_is_scoped_to_self_for_users() {
+        let search = SearchUserDto {
+            id: None,
+            username: Some("bob".into()),
+            email: None,
+            created_at: None,
+            modified_at: None,
+            include_deleted: Some(true),
+            sort: None,
+        };
+
+        let scoped = scope_search(&user(), search.clone());
+        assert_eq!(scoped.id.as_deref(), Some("user-id"));
+        assert_eq!(scoped.username.as_deref(), Some("bob"));
+        assert_eq!(scoped.include_deleted, None);
+
+        let unscoped = scope_search(&admin(), search);
+        assert_eq!(unscoped.id, None);
+        assert_eq!(unscoped.include_deleted, Some(true));
+    }
+
+    #[test]
+    fn test_only_admins_may_look_up_deleted_users() {
+        assert!(may_include_deleted(&admin(), true));
+        assert!(!may_include_deleted(&admin(), false));
+        assert!(!may_include_deleted(&user(), true));
+    }
+}
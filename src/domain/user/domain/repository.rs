@@ -4,33 +4,50 @@
 use std::future::Future;
 
 use crate::{
-    common::pagination::PageRequest,
-    domain::user::{CreateUserDto, SearchUserDto, UpdateUserDto},
+    common::{pagination::PageRequest, query_spec::QuerySpec},
+    domain::user::{CreateUserDto, UpdateUserDto},
 };
 
-use super::model::{User, UserId};
+use super::model::{EmailVerificationToken, SignupInvitation, User, UserId};
 
 use sqlx::{PgPool, Postgres, Transaction};
 
 /// Trait representing repository-level operations for user entities.
 /// Provides methods for creating, retrieving, updating, and deleting users in the database.
 pub trait UserRepository: Send + Sync {
-    /// Finds a user by their unique identifier.
+    /// Finds a user by their unique identifier. Deleted users are not found.
     fn find_by_id(
         &self,
         pool: &PgPool,
         id: &UserId,
     ) -> impl Future<Output = Result<Option<User>, sqlx::Error>> + Send;
 
-    /// Finds user list by condition with pagination.
+    /// Finds a user by their unique identifier, whether or not it is deleted.
+    fn find_by_id_with_deleted(
+        &self,
+        pool: &PgPool,
+        id: &UserId,
+    ) -> impl Future<Output = Result<Option<User>, sqlx::Error>> + Send;
+
+    /// Finds the users matching `spec`, in its order, with pagination.
     /// Returns a tuple of (users, total_count).
     fn find_list(
         &self,
         pool: &PgPool,
-        search_user_dto: SearchUserDto,
+        spec: &QuerySpec,
         page_request: &PageRequest,
     ) -> impl Future<Output = Result<(Vec<User>, u64), sqlx::Error>> + Send;
 
+    /// Finds the users matching `spec` from its seek position on, for cursor pagination.
+    /// Returns up to one more user than the page size, and the total count if
+    /// `include_total` was requested.
+    fn find_list_by_cursor(
+        &self,
+        pool: &PgPool,
+        spec: &QuerySpec,
+        page_request: &PageRequest,
+    ) -> impl Future<Output = Result<(Vec<User>, Option<u64>), sqlx::Error>> + Send;
+
     /// Creates a new user record using the provided data within an active transaction.
     fn create(
         &self,
@@ -38,18 +55,131 @@ pub trait UserRepository: Send + Sync {
         user: CreateUserDto,
     ) -> impl Future<Output = Result<UserId, sqlx::Error>> + Send;
 
+    /// Creates a user who signed up themselves, recorded as their own creator.
+    fn create_self_registered(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        username: &str,
+        email: &str,
+    ) -> impl Future<Output = Result<UserId, sqlx::Error>> + Send;
+
     /// Updates an existing user record using the provided data.
+    /// Returns `None` if the user does not exist or, with `expected_version`, has another version.
     fn update(
         &self,
         tx: &mut Transaction<'_, Postgres>,
         id: &UserId,
         user: UpdateUserDto,
+        expected_version: Option<i64>,
     ) -> impl Future<Output = Result<Option<User>, sqlx::Error>> + Send;
 
-    /// Deletes a user by their unique identifier within an active transaction.
+    /// Changes only the given fields of a user; `None` leaves a field untouched.
+    /// Changing the email address clears its verification.
+    /// Returns `None` if the user does not exist or, with `expected_version`, has another version.
+    fn patch(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &UserId,
+        username: Option<&str>,
+        email: Option<&str>,
+        modified_by: &str,
+        expected_version: Option<i64>,
+    ) -> impl Future<Output = Result<Option<User>, sqlx::Error>> + Send;
+
+    /// Marks a user as deleted within an active transaction.
+    /// Returns `true` if a user that was not deleted yet, and has `expected_version` if given,
+    /// was found.
     fn delete(
         &self,
         tx: &mut Transaction<'_, Postgres>,
         id: &UserId,
+        deleted_by: &str,
+        expected_version: Option<i64>,
     ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Clears the deletion of a user. Returns `None` if no deleted user has the given ID.
+    fn restore(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &UserId,
+        restored_by: &str,
+    ) -> impl Future<Output = Result<Option<User>, sqlx::Error>> + Send;
+
+    /// Permanently removes a deleted user together with the rows that belong to it.
+    /// Returns `true` if a deleted user was removed.
+    fn purge(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &UserId,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Marks the user's email as verified, provided it is still `email`.
+    /// Returns `true` if the user was updated.
+    fn mark_email_verified(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &UserId,
+        email: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for email verification tokens.
+pub trait EmailVerificationRepository: Send + Sync {
+    /// Inserts a new email verification token within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: &EmailVerificationToken,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Finds a verification token by its hash and locks the row for the rest of the transaction.
+    fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> impl Future<Output = Result<Option<EmailVerificationToken>, sqlx::Error>> + Send;
+
+    /// Marks every unused token of a user as used.
+    fn invalidate_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &UserId,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+
+    /// Deletes tokens that have expired or were used.
+    fn delete_stale(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+}
+
+/// Trait representing the repository contract for signup invitations.
+pub trait SignupInvitationRepository: Send + Sync {
+    /// Inserts a new invitation within an active transaction.
+    fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        invitation: &SignupInvitation,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Finds an invitation by its hash and locks the row for the rest of the transaction.
+    fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> impl Future<Output = Result<Option<SignupInvitation>, sqlx::Error>> + Send;
+
+    /// Marks an invitation as used by the user who signed up with it.
+    fn mark_used(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+        user_id: &UserId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Deletes invitations that have expired or were used.
+    fn delete_stale(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
 }
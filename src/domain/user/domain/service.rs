@@ -1,11 +1,21 @@
 //! This module defines the `UserServiceTrait` responsible for user-related business logic.
 //! It abstracts operations such as user creation, retrieval, update, and deletion.
+//! Every operation runs on behalf of an `Actor` and is checked against the user policy.
 
 use std::future::Future;
 
+use serde_json::{Map, Value};
+
 use crate::{
-    common::{error::AppError, pagination::PageRequest},
-    domain::user::{CreateUserDto, SearchUserDto, UpdateUserDto, User, UserId},
+    common::{
+        error::AppError,
+        etag::IfMatch,
+        pagination::{CursorPage, PageRequest},
+    },
+    domain::user::{
+        Actor, CreateInvitationDto, CreateUserDto, CreatedInvitationDto, SearchUserDto, SignupDto,
+        UpdateUserDto, User, UserId, VerifyEmailDto,
+    },
 };
 
 /// Trait defining business operations for user management.
@@ -13,29 +23,113 @@ use crate::{
 /// Returns domain User objects - handlers are responsible for converting to DTOs.
 pub trait UserServiceTrait: Send + Sync {
     /// Retrieves a user by their unique identifier.
-    fn get_user_by_id(&self, id: &UserId) -> impl Future<Output = Result<User, AppError>> + Send;
+    /// Deleted users are only returned to admins asking for them with `include_deleted`.
+    fn get_user_by_id(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+        include_deleted: bool,
+    ) -> impl Future<Output = Result<User, AppError>> + Send;
 
     /// Retrieves users with optional filters and pagination.
     /// Returns a tuple of (users, total_count).
+    /// Non-admin actors only see their own record.
     fn get_user_list(
         &self,
+        actor: &Actor,
         search_user_dto: SearchUserDto,
         page_request: &PageRequest,
     ) -> impl Future<Output = Result<(Vec<User>, u64), AppError>> + Send;
 
-    /// Creates a new user.
+    /// Retrieves users with optional filters, paginated with the cursor of `page_request`.
+    /// Non-admin actors only see their own record.
+    fn get_user_list_by_cursor(
+        &self,
+        actor: &Actor,
+        search_user_dto: SearchUserDto,
+        page_request: &PageRequest,
+    ) -> impl Future<Output = Result<CursorPage<User>, AppError>> + Send;
+
+    /// Creates a new user and emails them a verification link.
     fn create_user(
         &self,
+        actor: &Actor,
         create_user: CreateUserDto,
     ) -> impl Future<Output = Result<User, AppError>> + Send;
 
     /// Updates an existing user with the given payload.
+    /// Changing the email address marks it unverified and sends a new verification link.
+    /// `if_match` is checked against the user's version; see `IfMatch`.
     fn update_user(
         &self,
+        actor: &Actor,
         id: &UserId,
         payload: UpdateUserDto,
+        if_match: &IfMatch,
     ) -> impl Future<Output = Result<User, AppError>> + Send;
 
-    /// Deletes a user by their unique identifier.
-    fn delete_user(&self, id: &UserId) -> impl Future<Output = Result<String, AppError>> + Send;
+    /// Applies a JSON Merge Patch to the user's `PatchUserDto` fields and validates the result.
+    /// Only changed fields are written; changing the email address behaves as in `update_user`.
+    fn patch_user(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+        patch: Map<String, Value>,
+        modified_by: &str,
+        if_match: &IfMatch,
+    ) -> impl Future<Output = Result<User, AppError>> + Send;
+
+    /// Deletes a user by their unique identifier. The user is only deactivated
+    /// and can be restored until it is purged. All of the user's tokens are revoked
+    /// with the deletion.
+    fn delete_user(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+        deleted_by: &str,
+        if_match: &IfMatch,
+    ) -> impl Future<Output = Result<String, AppError>> + Send;
+
+    /// Restores a deleted user.
+    fn restore_user(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+        restored_by: &str,
+    ) -> impl Future<Output = Result<User, AppError>> + Send;
+
+    /// Permanently removes a deleted user and the rows that belong to it, once every
+    /// token issued to it before the deletion has expired.
+    fn purge_user(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Sends a new verification link for the user's current email address.
+    fn resend_verification_email(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Confirms an email address using a single-use verification token.
+    fn verify_email(
+        &self,
+        payload: VerifyEmailDto,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Creates an account with a password for someone signing up themselves.
+    /// Depending on `signup_mode`, signing up is open, requires an invitation, or is refused.
+    fn sign_up(&self, payload: SignupDto) -> impl Future<Output = Result<User, AppError>> + Send;
+
+    /// Creates an invitation to sign up, and emails it if it is for an address.
+    /// `created_by` is the user accountable for the request, which differs from `actor`
+    /// under impersonation.
+    fn create_invitation(
+        &self,
+        actor: &Actor,
+        payload: CreateInvitationDto,
+        created_by: &str,
+    ) -> impl Future<Output = Result<CreatedInvitationDto, AppError>> + Send;
 }
@@ -3,7 +3,15 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
 
-use crate::{common::pagination::PageResponse, domain::user::User};
+use crate::{
+    common::{
+        error::AppError,
+        pagination::{CursorPage, PageResponse},
+        password_policy::validate_password,
+        query_spec::{Field, FilterOp, QuerySpec},
+    },
+    domain::user::User,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserDto {
@@ -16,6 +24,11 @@ pub struct UserDto {
     pub modified_by: Option<String>,
     #[serde(with = "crate::common::ts_format::option")]
     pub modified_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::common::ts_format::option")]
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<String>,
+    #[serde(with = "crate::common::ts_format::option")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl From<User> for UserDto {
@@ -28,15 +41,61 @@ impl From<User> for UserDto {
             created_at: user.created_at,
             modified_by: user.modified_by.map(|id| id.into_inner()),
             modified_at: user.modified_at,
+            email_verified_at: user.email_verified_at,
+            deleted_by: user.deleted_by.map(|id| id.into_inner()),
+            deleted_at: user.deleted_at,
         }
     }
 }
 
+/// Query parameters for listing users. See `common::query_spec` for the filter syntax.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SearchUserDto {
     pub id: Option<String>,
     pub username: Option<String>,
     pub email: Option<String>,
+    pub created_at: Option<String>,
+    pub modified_at: Option<String>,
+    /// Also return deleted users. Only honoured for admins.
+    pub include_deleted: Option<bool>,
+    /// Comma-separated fields to sort by, descending with a leading `-`.
+    pub sort: Option<String>,
+}
+
+const ID_FIELD: Field = Field::text("id", "id", FilterOp::Eq);
+const USERNAME_FIELD: Field = Field::text("username", "username", FilterOp::ILike);
+const EMAIL_FIELD: Field = Field::text("email", "email", FilterOp::ILike);
+const CREATED_AT_FIELD: Field = Field::timestamp("created_at", "created_at");
+const MODIFIED_AT_FIELD: Field = Field::timestamp("modified_at", "modified_at");
+
+/// Fields users can be sorted by.
+const USER_SORT_FIELDS: [Field; 4] =
+    [USERNAME_FIELD, EMAIL_FIELD, CREATED_AT_FIELD, MODIFIED_AT_FIELD];
+
+impl SearchUserDto {
+    /// Parses the filters and sort order. Newest users come first unless `sort` says otherwise.
+    pub fn query_spec(&self) -> Result<QuerySpec, AppError> {
+        let spec = QuerySpec::new("id")
+            .filter(&ID_FIELD, self.id.as_deref())?
+            .filter(&USERNAME_FIELD, self.username.as_deref())?
+            .filter(&EMAIL_FIELD, self.email.as_deref())?
+            .filter(&CREATED_AT_FIELD, self.created_at.as_deref())?
+            .filter(&MODIFIED_AT_FIELD, self.modified_at.as_deref())?
+            .sort(&USER_SORT_FIELDS, self.sort.as_deref(), "-created_at")?;
+
+        Ok(if self.include_deleted == Some(true) {
+            spec
+        } else {
+            spec.is_null("deleted_at")
+        })
+    }
+}
+
+/// Query parameters for looking up a single user.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UserLookupDto {
+    /// Also return the user if it is deleted. Only honoured for admins.
+    pub include_deleted: Option<bool>,
 }
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct CreateUserDto {
@@ -57,6 +116,60 @@ pub struct UpdateUserDto {
     pub modified_by: String,
 }
 
+/// The fields of a user that `PATCH /users/{id}` can change.
+///
+/// The request body is a JSON Merge Patch (RFC 7396) of this document: omitted fields keep their
+/// current value. The patch is applied to the user's current fields and the result is validated.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct PatchUserDto {
+    #[validate(length(max = 64, message = "Username cannot exceed 64 characters"))]
+    #[schema(required = false)]
+    pub username: String,
+    #[validate(email(message = "Invalid email format"))]
+    #[schema(required = false)]
+    pub email: String,
+}
+
+/// Request body for confirming an email address with a verification token.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct VerifyEmailDto {
+    #[validate(length(min = 1, message = "Verification token is required"))]
+    pub token: String,
+}
+
+/// Request body for creating an account at `/auth/signup`.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct SignupDto {
+    #[validate(length(min = 1, max = 64, message = "Username must be 1 to 64 characters"))]
+    pub username: String,
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+    #[validate(custom(function = validate_password))]
+    pub password: String,
+    /// Invitation token; required when signup is invite-only.
+    pub invitation: Option<String>,
+}
+
+/// Request body for inviting someone to sign up.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateInvitationDto {
+    /// If set, the invitation is emailed to this address and only admits it.
+    #[validate(email(message = "Invalid email format"))]
+    pub email: Option<String>,
+}
+
+/// A newly created invitation. The token is only returned once.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedInvitationDto {
+    pub id: String,
+    /// Token to pass as `invitation` to `/auth/signup`.
+    pub invitation: String,
+    pub email: Option<String>,
+    #[serde(with = "crate::common::ts_format")]
+    pub expires_at: DateTime<Utc>,
+}
+
 /// Paginated response containing a list of users.
 #[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct PagedUserDto {
@@ -83,3 +196,38 @@ impl From<PageResponse<UserDto>> for PagedUserDto {
         }
     }
 }
+
+/// Page of users paginated with cursors.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CursorPagedUserDto {
+    /// The users on the current page
+    pub items: Vec<UserDto>,
+    /// Maximum number of users per page
+    pub page_size: u32,
+    /// Pass as `cursor` to get the next page; absent on the last page
+    pub next_cursor: Option<String>,
+    /// Pass as `cursor` to get the previous page; absent on the first page
+    pub prev_cursor: Option<String>,
+    /// Total number of users, if requested with `include_total`
+    pub total: Option<u64>,
+}
+
+impl From<CursorPage<UserDto>> for CursorPagedUserDto {
+    fn from(page: CursorPage<UserDto>) -> Self {
+        Self {
+            items: page.items,
+            page_size: page.page_size,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+            total: page.total,
+        }
+    }
+}
+
+/// A list of users, paginated by offset or, if a `cursor` was sent, with cursors.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum UserListDto {
+    Page(PagedUserDto),
+    Cursor(CursorPagedUserDto),
+}
@@ -1,11 +1,11 @@
 use crate::{
-    common::pagination::PageRequest,
+    common::{pagination::PageRequest, query_spec::QuerySpec, rbac::DEFAULT_ROLE},
     domain::user::{
         domain::{
-            model::{User, UserId},
-            repository::UserRepository,
+            model::{EmailVerificationToken, SignupInvitation, User, UserId},
+            repository::{EmailVerificationRepository, SignupInvitationRepository, UserRepository},
         },
-        dto::user_dto::{CreateUserDto, SearchUserDto, UpdateUserDto},
+        dto::user_dto::{CreateUserDto, UpdateUserDto},
     },
 };
 
@@ -16,59 +16,82 @@ use uuid::Uuid;
 pub struct UserRepo;
 
 const FIND_USER_BY_ID_QUERY: &str = r#"
-    SELECT id, username, email, created_by, created_at, modified_by, modified_at
+    SELECT id, username, email, created_by, created_at, modified_by, modified_at,
+           email_verified_at, deleted_by, deleted_at, version
     FROM users
     WHERE id = $1
+      AND deleted_at IS NULL
     "#;
 
+const FIND_USER_BY_ID_WITH_DELETED_QUERY: &str = r#"
+    SELECT id, username, email, created_by, created_at, modified_by, modified_at,
+           email_verified_at, deleted_by, deleted_at, version
+    FROM users
+    WHERE id = $1
+    "#;
+
+const FIND_USER_LIST_QUERY: &str = r#"
+    SELECT id, username, email, created_by, created_at, modified_by, modified_at,
+           email_verified_at, deleted_by, deleted_at, version
+    FROM users
+    WHERE 1=1"#;
+
+/// Counts the users matching the filters of `spec`.
+async fn count_users(pool: &PgPool, spec: &QuerySpec) -> Result<u64, sqlx::Error> {
+    let mut count_builder =
+        QueryBuilder::<Postgres>::new("SELECT COUNT(*) as count FROM users WHERE 1=1");
+    spec.push_where(&mut count_builder);
+    let count_row = count_builder.build().fetch_one(pool).await?;
+    let total: i64 = count_row.get("count");
+    Ok(total as u64)
+}
+
 impl UserRepository for UserRepo {
     async fn find_list(
         &self,
         pool: &PgPool,
-        search_user_dto: SearchUserDto,
+        spec: &QuerySpec,
         page_request: &PageRequest,
     ) -> Result<(Vec<User>, u64), sqlx::Error> {
-        // Build WHERE clause for reuse
-        let build_where_clause = |builder: &mut QueryBuilder<'_, Postgres>| {
-            if let Some(s) = search_user_dto
-                .id
-                .as_deref()
-                .filter(|s| !s.trim().is_empty())
-            {
-                builder.push(" AND id = ");
-                builder.push_bind(s.to_string());
-            }
-
-            if let Some(s) = search_user_dto
-                .username
-                .as_deref()
-                .filter(|s| !s.trim().is_empty())
-            {
-                builder.push(" AND username LIKE ");
-                builder.push_bind(format!("%{s}%"));
-            }
-        };
-
-        // Count query
-        let mut count_builder =
-            QueryBuilder::<Postgres>::new("SELECT COUNT(*) as count FROM users WHERE 1=1");
-        build_where_clause(&mut count_builder);
-        let count_row = count_builder.build().fetch_one(pool).await?;
-        let total: i64 = count_row.get("count");
+        let total = count_users(pool, spec).await?;
 
         // Data query with pagination
-        let mut data_builder = QueryBuilder::<Postgres>::new(
-            "SELECT id, username, email, created_by, created_at, modified_by, modified_at FROM users WHERE 1=1",
-        );
-        build_where_clause(&mut data_builder);
-        data_builder.push(" ORDER BY created_at DESC LIMIT ");
+        let mut data_builder = QueryBuilder::<Postgres>::new(FIND_USER_LIST_QUERY);
+        spec.push_where(&mut data_builder);
+        spec.push_order_by(&mut data_builder);
+        data_builder.push(" LIMIT ");
         data_builder.push_bind(page_request.limit());
         data_builder.push(" OFFSET ");
         data_builder.push_bind(page_request.offset());
 
         let users = data_builder.build_query_as::<User>().fetch_all(pool).await?;
 
-        Ok((users, total as u64))
+        Ok((users, total))
+    }
+
+    async fn find_list_by_cursor(
+        &self,
+        pool: &PgPool,
+        spec: &QuerySpec,
+        page_request: &PageRequest,
+    ) -> Result<(Vec<User>, Option<u64>), sqlx::Error> {
+        let total = if page_request.include_total {
+            Some(count_users(pool, spec).await?)
+        } else {
+            None
+        };
+
+        // One extra row shows whether there is another page
+        let mut data_builder = QueryBuilder::<Postgres>::new(FIND_USER_LIST_QUERY);
+        spec.push_where(&mut data_builder);
+        spec.push_seek(&mut data_builder);
+        spec.push_order_by(&mut data_builder);
+        data_builder.push(" LIMIT ");
+        data_builder.push_bind(page_request.limit() + 1);
+
+        let users = data_builder.build_query_as::<User>().fetch_all(pool).await?;
+
+        Ok((users, total))
     }
 
     async fn find_by_id(&self, pool: &PgPool, id: &UserId) -> Result<Option<User>, sqlx::Error> {
@@ -79,78 +102,397 @@ impl UserRepository for UserRepo {
         Ok(user)
     }
 
+    async fn find_by_id_with_deleted(
+        &self,
+        pool: &PgPool,
+        id: &UserId,
+    ) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(FIND_USER_BY_ID_WITH_DELETED_QUERY)
+            .bind(id.as_str())
+            .fetch_optional(pool)
+            .await
+    }
+
     async fn create(
         &self,
         tx: &mut Transaction<'_, Postgres>,
         user: CreateUserDto,
     ) -> Result<UserId, sqlx::Error> {
         let id = UserId::new(Uuid::new_v4().to_string());
+        insert_user(tx, &id, &user.username, &user.email, &user.modified_by).await?;
+        Ok(id)
+    }
 
-        sqlx::query(
+    async fn create_self_registered(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        username: &str,
+        email: &str,
+    ) -> Result<UserId, sqlx::Error> {
+        let id = UserId::new(Uuid::new_v4().to_string());
+        insert_user(tx, &id, username, email, id.as_str()).await?;
+        Ok(id)
+    }
+
+    async fn update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &UserId,
+        user: UpdateUserDto,
+        expected_version: Option<i64>,
+    ) -> Result<Option<User>, sqlx::Error> {
+        let res = sqlx::query(
             r#"
-                INSERT INTO users (id, username, email, created_by, modified_by)
-                VALUES ($1, $2, $3, $4, $5)
-                "#,
+            UPDATE users
+            SET username = $1,
+                email = $2,
+                email_verified_at = CASE WHEN email = $2 THEN email_verified_at END,
+                modified_by = $3,
+                modified_at = NOW(),
+                version = version + 1
+            WHERE id = $4
+              AND deleted_at IS NULL
+              AND ($5::BIGINT IS NULL OR version = $5)
+            "#,
         )
-        .bind(id.as_str())
         .bind(&user.username)
         .bind(&user.email)
         .bind(&user.modified_by)
-        .bind(&user.modified_by)
+        .bind(id.as_str())
+        .bind(expected_version)
         .execute(&mut **tx)
         .await?;
 
-        Ok(id)
+        if res.rows_affected() == 0 {
+            return Ok(None);
+        }
+        let updated_user = sqlx::query_as::<_, User>(FIND_USER_BY_ID_QUERY)
+            .bind(id.as_str())
+            .fetch_one(&mut **tx)
+            .await?;
+        Ok(Some(updated_user))
     }
 
-    async fn update(
+    async fn patch(
         &self,
         tx: &mut Transaction<'_, Postgres>,
         id: &UserId,
-        user: UpdateUserDto,
+        username: Option<&str>,
+        email: Option<&str>,
+        modified_by: &str,
+        expected_version: Option<i64>,
+    ) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+               SET username = COALESCE($1, username),
+                   email = COALESCE($2, email),
+                   email_verified_at = CASE WHEN email = COALESCE($2, email)
+                                            THEN email_verified_at END,
+                   modified_by = $3,
+                   modified_at = NOW(),
+                   version = version + 1
+             WHERE id = $4
+               AND deleted_at IS NULL
+               AND ($5::BIGINT IS NULL OR version = $5)
+            RETURNING id, username, email, created_by, created_at, modified_by, modified_at,
+                      email_verified_at, deleted_by, deleted_at, version
+            "#,
+        )
+        .bind(username)
+        .bind(email)
+        .bind(modified_by)
+        .bind(id.as_str())
+        .bind(expected_version)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn delete(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &UserId,
+        deleted_by: &str,
+        expected_version: Option<i64>,
+    ) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE users
+               SET deleted_at = NOW(),
+                   deleted_by = $2,
+                   modified_by = $2,
+                   modified_at = NOW(),
+                   version = version + 1
+             WHERE id = $1
+               AND deleted_at IS NULL
+               AND ($3::BIGINT IS NULL OR version = $3)
+            "#,
+        )
+        .bind(id.as_str())
+        .bind(deleted_by)
+        .bind(expected_version)
+        .execute(&mut **tx)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn restore(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &UserId,
+        restored_by: &str,
     ) -> Result<Option<User>, sqlx::Error> {
-        let existing = sqlx::query_as::<_, User>(FIND_USER_BY_ID_QUERY)
+        sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+               SET deleted_at = NULL,
+                   deleted_by = NULL,
+                   modified_by = $2,
+                   modified_at = NOW(),
+                   version = version + 1
+             WHERE id = $1
+               AND deleted_at IS NOT NULL
+            RETURNING id, username, email, created_by, created_at, modified_by, modified_at,
+                      email_verified_at, deleted_by, deleted_at, version
+            "#,
+        )
+        .bind(id.as_str())
+        .bind(restored_by)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn purge(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &UserId,
+    ) -> Result<bool, sqlx::Error> {
+        // Revoked access tokens are the only rows of the user without a cascading foreign key.
+        sqlx::query(r#"DELETE FROM revoked_tokens WHERE user_id = $1"#)
             .bind(id.as_str())
-            .fetch_optional(&mut **tx)
+            .execute(&mut **tx)
             .await?;
 
-        if existing.is_some() {
-            sqlx::query(
-                r#"
-                UPDATE users
-                SET username = $1,
-                    email = $2,
-                    modified_by = $3,
-                    modified_at = NOW()
-                WHERE id = $4
-                "#,
-            )
-            .bind(&user.username)
-            .bind(&user.email)
-            .bind(&user.modified_by)
+        let res = sqlx::query(r#"DELETE FROM users WHERE id = $1 AND deleted_at IS NOT NULL"#)
             .bind(id.as_str())
             .execute(&mut **tx)
             .await?;
-
-            let updated_user = sqlx::query_as::<_, User>(FIND_USER_BY_ID_QUERY)
-                .bind(id.as_str())
-                .fetch_one(&mut **tx)
-                .await?;
-
-            return Ok(Some(updated_user));
-        }
-        Ok(None)
+        Ok(res.rows_affected() > 0)
     }
 
-    async fn delete(
+    async fn mark_email_verified(
         &self,
         tx: &mut Transaction<'_, Postgres>,
         id: &UserId,
+        email: &str,
     ) -> Result<bool, sqlx::Error> {
-        let res = sqlx::query(r#"DELETE FROM users WHERE id = $1"#)
-            .bind(id.as_str())
-            .execute(&mut **tx)
-            .await?;
+        let res = sqlx::query(
+            r#"
+            UPDATE users
+               SET email_verified_at = NOW(),
+                   version = version + 1
+             WHERE id = $1
+               AND email = $2
+            "#,
+        )
+        .bind(id.as_str())
+        .bind(email)
+        .execute(&mut **tx)
+        .await?;
         Ok(res.rows_affected() > 0)
     }
 }
+
+/// Inserts a user with the default role.
+async fn insert_user(
+    tx: &mut Transaction<'_, Postgres>,
+    id: &UserId,
+    username: &str,
+    email: &str,
+    created_by: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+            INSERT INTO users (id, username, email, created_by, modified_by)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+    )
+    .bind(id.as_str())
+    .bind(username)
+    .bind(email)
+    .bind(created_by)
+    .bind(created_by)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(r#"INSERT INTO user_roles (user_id, role_name) VALUES ($1, $2)"#)
+        .bind(id.as_str())
+        .bind(DEFAULT_ROLE)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct EmailVerificationRepo;
+
+impl EmailVerificationRepository for EmailVerificationRepo {
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: &EmailVerificationToken,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO email_verification_tokens
+            (id, user_id, email, token_hash, expires_at)
+            VALUES
+            ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(&token.id)
+        .bind(token.user_id.as_str())
+        .bind(&token.email)
+        .bind(&token.token_hash)
+        .bind(token.expires_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<EmailVerificationToken>, sqlx::Error> {
+        sqlx::query_as::<_, EmailVerificationToken>(
+            r#"
+            SELECT id, user_id, email, token_hash, expires_at, used_at
+              FROM email_verification_tokens
+             WHERE token_hash = $1
+               FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn invalidate_for_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &UserId,
+    ) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            UPDATE email_verification_tokens
+               SET used_at = NOW()
+             WHERE user_id = $1
+               AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id.as_str())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn delete_stale(&self, tx: &mut Transaction<'_, Postgres>) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            DELETE FROM email_verification_tokens
+             WHERE expires_at < NOW()
+                OR used_at IS NOT NULL
+            "#,
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+}
+
+#[derive(Clone)]
+pub struct SignupInvitationRepo;
+
+impl SignupInvitationRepository for SignupInvitationRepo {
+    async fn create(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        invitation: &SignupInvitation,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO signup_invitations
+            (id, token_hash, email, created_by, expires_at)
+            VALUES
+            ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(&invitation.id)
+        .bind(&invitation.token_hash)
+        .bind(&invitation.email)
+        .bind(invitation.created_by.as_ref().map(UserId::as_str))
+        .bind(invitation.expires_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_hash_for_update(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<SignupInvitation>, sqlx::Error> {
+        sqlx::query_as::<_, SignupInvitation>(
+            r#"
+            SELECT id, token_hash, email, created_by, expires_at, used_at
+              FROM signup_invitations
+             WHERE token_hash = $1
+               FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    async fn mark_used(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: &str,
+        user_id: &UserId,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE signup_invitations
+               SET used_at = NOW(), used_by = $2
+             WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(user_id.as_str())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_stale(&self, tx: &mut Transaction<'_, Postgres>) -> Result<u64, sqlx::Error> {
+        let res = sqlx::query(
+            r#"
+            DELETE FROM signup_invitations
+             WHERE expires_at < NOW()
+                OR used_at IS NOT NULL
+            "#,
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+}
@@ -1,17 +1,44 @@
 use crate::{
-    common::{error::AppError, pagination::PageRequest},
-    domain::user::{
-        domain::{
-            model::{User, UserId},
-            repository::UserRepository,
-            service::UserServiceTrait,
+    common::{
+        config::{Config, SignupMode},
+        error::{is_unique_violation, AppError},
+        etag::IfMatch,
+        hash_util,
+        mailer::{self, AppMailer, Email},
+        merge_patch,
+        pagination::{CursorPage, PageRequest},
+        password_policy::PASSWORD_POLICY,
+        token_util,
+    },
+    domain::{
+        auth::{
+            RefreshTokenRepo, RefreshTokenRepository, RevocationCache, SessionRepo,
+            SessionRepository, TokenRevocationRepo, TokenRevocationRepository, UserAuth,
+            UserAuthRepo, UserAuthRepository,
+        },
+        user::{
+            domain::{
+                model::{EmailVerificationToken, SignupInvitation, User, UserId},
+                policy::{self, Actor, UserAction},
+                repository::{
+                    EmailVerificationRepository, SignupInvitationRepository, UserRepository,
+                },
+                service::UserServiceTrait,
+            },
+            dto::user_dto::{
+                CreateInvitationDto, CreateUserDto, CreatedInvitationDto, PatchUserDto,
+                SearchUserDto, SignupDto, UpdateUserDto, VerifyEmailDto,
+            },
+            infra::postgres_repository::{EmailVerificationRepo, SignupInvitationRepo, UserRepo},
         },
-        dto::user_dto::{CreateUserDto, SearchUserDto, UpdateUserDto},
-        infra::postgres_repository::UserRepo,
     },
 };
-use sqlx::PgPool;
+use chrono::{Duration, Utc};
+use serde_json::{json, Map, Value};
+use sqlx::{PgPool, Postgres, Transaction};
 use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
 
 /// Service struct for handling user-related operations
 /// such as creating, updating, deleting, and fetching users.
@@ -20,24 +47,183 @@ use std::sync::Arc;
 pub struct UserService {
     pub pool: PgPool,
     pub repo: UserRepo,
+    pub verification_repo: EmailVerificationRepo,
+    pub invitation_repo: SignupInvitationRepo,
+    auth_repo: UserAuthRepo,
+    revocation_repo: TokenRevocationRepo,
+    refresh_token_repo: RefreshTokenRepo,
+    session_repo: SessionRepo,
+    revocation_cache: Arc<RevocationCache>,
+    config: Config,
+    mailer: Arc<AppMailer>,
 }
 
 impl UserService {
-    /// constructor for the service.
-    pub fn new(pool: PgPool) -> Arc<Self> {
+    /// constructor for the service. `revocation_cache` is the one `jwt_auth` consults, so
+    /// tokens revoked when a user is deleted are rejected right away.
+    pub fn new(
+        pool: PgPool,
+        config: Config,
+        mailer: Arc<AppMailer>,
+        revocation_cache: Arc<RevocationCache>,
+    ) -> Arc<Self> {
         Arc::new(Self {
             pool,
             repo: UserRepo,
+            verification_repo: EmailVerificationRepo,
+            invitation_repo: SignupInvitationRepo,
+            auth_repo: UserAuthRepo,
+            revocation_repo: TokenRevocationRepo,
+            refresh_token_repo: RefreshTokenRepo,
+            session_repo: SessionRepo,
+            revocation_cache,
+            config,
+            mailer,
         })
     }
+
+    /// Creates a verification token for `email` within the given transaction, replacing any
+    /// earlier token of the user. Returns the plain token to be emailed.
+    async fn create_verification_token(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &UserId,
+        email: &str,
+    ) -> Result<String, AppError> {
+        let token = token_util::generate_token();
+        let record = EmailVerificationToken {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.clone(),
+            email: email.to_string(),
+            token_hash: token_util::hash_token(&token),
+            expires_at: Utc::now()
+                + Duration::seconds(self.config.email_verification_token_ttl_secs),
+            used_at: None,
+        };
+
+        self.verification_repo
+            .invalidate_for_user(tx, user_id)
+            .await?;
+        self.verification_repo.delete_stale(tx).await?;
+        self.verification_repo
+            .create(tx, &record)
+            .await
+            .inspect_err(|e| tracing::error!("Error creating email verification token: {e}"))?;
+
+        Ok(token)
+    }
+
+    /// Emails a verification link to the user's address.
+    fn send_verification_email(&self, user: &User, token: &str) {
+        let Some(to) = user.email.clone() else {
+            return;
+        };
+
+        let link = format!("{}?token={token}", self.config.email_verification_url);
+        let email = Email {
+            to,
+            subject: "Verify your email address".into(),
+            body: format!(
+                "Hello {},\n\n\
+                 Please confirm your email address by opening the link below. \
+                 It expires in {} hours.\n\n\
+                 {link}\n\n\
+                 If you did not expect this email, you can ignore it.\n",
+                user.username,
+                self.config.email_verification_token_ttl_secs / 3600
+            ),
+        };
+        mailer::send_in_background(self.mailer.clone(), email);
+    }
+
+    /// Finds and locks the invitation a signup presents. Invitations that are unknown, used,
+    /// expired or issued for another address are rejected.
+    async fn find_invitation(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: Option<&str>,
+        email: &str,
+    ) -> Result<SignupInvitation, AppError> {
+        let token = token.ok_or_else(|| {
+            AppError::ValidationError("An invitation is required to sign up".into())
+        })?;
+
+        let invitation = self
+            .invitation_repo
+            .find_by_hash_for_update(tx, &token_util::hash_token(token))
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving signup invitation: {e}"))?
+            .filter(|invitation| {
+                invitation.used_at.is_none() && invitation.expires_at > Utc::now()
+            })
+            .ok_or_else(|| AppError::ValidationError("Invalid or expired invitation".into()))?;
+
+        if invitation
+            .email
+            .as_deref()
+            .is_some_and(|invited| !invited.eq_ignore_ascii_case(email))
+        {
+            return Err(AppError::ValidationError(
+                "The invitation is for a different email address".into(),
+            ));
+        }
+
+        Ok(invitation)
+    }
+
+    /// Emails an invitation link to the invited address.
+    fn send_invitation_email(&self, to: String, token: &str) {
+        let link = format!("{}?invitation={token}", self.config.signup_url);
+        let email = Email {
+            to,
+            subject: "You have been invited".into(),
+            body: format!(
+                "Hello,\n\n\
+                 You have been invited to create an account. Open the link below to sign up. \
+                 It expires in {} days.\n\n\
+                 {link}\n\n\
+                 If you did not expect this email, you can ignore it.\n",
+                self.config.signup_invitation_ttl_secs / 86_400
+            ),
+        };
+        mailer::send_in_background(self.mailer.clone(), email);
+    }
+}
+
+/// Maps a failed insert or update of a user, reporting a taken username as a conflict.
+fn map_write_user_error(err: sqlx::Error) -> AppError {
+    if is_unique_violation(&err) {
+        return AppError::Conflict("Username is already taken".into());
+    }
+    tracing::error!("Error writing user: {err}");
+    AppError::DatabaseError(err)
+}
+
+/// Maps a change that found no user to write. With an expected version, the user was
+/// found when the precondition was checked, so it has been changed in the meantime.
+fn user_not_written(expected_version: Option<i64>) -> AppError {
+    match expected_version {
+        Some(_) => AppError::PreconditionFailed,
+        None => AppError::NotFound("User not found".into()),
+    }
 }
 
 impl UserServiceTrait for UserService {
     /// Retrieves a user by their ID.
-    async fn get_user_by_id(&self, id: &UserId) -> Result<User, AppError> {
-        self.repo
-            .find_by_id(&self.pool, id)
-            .await
+    async fn get_user_by_id(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+        include_deleted: bool,
+    ) -> Result<User, AppError> {
+        policy::authorize(actor, UserAction::Read(id))?;
+
+        let user = if policy::may_include_deleted(actor, include_deleted) {
+            self.repo.find_by_id_with_deleted(&self.pool, id).await
+        } else {
+            self.repo.find_by_id(&self.pool, id).await
+        };
+        user
             .inspect_err(|e| tracing::error!("Error retrieving user: {e}"))?
             .ok_or_else(|| AppError::NotFound("User not found".into()))
     }
@@ -45,65 +231,529 @@ impl UserServiceTrait for UserService {
     /// Retrieves users with optional filters and pagination.
     async fn get_user_list(
         &self,
+        actor: &Actor,
         search_user_dto: SearchUserDto,
         page_request: &PageRequest,
     ) -> Result<(Vec<User>, u64), AppError> {
+        policy::authorize(actor, UserAction::List)?;
+        let spec = policy::scope_search(actor, search_user_dto).query_spec()?;
+
         self.repo
-            .find_list(&self.pool, search_user_dto, page_request)
+            .find_list(&self.pool, &spec, page_request)
             .await
             .inspect_err(|e| tracing::error!("Error fetching users: {e}"))
             .map_err(AppError::from)
     }
 
-    /// Creates a new user.
-    async fn create_user(&self, create_user: CreateUserDto) -> Result<User, AppError> {
+    /// Retrieves users with optional filters, paginated with cursors.
+    async fn get_user_list_by_cursor(
+        &self,
+        actor: &Actor,
+        search_user_dto: SearchUserDto,
+        page_request: &PageRequest,
+    ) -> Result<CursorPage<User>, AppError> {
+        policy::authorize(actor, UserAction::List)?;
+        let spec = policy::scope_search(actor, search_user_dto).query_spec()?;
+        let (spec, cursor) = self.config.cursor_key.seek(spec, page_request)?;
+
+        let (users, total) = self
+            .repo
+            .find_list_by_cursor(&self.pool, &spec, page_request)
+            .await
+            .inspect_err(|e| tracing::error!("Error fetching users: {e}"))?;
+
+        Ok(CursorPage::new(
+            users,
+            total,
+            page_request,
+            &spec,
+            cursor.as_ref(),
+            &self.config.cursor_key,
+        ))
+    }
+
+    /// Creates a new user and sends a verification link to their email address.
+    async fn create_user(
+        &self,
+        actor: &Actor,
+        create_user: CreateUserDto,
+    ) -> Result<User, AppError> {
+        policy::authorize(actor, UserAction::Create)?;
+
         let mut tx = self.pool.begin().await?;
 
+        let email = create_user.email.clone();
         let user_id = self
             .repo
             .create(&mut tx, create_user)
             .await
-            .inspect_err(|e| tracing::error!("Error creating user: {e}"))?;
+            .map_err(map_write_user_error)?;
+        let token = self
+            .create_verification_token(&mut tx, &user_id, &email)
+            .await?;
 
         tx.commit().await?;
 
-        self.repo
+        let user = self
+            .repo
             .find_by_id(&self.pool, &user_id)
             .await
             .inspect_err(|e| tracing::error!("Error retrieving user: {e}"))?
-            .ok_or_else(|| AppError::NotFound("User not found".into()))
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+        self.send_verification_email(&user, &token);
+        Ok(user)
     }
 
     /// Updates an existing user.
-    async fn update_user(&self, id: &UserId, payload: UpdateUserDto) -> Result<User, AppError> {
-        let mut tx = self.pool.begin().await?;
+    /// A changed email address has to be verified again.
+    async fn update_user(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+        payload: UpdateUserDto,
+        if_match: &IfMatch,
+    ) -> Result<User, AppError> {
+        policy::authorize(actor, UserAction::Update(id))?;
 
+        let previous = self
+            .repo
+            .find_by_id(&self.pool, id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user: {e}"))?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+        let expected_version = if_match.expected_version(previous.version)?;
+
+        let mut tx = self.pool.begin().await?;
         let user = self
             .repo
-            .update(&mut tx, id, payload)
+            .update(&mut tx, id, payload, expected_version)
             .await
             .inspect_err(|e| tracing::error!("Error updating user: {e}"))?
+            .ok_or_else(|| user_not_written(expected_version))?;
+
+        // The repository clears `email_verified_at` when the address changes.
+        let email_changed = previous.email != user.email;
+        let token = match &user.email {
+            Some(email) if email_changed => {
+                Some(self.create_verification_token(&mut tx, id, email).await?)
+            }
+            _ => None,
+        };
+
+        tx.commit().await?;
+
+        if let Some(token) = token {
+            self.send_verification_email(&user, &token);
+        }
+        Ok(user)
+    }
+
+    /// Applies a JSON Merge Patch to a user, writing only the fields that change.
+    async fn patch_user(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+        patch: Map<String, Value>,
+        modified_by: &str,
+        if_match: &IfMatch,
+    ) -> Result<User, AppError> {
+        policy::authorize(actor, UserAction::Update(id))?;
+
+        let current = self
+            .repo
+            .find_by_id(&self.pool, id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user: {e}"))?
             .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+        let expected_version = if_match.expected_version(current.version)?;
+
+        let mut document = json!({ "username": current.username, "email": current.email });
+        merge_patch::merge(&mut document, &Value::Object(patch));
+        let merged: PatchUserDto = serde_json::from_value(document)
+            .map_err(|e| AppError::ValidationError(e.to_string()))?;
+        merged
+            .validate()
+            .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        let username = Some(merged.username.as_str()).filter(|u| *u != current.username);
+        let email = Some(merged.email.as_str()).filter(|e| Some(*e) != current.email.as_deref());
+        if username.is_none() && email.is_none() {
+            return Ok(current);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let user = self
+            .repo
+            .patch(&mut tx, id, username, email, modified_by, expected_version)
+            .await
+            .map_err(map_write_user_error)?
+            .ok_or_else(|| user_not_written(expected_version))?;
+
+        let token = match email {
+            Some(email) => Some(self.create_verification_token(&mut tx, id, email).await?),
+            None => None,
+        };
 
         tx.commit().await?;
+
+        if let Some(token) = token {
+            self.send_verification_email(&user, &token);
+        }
         Ok(user)
     }
 
     /// Deletes a user by their ID.
-    async fn delete_user(&self, id: &UserId) -> Result<String, AppError> {
+    async fn delete_user(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+        deleted_by: &str,
+        if_match: &IfMatch,
+    ) -> Result<String, AppError> {
+        policy::authorize(actor, UserAction::Delete(id))?;
+
+        let user = self
+            .repo
+            .find_by_id(&self.pool, id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user: {e}"))?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+        let expected_version = if_match.expected_version(user.version)?;
+
         let mut tx = self.pool.begin().await?;
 
         let deleted = self
             .repo
-            .delete(&mut tx, id)
+            .delete(&mut tx, id, deleted_by, expected_version)
             .await
             .inspect_err(|e| tracing::error!("Error deleting user: {e}"))?;
 
         if !deleted {
-            return Err(AppError::NotFound("User not found".into()));
+            return Err(user_not_written(expected_version));
         }
 
+        // Revoke every token of the user in the same transaction, so a deleted user never
+        // keeps working credentials.
+        let revoked_before = Utc::now();
+        self.revocation_repo
+            .revoke_all_for_user(&mut tx, id.as_str(), revoked_before, deleted_by)
+            .await?;
+        self.refresh_token_repo
+            .revoke_all_for_user(&mut tx, id.as_str())
+            .await?;
+        self.session_repo
+            .revoke_all_for_user(&mut tx, id.as_str(), None)
+            .await?;
+
         tx.commit().await?;
+        self.revocation_cache
+            .store_user(id.as_str(), Some(revoked_before.timestamp_millis()));
+        tracing::info!(user_id = %id, deleted_by, "User deleted, all tokens revoked");
         Ok("User deleted".into())
     }
+
+    /// Restores a deleted user. Its tokens stay revoked, so it has to log in again.
+    async fn restore_user(
+        &self,
+        actor: &Actor,
+        id: &UserId,
+        restored_by: &str,
+    ) -> Result<User, AppError> {
+        policy::authorize(actor, UserAction::Restore(id))?;
+
+        let mut tx = self.pool.begin().await?;
+        let user = self
+            .repo
+            .restore(&mut tx, id, restored_by)
+            .await
+            .inspect_err(|e| tracing::error!("Error restoring user: {e}"))?
+            .ok_or_else(|| AppError::NotFound("Deleted user not found".into()))?;
+        tx.commit().await?;
+
+        tracing::info!(user_id = %id, restored_by, "User restored");
+        Ok(user)
+    }
+
+    /// Permanently removes a deleted user.
+    ///
+    /// Purging also drops the user's token revocation cut-off, so it has to wait until the
+    /// access and impersonation tokens issued before the deletion have expired.
+    async fn purge_user(&self, actor: &Actor, id: &UserId) -> Result<(), AppError> {
+        policy::authorize(actor, UserAction::Purge(id))?;
+
+        let user = self
+            .repo
+            .find_by_id_with_deleted(&self.pool, id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user: {e}"))?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+        let Some(deleted_at) = user.deleted_at else {
+            return Err(AppError::Conflict("Delete the user before purging it".into()));
+        };
+        let token_ttl_secs = self
+            .config
+            .access_token_ttl_secs
+            .max(self.config.impersonation_token_ttl_secs);
+        if deleted_at + Duration::seconds(token_ttl_secs) > Utc::now() {
+            return Err(AppError::Conflict(format!(
+                "Deleted users can be purged once their tokens have expired, \
+                 {token_ttl_secs} seconds after deletion"
+            )));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let purged = self
+            .repo
+            .purge(&mut tx, id)
+            .await
+            .inspect_err(|e| tracing::error!("Error purging user: {e}"))?;
+        if !purged {
+            return Err(AppError::NotFound("User not found".into()));
+        }
+        tx.commit().await?;
+
+        tracing::warn!(user_id = %id, actor = %actor.id, "User purged");
+        Ok(())
+    }
+
+    /// Sends a new verification link, invalidating earlier ones.
+    async fn resend_verification_email(&self, actor: &Actor, id: &UserId) -> Result<(), AppError> {
+        policy::authorize(actor, UserAction::Update(id))?;
+
+        let user = self
+            .repo
+            .find_by_id(&self.pool, id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user: {e}"))?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+        if user.email_verified_at.is_some() {
+            return Err(AppError::ValidationError("Email address is already verified".into()));
+        }
+        let Some(email) = user.email.as_deref() else {
+            return Err(AppError::ValidationError("User has no email address".into()));
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let token = self.create_verification_token(&mut tx, id, email).await?;
+        tx.commit().await?;
+
+        self.send_verification_email(&user, &token);
+        Ok(())
+    }
+
+    /// Marks the email address the token was issued for as verified.
+    /// Tokens that are unknown, used, expired or issued for a previous address are rejected.
+    async fn verify_email(&self, payload: VerifyEmailDto) -> Result<(), AppError> {
+        let token_hash = token_util::hash_token(&payload.token);
+
+        let mut tx = self.pool.begin().await?;
+
+        let token = self
+            .verification_repo
+            .find_by_hash_for_update(&mut tx, &token_hash)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving email verification token: {e}"))?
+            .ok_or(AppError::InvalidToken)?;
+
+        if token.used_at.is_some() || token.expires_at <= Utc::now() {
+            return Err(AppError::InvalidToken);
+        }
+
+        let verified = self
+            .repo
+            .mark_email_verified(&mut tx, &token.user_id, &token.email)
+            .await
+            .inspect_err(|e| tracing::error!("Error verifying email: {e}"))?;
+        if !verified {
+            return Err(AppError::InvalidToken);
+        }
+
+        self.verification_repo
+            .invalidate_for_user(&mut tx, &token.user_id)
+            .await?;
+        tx.commit().await?;
+
+        tracing::info!(user_id = %token.user_id, "Email address verified");
+        Ok(())
+    }
+
+    /// Creates the user, their default role and their password in one transaction.
+    /// An invitation issued for the user's address also verifies it; otherwise a verification
+    /// link is emailed.
+    async fn sign_up(&self, payload: SignupDto) -> Result<User, AppError> {
+        if self.config.signup_mode == SignupMode::Disabled {
+            return Err(AppError::Forbidden);
+        }
+
+        PASSWORD_POLICY
+            .check_identifiers(&payload.password, &[&payload.username, &payload.email])
+            .map_err(|e| AppError::ValidationError(e.to_string()))?;
+        let password_hash =
+            hash_util::hash_password(&payload.password, &self.config.password_hash_params)
+                .map_err(|_| AppError::InternalError)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let invitation = match self.config.signup_mode {
+            SignupMode::InviteOnly => Some(
+                self.find_invitation(&mut tx, payload.invitation.as_deref(), &payload.email)
+                    .await?,
+            ),
+            _ => None,
+        };
+
+        let user_id = self
+            .repo
+            .create_self_registered(&mut tx, &payload.username, &payload.email)
+            .await
+            .map_err(map_write_user_error)?;
+        self.auth_repo
+            .create(&mut tx, UserAuth::new(user_id.to_string(), password_hash))
+            .await
+            .inspect_err(|e| tracing::error!("Error creating user auth: {e}"))?;
+
+        let token = match invitation {
+            Some(invitation) => {
+                self.invitation_repo
+                    .mark_used(&mut tx, &invitation.id, &user_id)
+                    .await?;
+                if invitation.email.is_some() {
+                    self.repo
+                        .mark_email_verified(&mut tx, &user_id, &payload.email)
+                        .await?;
+                    None
+                } else {
+                    Some(self.create_verification_token(&mut tx, &user_id, &payload.email).await?)
+                }
+            }
+            None => Some(self.create_verification_token(&mut tx, &user_id, &payload.email).await?),
+        };
+
+        tx.commit().await?;
+
+        let user = self
+            .repo
+            .find_by_id(&self.pool, &user_id)
+            .await
+            .inspect_err(|e| tracing::error!("Error retrieving user: {e}"))?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+        if let Some(token) = token {
+            self.send_verification_email(&user, &token);
+        }
+        tracing::info!(user_id = %user.id, "User signed up");
+        Ok(user)
+    }
+
+    /// Creates a single-use invitation that expires after `signup_invitation_ttl_secs`.
+    async fn create_invitation(
+        &self,
+        actor: &Actor,
+        payload: CreateInvitationDto,
+        created_by: &str,
+    ) -> Result<CreatedInvitationDto, AppError> {
+        policy::authorize(actor, UserAction::Create)?;
+
+        let token = token_util::generate_token();
+        let invitation = SignupInvitation {
+            id: Uuid::new_v4().to_string(),
+            token_hash: token_util::hash_token(&token),
+            email: payload.email,
+            created_by: Some(UserId::from(created_by)),
+            expires_at: Utc::now() + Duration::seconds(self.config.signup_invitation_ttl_secs),
+            used_at: None,
+        };
+
+        let mut tx = self.pool.begin().await?;
+        self.invitation_repo.delete_stale(&mut tx).await?;
+        self.invitation_repo
+            .create(&mut tx, &invitation)
+            .await
+            .inspect_err(|e| tracing::error!("Error creating signup invitation: {e}"))?;
+        tx.commit().await?;
+
+        if let Some(email) = invitation.email.clone() {
+            self.send_invitation_email(email, &token);
+        }
+        tracing::info!(
+            actor = created_by,
+            invitation_id = invitation.id,
+            "Signup invitation created"
+        );
+
+        Ok(CreatedInvitationDto {
+            id: invitation.id,
+            invitation: token,
+            email: invitation.email,
+            expires_at: invitation.expires_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, error::Error, fmt};
+
+    use sqlx::error::{DatabaseError, ErrorKind};
+
+    use super::*;
+
+    /// A database error that is a unique violation or some other failure.
+    #[derive(Debug)]
+    struct FakeDatabaseError {
+        unique_violation: bool,
+    }
+
+    impl fmt::Display for FakeDatabaseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.message())
+        }
+    }
+
+    impl Error for FakeDatabaseError {}
+
+    impl DatabaseError for FakeDatabaseError {
+        fn message(&self) -> &str {
+            "fake database error"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            if self.unique_violation {
+                ErrorKind::UniqueViolation
+            } else {
+                ErrorKind::Other
+            }
+        }
+    }
+
+    #[test]
+    fn test_taken_username_is_a_conflict() {
+        let taken = sqlx::Error::Database(Box::new(FakeDatabaseError {
+            unique_violation: true,
+        }));
+        assert!(matches!(map_write_user_error(taken), AppError::Conflict(_)));
+
+        let other = sqlx::Error::Database(Box::new(FakeDatabaseError {
+            unique_violation: false,
+        }));
+        assert!(matches!(map_write_user_error(other), AppError::DatabaseError(_)));
+    }
 }
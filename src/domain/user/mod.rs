@@ -5,6 +5,7 @@ mod api {
 
 mod domain {
     pub mod model;
+    pub mod policy;
     pub mod repository;
     pub mod service;
 }
@@ -19,9 +20,16 @@ mod infra {
 }
 
 // Re-export commonly used items for convenience
-pub use api::routes::{user_routes, UserApiDoc};
-pub use domain::model::{User, UserId};
-pub use domain::repository::UserRepository;
+pub use api::routes::{user_public_routes, user_routes, UserApiDoc};
+pub use domain::model::{EmailVerificationToken, SignupInvitation, User, UserId};
+pub use domain::policy::{Actor, UserAction};
+pub use domain::repository::{
+    EmailVerificationRepository, SignupInvitationRepository, UserRepository,
+};
 pub use domain::service::UserServiceTrait;
-pub use dto::user_dto::{CreateUserDto, PagedUserDto, SearchUserDto, UpdateUserDto, UserDto};
+pub use dto::user_dto::{
+    CreateInvitationDto, CreateUserDto, CreatedInvitationDto, CursorPagedUserDto, PagedUserDto,
+    PatchUserDto, SearchUserDto, SignupDto, UpdateUserDto, UserDto, UserListDto, UserLookupDto,
+    VerifyEmailDto,
+};
 pub use infra::postgres_service::UserService as UserServiceImpl;